@@ -46,6 +46,8 @@ fn vs_main(in: VertexInput) -> VertexOutput {
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let coverage = textureSample(font_atlas, default_sampler, in.tex_coord).r;
-    return in.color * coverage;
+    // Mask glyphs are stored as white with coverage in alpha, while color glyphs are stored as
+    // is, so both are resolved by tinting the sample with the vertex color
+    let glyph = textureSample(font_atlas, default_sampler, in.tex_coord);
+    return in.color * glyph;
 }
@@ -0,0 +1,224 @@
+use std::{collections::BTreeMap, fmt::Display, sync::Arc};
+
+use parking_lot::Mutex;
+use winit::{
+    event::ElementState,
+    keyboard::{Key, ModifiersState},
+};
+
+use crate::{input::KeyboardInput, ScopeRef};
+
+/// A key combination which invokes a command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shortcut {
+    pub modifiers: ModifiersState,
+    pub key: Key,
+}
+
+impl Shortcut {
+    pub fn new(modifiers: ModifiersState, key: Key) -> Self {
+        Self { modifiers, key }
+    }
+
+    /// Shortcut for a character key, such as `Ctrl+S`
+    pub fn character(modifiers: ModifiersState, key: &str) -> Self {
+        Self::new(modifiers, Key::Character(key.to_lowercase().into()))
+    }
+
+    pub fn matches(&self, input: &KeyboardInput) -> bool {
+        if input.state != ElementState::Pressed || input.modifiers != self.modifiers {
+            return false;
+        }
+
+        match (&self.key, &input.key) {
+            (Key::Character(a), Key::Character(b)) => a.to_lowercase() == b.to_lowercase(),
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl Display for Shortcut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let modifiers = [
+            (ModifiersState::CONTROL, "Ctrl"),
+            (ModifiersState::ALT, "Alt"),
+            (ModifiersState::SHIFT, "Shift"),
+            (ModifiersState::SUPER, "Super"),
+        ];
+
+        for (modifier, name) in modifiers {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+
+        match &self.key {
+            Key::Character(c) => write!(f, "{}", c.to_uppercase()),
+            Key::Named(key) => write!(f, "{key:?}"),
+            key => write!(f, "{key:?}"),
+        }
+    }
+}
+
+type CommandAction = Arc<dyn Send + Sync + Fn(&ScopeRef<'_>)>;
+
+/// An action which can be invoked from menus or using a keyboard shortcut
+#[derive(Clone)]
+pub struct Command {
+    name: String,
+    shortcut: Option<Shortcut>,
+    action: CommandAction,
+}
+
+impl Command {
+    pub fn new(
+        name: impl Into<String>,
+        action: impl 'static + Send + Sync + Fn(&ScopeRef<'_>),
+    ) -> Self {
+        Self {
+            name: name.into(),
+            shortcut: None,
+            action: Arc::new(action),
+        }
+    }
+
+    pub fn with_shortcut(mut self, shortcut: Shortcut) -> Self {
+        self.shortcut = Some(shortcut);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn shortcut(&self) -> Option<&Shortcut> {
+        self.shortcut.as_ref()
+    }
+
+    pub fn invoke(&self, scope: &ScopeRef<'_>) {
+        (self.action)(scope)
+    }
+}
+
+/// Shared set of named commands.
+///
+/// Menus use the registry to show the name and shortcut of a command, and [`Self::dispatch`]
+/// invokes the command matching a shortcut.
+#[derive(Default, Clone)]
+pub struct CommandRegistry {
+    commands: Arc<Mutex<BTreeMap<String, Command>>>,
+}
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a command, replacing any existing command with the same id
+    pub fn register(&self, id: impl Into<String>, command: Command) {
+        self.commands.lock().insert(id.into(), command);
+    }
+
+    pub fn get(&self, id: &str) -> Option<Command> {
+        self.commands.lock().get(id).cloned()
+    }
+
+    /// Invokes the command whose shortcut matches the input.
+    ///
+    /// Returns true if a command was invoked.
+    pub fn dispatch(&self, scope: &ScopeRef<'_>, input: &KeyboardInput) -> bool {
+        let command = self
+            .commands
+            .lock()
+            .values()
+            .find(|v| v.shortcut.as_ref().is_some_and(|v| v.matches(input)))
+            .cloned();
+
+        if let Some(command) = command {
+            command.invoke(scope);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use flax::{Entity, World};
+    use winit::keyboard::NamedKey;
+
+    use super::*;
+    use crate::{assets::AssetCache, executor::Executor, Frame};
+
+    fn press(modifiers: ModifiersState, key: &str) -> KeyboardInput {
+        KeyboardInput {
+            modifiers,
+            state: ElementState::Pressed,
+            key: Key::Character(key.into()),
+            text: None,
+        }
+    }
+
+    #[test]
+    fn shortcut_labels() {
+        assert_eq!(
+            Shortcut::character(ModifiersState::CONTROL, "s").to_string(),
+            "Ctrl+S"
+        );
+        assert_eq!(
+            Shortcut::character(ModifiersState::CONTROL | ModifiersState::SHIFT, "z").to_string(),
+            "Ctrl+Shift+Z"
+        );
+        assert_eq!(
+            Shortcut::new(ModifiersState::ALT, Key::Named(NamedKey::F4)).to_string(),
+            "Alt+F4"
+        );
+    }
+
+    #[test]
+    fn match_modifiers() {
+        let save = Shortcut::character(ModifiersState::CONTROL, "S");
+
+        assert!(save.matches(&press(ModifiersState::CONTROL, "s")));
+        assert!(save.matches(&press(ModifiersState::CONTROL, "S")));
+        assert!(!save.matches(&press(ModifiersState::empty(), "s")));
+        assert!(!save.matches(&press(ModifiersState::ALT, "s")));
+        assert!(!save.matches(&press(ModifiersState::CONTROL | ModifiersState::SHIFT, "s")));
+
+        let released = KeyboardInput {
+            state: ElementState::Released,
+            ..press(ModifiersState::CONTROL, "s")
+        };
+        assert!(!save.matches(&released));
+    }
+
+    #[test]
+    fn dispatch() {
+        let executor = Executor::new();
+        let mut frame = Frame::new(executor.spawner(), AssetCache::new(), World::new());
+        let id = Entity::builder().spawn(frame.world_mut());
+
+        let saved = Arc::new(AtomicUsize::new(0));
+        let commands = CommandRegistry::new();
+        commands.register(
+            "save",
+            Command::new("Save", {
+                let saved = saved.clone();
+                move |_| {
+                    saved.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .with_shortcut(Shortcut::character(ModifiersState::CONTROL, "s")),
+        );
+        commands.register("open", Command::new("Open", |_| {}));
+
+        let scope = ScopeRef::new(&frame, frame.world().entity(id).unwrap());
+        assert!(commands.dispatch(&scope, &press(ModifiersState::CONTROL, "s")));
+        assert!(!commands.dispatch(&scope, &press(ModifiersState::CONTROL, "o")));
+        assert!(!commands.dispatch(&scope, &press(ModifiersState::empty(), "s")));
+        assert_eq!(saved.load(Ordering::Relaxed), 1);
+    }
+}
@@ -9,7 +9,7 @@ use crate::{
     assets::Asset,
     layout::{Align, Layout, LayoutArgs, SizeResolver},
     stored::UntypedHandle,
-    text::{LayoutGlyphs, TextSegment, Wrap},
+    text::{LayoutGlyphs, TextOverflow, TextSegment, Wrap},
     unit::Unit,
     Edges, Frame, Rect, Scope,
 };
@@ -187,7 +187,13 @@ component! {
 
     pub text: Vec<TextSegment> => [ ],
     pub text_wrap: Wrap => [ Debuggable ],
+    /// Shortens text which does not fit
+    pub text_overflow: TextOverflow => [ Debuggable ],
+    /// Limits the number of laid out lines of the text
+    pub text_max_lines: usize => [ Debuggable ],
     pub font_size: f32 => [ Debuggable ],
+    /// Distance between lines of text, in multiples of the font size
+    pub line_height: f32 => [ Debuggable ],
 
     /// To retain consistent text wrapping between size query and the snug fitted rect the bounds
     /// of the size query are stored and used instead of the snug-fitted rect which will cause a
@@ -0,0 +1,188 @@
+use std::{borrow::Cow, fmt::Display};
+
+use ropey::{Rope, RopeSlice};
+
+use crate::text::CursorLocation;
+
+/// Rope backed text storage for the editor.
+///
+/// Edits and line lookups are `O(log n)` in the size of the text, which keeps editing large
+/// documents responsive.
+///
+/// Locations are given as a row and a byte offset within the row, excluding the line break.
+#[derive(Default, Debug, Clone)]
+pub struct TextBuffer {
+    rope: Rope,
+}
+
+impl TextBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of lines in the buffer.
+    ///
+    /// This is always at least one, as an empty buffer consists of a single empty line.
+    pub fn len_lines(&self) -> usize {
+        self.rope.len_lines()
+    }
+
+    pub fn len_bytes(&self) -> usize {
+        self.rope.len_bytes()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rope.len_bytes() == 0
+    }
+
+    /// Returns the line at `row`, excluding the line break
+    pub fn line_slice(&self, row: usize) -> RopeSlice<'_> {
+        let line = self.rope.line(row);
+        let len = line.len_chars();
+
+        if len > 0 && is_line_break(line.char(len - 1)) {
+            line.slice(..len - 1)
+        } else {
+            line
+        }
+    }
+
+    /// Returns the line at `row`, excluding the line break.
+    ///
+    /// The line is only copied if it is not stored contiguously in the rope.
+    pub fn line(&self, row: usize) -> Cow<'_, str> {
+        self.line_slice(row).into()
+    }
+
+    /// Returns the length of the line at `row` in bytes, excluding the line break
+    pub fn line_len(&self, row: usize) -> usize {
+        self.line_slice(row).len_bytes()
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = Cow<'_, str>> {
+        (0..self.len_lines()).map(|row| self.line(row))
+    }
+
+    /// Location of the end of the text
+    pub fn end(&self) -> CursorLocation {
+        let row = self.len_lines() - 1;
+        CursorLocation::new(row, self.line_len(row))
+    }
+
+    /// Returns the location clamped to the existing text
+    pub fn clamp(&self, loc: CursorLocation) -> CursorLocation {
+        let row = loc.row.min(self.len_lines() - 1);
+        CursorLocation::new(row, loc.col.min(self.line_len(row)))
+    }
+
+    /// Converts a location to a byte offset into the whole text
+    pub fn to_byte(&self, loc: CursorLocation) -> usize {
+        let loc = self.clamp(loc);
+        self.rope.line_to_byte(loc.row) + loc.col
+    }
+
+    /// Converts a byte offset into the whole text to a location
+    pub fn to_location(&self, byte: usize) -> CursorLocation {
+        let byte = byte.min(self.len_bytes());
+        let row = self.rope.byte_to_line(byte);
+        CursorLocation::new(row, byte - self.rope.line_to_byte(row))
+    }
+
+    /// Returns the text between two locations
+    pub fn slice(&self, start: CursorLocation, end: CursorLocation) -> Cow<'_, str> {
+        self.byte_slice(self.to_byte(start), self.to_byte(end))
+    }
+
+    pub fn byte_slice(&self, start: usize, end: usize) -> Cow<'_, str> {
+        self.rope.byte_slice(start..end).into()
+    }
+
+    /// Inserts text at the given byte offset.
+    ///
+    /// Line breaks are normalized to `\n`.
+    pub fn insert(&mut self, byte: usize, text: &str) {
+        let idx = self.rope.byte_to_char(byte);
+        self.rope.insert(idx, &normalize_line_breaks(text));
+    }
+
+    /// Removes the text within the given byte range
+    pub fn remove(&mut self, start: usize, end: usize) {
+        let start = self.rope.byte_to_char(start);
+        let end = self.rope.byte_to_char(end);
+        self.rope.remove(start..end);
+    }
+
+    pub fn rope(&self) -> &Rope {
+        &self.rope
+    }
+}
+
+impl From<&str> for TextBuffer {
+    fn from(text: &str) -> Self {
+        Self {
+            rope: Rope::from_str(&normalize_line_breaks(text)),
+        }
+    }
+}
+
+impl Display for TextBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for chunk in self.rope.chunks() {
+            f.write_str(chunk)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares the text chunk by chunk, without copying it
+impl PartialEq<str> for TextBuffer {
+    fn eq(&self, other: &str) -> bool {
+        self.rope == other
+    }
+}
+
+/// Converts `\r\n` and lone `\r` to `\n`
+pub(crate) fn normalize_line_breaks(text: &str) -> Cow<'_, str> {
+    if text.contains('\r') {
+        text.replace("\r\n", "\n").replace('\r', "\n").into()
+    } else {
+        text.into()
+    }
+}
+
+/// Characters which ropey treats as line breaks
+fn is_line_break(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\u{000B}' | '\u{000C}' | '\r' | '\u{0085}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations() {
+        let mut buffer = TextBuffer::from("Hello\r\nwörld\n");
+        assert_eq!(buffer.len_lines(), 3);
+        assert_eq!(buffer.lines().collect::<Vec<_>>(), ["Hello", "wörld", ""]);
+
+        let loc = CursorLocation::new(1, "wö".len());
+        assert_eq!(buffer.to_byte(loc), "Hello\nwö".len());
+        assert_eq!(buffer.to_location(buffer.to_byte(loc)), loc);
+        assert_eq!(buffer.end(), CursorLocation::new(2, 0));
+
+        buffer.insert(buffer.to_byte(loc), "r\nw");
+        assert_eq!(buffer.to_string(), "Hello\nwör\nwrld\n");
+
+        buffer.remove(
+            buffer.to_byte(CursorLocation::new(0, 5)),
+            buffer.to_byte(CursorLocation::new(2, 1)),
+        );
+        assert_eq!(buffer.to_string(), "Hellorld\n");
+        assert!(buffer == *"Hellorld\n");
+        assert!(buffer != *"Hellorld");
+    }
+}
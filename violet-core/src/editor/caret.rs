@@ -0,0 +1,77 @@
+use crate::text::CursorLocation;
+
+/// A cursor along with the anchor of its selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Caret {
+    pub cursor: CursorLocation,
+    /// The other end of the selection, if any
+    pub selection: Option<CursorLocation>,
+}
+
+impl Caret {
+    pub fn new(cursor: CursorLocation) -> Self {
+        Self {
+            cursor,
+            selection: None,
+        }
+    }
+
+    pub fn with_selection(mut self, selection: CursorLocation) -> Self {
+        self.selection = Some(selection);
+        self
+    }
+
+    /// Returns the start and end of the selection, or the cursor if nothing is selected
+    pub fn bounds(&self) -> (CursorLocation, CursorLocation) {
+        self.selection_bounds()
+            .unwrap_or((self.cursor, self.cursor))
+    }
+
+    pub fn selection_bounds(&self) -> Option<(CursorLocation, CursorLocation)> {
+        let sel = self.selection?;
+        if sel < self.cursor {
+            Some((sel, self.cursor))
+        } else {
+            Some((self.cursor, sel))
+        }
+    }
+}
+
+/// Sorts the carets and merges the ones which overlap.
+///
+/// The flag marks the primary caret and is kept by the caret it is merged into.
+pub(crate) fn merge_carets(mut carets: Vec<(Caret, bool)>) -> Vec<(Caret, bool)> {
+    carets.sort_by_key(|(v, _)| v.bounds());
+
+    let mut merged: Vec<(Caret, bool)> = Vec::with_capacity(carets.len());
+    for (caret, primary) in carets {
+        if let Some((last, last_primary)) = merged.last_mut() {
+            let (last_start, last_end) = last.bounds();
+            let (start, end) = caret.bounds();
+
+            // Touching carets are merged if one of them is empty
+            let overlaps =
+                start < last_end || (start == last_end && (start == end || last_start == last_end));
+
+            if overlaps {
+                let end = end.max(last_end);
+                let forward = !last.selection.is_some_and(|v| v > last.cursor);
+
+                *last = if last_start == end {
+                    Caret::new(end)
+                } else if forward {
+                    Caret::new(end).with_selection(last_start)
+                } else {
+                    Caret::new(last_start).with_selection(end)
+                };
+
+                *last_primary |= primary;
+                continue;
+            }
+        }
+
+        merged.push((caret, primary));
+    }
+
+    merged
+}
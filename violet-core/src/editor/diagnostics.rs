@@ -0,0 +1,149 @@
+use super::{EditOp, TextEditorCore};
+use crate::text::CursorLocation;
+
+/// How severe a diagnostic is, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A message about a range of the text, such as a syntax error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub start: CursorLocation,
+    pub end: CursorLocation,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(
+        start: CursorLocation,
+        end: CursorLocation,
+        severity: Severity,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            start,
+            end,
+            severity,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(start: CursorLocation, end: CursorLocation, message: impl Into<String>) -> Self {
+        Self::new(start, end, Severity::Error, message)
+    }
+
+    pub fn warning(start: CursorLocation, end: CursorLocation, message: impl Into<String>) -> Self {
+        Self::new(start, end, Severity::Warning, message)
+    }
+
+    pub fn info(start: CursorLocation, end: CursorLocation, message: impl Into<String>) -> Self {
+        Self::new(start, end, Severity::Info, message)
+    }
+}
+
+impl TextEditorCore {
+    /// Replaces the diagnostics attached to the text.
+    ///
+    /// Diagnostics follow the text they refer to as it is edited, until they are replaced.
+    pub fn set_diagnostics(&mut self, diagnostics: impl IntoIterator<Item = Diagnostic>) {
+        self.diagnostics = diagnostics
+            .into_iter()
+            .map(|v| {
+                let start = self.text.to_byte(self.text.clamp(v.start));
+                let end = self.text.to_byte(self.text.clamp(v.end));
+                (start.min(end)..start.max(end), v)
+            })
+            .collect();
+
+        self.diagnostics.sort_by_key(|(range, _)| range.start);
+    }
+
+    pub fn clear_diagnostics(&mut self) {
+        self.diagnostics.clear();
+    }
+
+    /// Returns the diagnostics in the order of the text, at their current locations
+    pub fn diagnostics(&self) -> impl Iterator<Item = Diagnostic> + '_ {
+        self.diagnostics.iter().map(|(range, v)| Diagnostic {
+            start: self.text.to_location(range.start),
+            end: self.text.to_location(range.end),
+            ..v.clone()
+        })
+    }
+
+    /// Moves the diagnostics along with the text affected by an edit
+    pub(super) fn shift_diagnostics(&mut self, op: &EditOp) {
+        for (range, _) in &mut self.diagnostics {
+            *range = op.shift(range.start)..op.shift(range.end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::editor::EditAction;
+
+    #[test]
+    fn follow_edits() {
+        let mut editor = TextEditorCore::new(|_, _| {});
+        editor.set_text(["let x = 1 +", "x * y"]);
+        editor.set_diagnostics([
+            Diagnostic::error(
+                CursorLocation::new(0, 10),
+                CursorLocation::new(0, 11),
+                "missing operand",
+            ),
+            Diagnostic::warning(
+                CursorLocation::new(1, 4),
+                CursorLocation::new(1, 5),
+                "unknown variable `y`",
+            ),
+        ]);
+
+        editor.set_cursor(0, 0);
+        editor.edit(EditAction::<String>::InsertLine);
+
+        let locations = |editor: &TextEditorCore| {
+            editor
+                .diagnostics()
+                .map(|v| (v.start, v.end, v.severity))
+                .collect_vec()
+        };
+
+        assert_eq!(
+            locations(&editor),
+            [
+                (
+                    CursorLocation::new(1, 10),
+                    CursorLocation::new(1, 11),
+                    Severity::Error
+                ),
+                (
+                    CursorLocation::new(2, 4),
+                    CursorLocation::new(2, 5),
+                    Severity::Warning
+                ),
+            ]
+        );
+
+        // Deleting the text of a diagnostic leaves it empty
+        editor.set_cursor(2, 5);
+        editor.edit(EditAction::<String>::DeleteBackwardChar);
+        assert_eq!(
+            locations(&editor)[1],
+            (
+                CursorLocation::new(2, 4),
+                CursorLocation::new(2, 4),
+                Severity::Warning
+            )
+        );
+    }
+}
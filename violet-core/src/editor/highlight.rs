@@ -0,0 +1,578 @@
+use std::{iter, ops::Range, sync::Arc};
+
+use palette::Srgba;
+
+use super::{TextBuffer, TextChange};
+use crate::{
+    style::{base_colors::*, ValueOrRef},
+    text::{Style, TextSegment, Weight},
+};
+
+/// State carried from the end of one line to the start of the next, such as being inside a
+/// multi-line string.
+///
+/// The meaning of the value is up to each highlighter.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HighlightState(pub u32);
+
+/// Produces styled segments for the lines of a text
+pub trait Highlighter: Send + Sync {
+    /// Highlights a line starting in the given state.
+    ///
+    /// The text of the returned segments must add up to the line. Returns the state at the end of
+    /// the line.
+    fn highlight_line(
+        &self,
+        line: &str,
+        state: HighlightState,
+    ) -> (Vec<TextSegment>, HighlightState);
+}
+
+/// Tracks the highlighting state of each line of a text, updated incrementally as the text
+/// changes.
+///
+/// Only the state at the end of each line is kept; segments are produced on demand so that long
+/// texts stay cheap to highlight.
+pub struct HighlightCache {
+    highlighter: Arc<dyn Highlighter>,
+    /// State at the end of each line
+    states: Vec<HighlightState>,
+}
+
+impl HighlightCache {
+    /// Creates a cache for an empty text
+    pub fn new(highlighter: Arc<dyn Highlighter>) -> Self {
+        let (_, state) = highlighter.highlight_line("", HighlightState::default());
+        Self {
+            highlighter,
+            states: vec![state],
+        }
+    }
+
+    /// Updates the line states after a change to the text.
+    ///
+    /// Returns the rows following the change whose highlighting changed as a consequence, such as
+    /// when a multi-line string is opened or closed.
+    pub fn apply_change(&mut self, text: &TextBuffer, change: &TextChange) -> Range<usize> {
+        // The stored state at the end of the last changed row is what the following rows
+        // previously started in, and is compared against to stop rehighlighting
+        let (start, end) = match change {
+            TextChange::Insert(start, end) => {
+                let at = (start.row + 1).min(self.states.len());
+                let old = self.states.get(start.row).copied().unwrap_or_default();
+                self.states.splice(
+                    at..at,
+                    iter::repeat(HighlightState::default()).take(end.row - start.row),
+                );
+
+                if let Some(state) = self.states.get_mut(end.row) {
+                    *state = old;
+                }
+
+                (start.row, end.row)
+            }
+            TextChange::Delete(start, end) => {
+                let len = self.states.len();
+                let old = self.states.get(end.row.min(len - 1)).copied();
+                self.states
+                    .drain((start.row + 1).min(len)..(end.row + 1).min(len));
+
+                if let (Some(state), Some(old)) = (self.states.get_mut(start.row), old) {
+                    *state = old;
+                }
+
+                (start.row, start.row)
+            }
+        };
+
+        let mut row = start;
+        let mut changed = true;
+        while row < self.states.len().min(text.len_lines()) && (row <= end || changed) {
+            let (_, state) = self
+                .highlighter
+                .highlight_line(&text.line(row), self.state(row));
+
+            changed = self.states[row] != state;
+            self.states[row] = state;
+            row += 1;
+        }
+
+        (end + 1).min(row)..row
+    }
+
+    /// Returns the state at the start of a row
+    pub fn state(&self, row: usize) -> HighlightState {
+        row.checked_sub(1)
+            .and_then(|v| self.states.get(v))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the highlighted segments of a row
+    pub fn line(&self, text: &TextBuffer, row: usize) -> Vec<TextSegment> {
+        self.highlighter
+            .highlight_line(&text.line(row), self.state(row))
+            .0
+    }
+}
+
+/// Kind of a token recognized by the built-in highlighters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Key,
+    String,
+    Number,
+    Keyword,
+    Comment,
+    Punctuation,
+    /// Table header, such as `[package]`
+    Header,
+}
+
+/// Colors used by the built-in highlighters
+#[derive(Debug, Clone)]
+pub struct HighlightTheme {
+    pub key: ValueOrRef<Srgba>,
+    pub string: ValueOrRef<Srgba>,
+    pub number: ValueOrRef<Srgba>,
+    pub keyword: ValueOrRef<Srgba>,
+    pub comment: ValueOrRef<Srgba>,
+    pub punctuation: ValueOrRef<Srgba>,
+    pub header: ValueOrRef<Srgba>,
+}
+
+impl Default for HighlightTheme {
+    fn default() -> Self {
+        Self {
+            key: SAPPHIRE_400.into(),
+            string: FOREST_400.into(),
+            number: AMBER_400.into(),
+            keyword: AMETHYST_400.into(),
+            comment: STONE_400.into(),
+            punctuation: ZINC_400.into(),
+            header: TEAL_400.into(),
+        }
+    }
+}
+
+impl HighlightTheme {
+    /// Returns a segment styled for the given kind of token
+    pub fn segment(&self, text: impl Into<String>, kind: Option<TokenKind>) -> TextSegment {
+        let segment = TextSegment::new(text);
+        match kind {
+            None => segment,
+            Some(TokenKind::Key) => segment.with_color(self.key),
+            Some(TokenKind::String) => segment.with_color(self.string),
+            Some(TokenKind::Number) => segment.with_color(self.number),
+            Some(TokenKind::Keyword) => segment.with_color(self.keyword),
+            Some(TokenKind::Comment) => segment.with_color(self.comment).with_style(Style::Italic),
+            Some(TokenKind::Punctuation) => segment.with_color(self.punctuation),
+            Some(TokenKind::Header) => segment.with_color(self.header).with_weight(Weight::BOLD),
+        }
+    }
+
+    /// Converts the tokens of a line to segments, leaving the text between them unstyled
+    pub fn segments(&self, line: &str, tokens: &[(Range<usize>, TokenKind)]) -> Vec<TextSegment> {
+        let mut segments = Vec::new();
+        let mut pos = 0;
+
+        for (range, kind) in tokens {
+            if range.start > pos {
+                segments.push(self.segment(&line[pos..range.start], None));
+            }
+
+            segments.push(self.segment(&line[range.clone()], Some(*kind)));
+            pos = range.end;
+        }
+
+        if pos < line.len() || segments.is_empty() {
+            segments.push(self.segment(&line[pos..], None));
+        }
+
+        segments
+    }
+}
+
+/// Highlighter for JSON, including `//` comments
+#[derive(Default, Debug, Clone)]
+pub struct JsonHighlighter {
+    theme: HighlightTheme,
+}
+
+impl JsonHighlighter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_theme(mut self, theme: HighlightTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl Highlighter for JsonHighlighter {
+    fn highlight_line(
+        &self,
+        line: &str,
+        state: HighlightState,
+    ) -> (Vec<TextSegment>, HighlightState) {
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        while let Some(c) = line[i..].chars().next() {
+            let (end, kind) = match c {
+                '/' if line[i..].starts_with("//") => (line.len(), Some(TokenKind::Comment)),
+                '"' => {
+                    let end = string_end(line, i, '"', true).unwrap_or(line.len());
+                    (end, Some(key_or(line, end, ':', TokenKind::String)))
+                }
+                '{' | '}' | '[' | ']' | ',' | ':' => (i + 1, Some(TokenKind::Punctuation)),
+                '-' | '0'..='9' => (
+                    scan(line, i, |c| {
+                        c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-')
+                    }),
+                    Some(TokenKind::Number),
+                ),
+                c if c.is_ascii_alphabetic() => {
+                    let end = scan(line, i, |c| c.is_ascii_alphanumeric());
+                    let kind = matches!(&line[i..end], "true" | "false" | "null")
+                        .then_some(TokenKind::Keyword);
+                    (end, kind)
+                }
+                c => (i + c.len_utf8(), None),
+            };
+
+            if let Some(kind) = kind {
+                tokens.push((i..end, kind));
+            }
+
+            i = end;
+        }
+
+        (self.theme.segments(line, &tokens), state)
+    }
+}
+
+/// Highlighter for TOML
+#[derive(Default, Debug, Clone)]
+pub struct TomlHighlighter {
+    theme: HighlightTheme,
+}
+
+impl TomlHighlighter {
+    /// Inside a multi-line basic string, `"""`
+    const BASIC_STRING: HighlightState = HighlightState(1);
+    /// Inside a multi-line literal string, `'''`
+    const LITERAL_STRING: HighlightState = HighlightState(2);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_theme(mut self, theme: HighlightTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl Highlighter for TomlHighlighter {
+    fn highlight_line(
+        &self,
+        line: &str,
+        mut state: HighlightState,
+    ) -> (Vec<TextSegment>, HighlightState) {
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        // Continue a multi-line string from a previous line
+        let open = match state {
+            Self::BASIC_STRING => Some(("\"\"\"", true)),
+            Self::LITERAL_STRING => Some(("'''", false)),
+            _ => None,
+        };
+
+        if let Some((delim, escapes)) = open {
+            let Some(end) = multiline_string_end(line, 0, delim, escapes) else {
+                tokens.push((0..line.len(), TokenKind::String));
+                return (self.theme.segments(line, &tokens), state);
+            };
+
+            tokens.push((0..end, TokenKind::String));
+            state = HighlightState::default();
+            i = end;
+        }
+
+        let indent = line.len() - line.trim_start().len();
+        if i == 0 && line[indent..].starts_with('[') {
+            let close = if line[indent..].starts_with("[[") {
+                "]]"
+            } else {
+                "]"
+            };
+
+            let end = line[indent..]
+                .find(close)
+                .map_or(line.len(), |v| indent + v + close.len());
+
+            tokens.push((indent..end, TokenKind::Header));
+            i = end;
+        }
+
+        while let Some(c) = line[i..].chars().next() {
+            let rest = &line[i..];
+            let (end, kind) = match c {
+                '#' => (line.len(), Some(TokenKind::Comment)),
+                '"' | '\'' if rest.starts_with("\"\"\"") || rest.starts_with("'''") => {
+                    let escapes = c == '"';
+                    match multiline_string_end(line, i + 3, &rest[..3], escapes) {
+                        Some(end) => (end, Some(TokenKind::String)),
+                        None => {
+                            state = if escapes {
+                                Self::BASIC_STRING
+                            } else {
+                                Self::LITERAL_STRING
+                            };
+
+                            (line.len(), Some(TokenKind::String))
+                        }
+                    }
+                }
+                '"' | '\'' => {
+                    let end = string_end(line, i, c, c == '"').unwrap_or(line.len());
+                    (end, Some(key_or(line, end, '=', TokenKind::String)))
+                }
+                '0'..='9' | '+' | '-' if rest[1..].starts_with(|c: char| c.is_ascii_digit()) => (
+                    scan(line, i + 1, |c| {
+                        c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | ':' | '-' | '+')
+                    }),
+                    Some(TokenKind::Number),
+                ),
+                '0'..='9' => (i + 1, Some(TokenKind::Number)),
+                c if c.is_ascii_alphabetic() || c == '_' || c == '-' => {
+                    let end = scan(line, i, |c| {
+                        c.is_ascii_alphanumeric() || matches!(c, '_' | '-')
+                    });
+                    let kind = if matches!(&line[i..end], "true" | "false" | "inf" | "nan") {
+                        Some(TokenKind::Keyword)
+                    } else {
+                        let kind = key_or(line, end, '=', TokenKind::String);
+                        (kind == TokenKind::Key).then_some(kind)
+                    };
+
+                    (end, kind)
+                }
+                '=' | ',' | '.' | '[' | ']' | '{' | '}' => (i + 1, Some(TokenKind::Punctuation)),
+                c => (i + c.len_utf8(), None),
+            };
+
+            if let Some(kind) = kind {
+                tokens.push((i..end, kind));
+            }
+
+            i = end;
+        }
+
+        (self.theme.segments(line, &tokens), state)
+    }
+}
+
+/// Returns the end of the character run starting at `start` matching `pred`
+fn scan(line: &str, start: usize, pred: impl Fn(char) -> bool) -> usize {
+    line[start..]
+        .find(|c| !pred(c))
+        .map_or(line.len(), |v| start + v)
+}
+
+/// Returns the end of the quoted string starting at `start`, or `None` if the string is not
+/// terminated on the line
+fn string_end(line: &str, start: usize, quote: char, escapes: bool) -> Option<usize> {
+    let mut chars = line[start + 1..].char_indices();
+    while let Some((i, c)) = chars.next() {
+        if escapes && c == '\\' {
+            chars.next();
+        } else if c == quote {
+            return Some(start + 1 + i + 1);
+        }
+    }
+
+    None
+}
+
+/// Returns the end of the closing delimiter of a multi-line string, searching from `start`
+fn multiline_string_end(line: &str, start: usize, delim: &str, escapes: bool) -> Option<usize> {
+    let mut chars = line[start..].char_indices();
+    while let Some((i, c)) = chars.next() {
+        if escapes && c == '\\' {
+            chars.next();
+        } else if line[start + i..].starts_with(delim) {
+            return Some(start + i + delim.len());
+        }
+    }
+
+    None
+}
+
+/// A token followed by `separator` (or a `.` of a dotted key) is a key
+fn key_or(line: &str, end: usize, separator: char, kind: TokenKind) -> TokenKind {
+    let rest = line[end..].trim_start();
+    if rest.starts_with(separator) || (separator == '=' && rest.starts_with('.')) {
+        TokenKind::Key
+    } else {
+        kind
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::text::CursorLocation;
+
+    fn kinds(segments: &[TextSegment], theme: &HighlightTheme) -> Vec<(String, Option<TokenKind>)> {
+        let kinds = [
+            TokenKind::Key,
+            TokenKind::String,
+            TokenKind::Number,
+            TokenKind::Keyword,
+            TokenKind::Comment,
+            TokenKind::Punctuation,
+            TokenKind::Header,
+        ];
+
+        segments
+            .iter()
+            .map(|segment| {
+                let kind = kinds
+                    .iter()
+                    .find(|&&kind| theme.segment("", Some(kind)).color == segment.color)
+                    .copied();
+
+                (segment.text.clone(), kind)
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn json() {
+        let theme = HighlightTheme::default();
+        let (segments, _) = JsonHighlighter::new().highlight_line(
+            r#"  "key": ["a\"b", -1.5e3, null] // note"#,
+            Default::default(),
+        );
+
+        use TokenKind::*;
+        assert_eq!(
+            kinds(&segments, &theme),
+            [
+                ("  ".into(), None),
+                (r#""key""#.into(), Some(Key)),
+                (":".into(), Some(Punctuation)),
+                (" ".into(), None),
+                ("[".into(), Some(Punctuation)),
+                (r#""a\"b""#.into(), Some(String)),
+                (",".into(), Some(Punctuation)),
+                (" ".into(), None),
+                ("-1.5e3".into(), Some(Number)),
+                (",".into(), Some(Punctuation)),
+                (" ".into(), None),
+                ("null".into(), Some(Keyword)),
+                ("]".into(), Some(Punctuation)),
+                (" ".into(), None),
+                ("// note".into(), Some(Comment)),
+            ]
+        );
+    }
+
+    #[test]
+    fn toml() {
+        let theme = HighlightTheme::default();
+        let highlighter = TomlHighlighter::new();
+
+        use TokenKind::*;
+        let (segments, state) = highlighter.highlight_line("[package]", Default::default());
+        assert_eq!(
+            kinds(&segments, &theme),
+            [("[package]".into(), Some(Header))]
+        );
+        assert_eq!(state, HighlightState::default());
+
+        let (segments, state) = highlighter.highlight_line("a.b = true # c", Default::default());
+        assert_eq!(
+            kinds(&segments, &theme),
+            [
+                ("a".into(), Some(Key)),
+                (".".into(), Some(Punctuation)),
+                ("b".into(), Some(Key)),
+                (" ".into(), None),
+                ("=".into(), Some(Punctuation)),
+                (" ".into(), None),
+                ("true".into(), Some(Keyword)),
+                (" ".into(), None),
+                ("# c".into(), Some(Comment)),
+            ]
+        );
+        assert_eq!(state, HighlightState::default());
+
+        let (_, state) = highlighter.highlight_line(r#"s = """first"#, Default::default());
+        assert_eq!(state, TomlHighlighter::BASIC_STRING);
+
+        let (segments, state) = highlighter.highlight_line(r#"end""" # x"#, state);
+        assert_eq!(
+            kinds(&segments, &theme),
+            [
+                (r#"end""""#.into(), Some(String)),
+                (" ".into(), None),
+                ("# x".into(), Some(Comment)),
+            ]
+        );
+        assert_eq!(state, HighlightState::default());
+    }
+
+    #[test]
+    fn incremental() {
+        let mut text = TextBuffer::from("a = 1\nb = 2\nc = 3");
+        let mut cache = HighlightCache::new(Arc::new(TomlHighlighter::new()));
+
+        cache.apply_change(
+            &text,
+            &TextChange::Insert(CursorLocation::new(0, 0), CursorLocation::new(2, 5)),
+        );
+
+        assert!((0..3).all(|row| cache.state(row) == HighlightState::default()));
+
+        // Opening a multi-line string changes the highlighting of the following rows
+        text.insert(4, "'''");
+        let restyled = cache.apply_change(
+            &text,
+            &TextChange::Insert(CursorLocation::new(0, 4), CursorLocation::new(0, 7)),
+        );
+
+        assert_eq!(restyled, 1..3);
+        assert_eq!(cache.state(1), TomlHighlighter::LITERAL_STRING);
+        assert_eq!(cache.state(2), TomlHighlighter::LITERAL_STRING);
+
+        // Closing it on the next row only affects the rows up to where the states agree again
+        text.insert(text.to_byte(CursorLocation::new(1, 0)), "'''");
+        let restyled = cache.apply_change(
+            &text,
+            &TextChange::Insert(CursorLocation::new(1, 0), CursorLocation::new(1, 3)),
+        );
+
+        assert_eq!(restyled, 2..3);
+        assert_eq!(cache.state(2), HighlightState::default());
+
+        // Removing a row keeps the following states aligned
+        let start = text.to_byte(CursorLocation::new(0, 8));
+        let end = text.to_byte(CursorLocation::new(1, text.line_len(1)));
+        text.remove(start, end);
+        let restyled = cache.apply_change(
+            &text,
+            &TextChange::Delete(CursorLocation::new(0, 8), CursorLocation::new(1, 8)),
+        );
+
+        assert_eq!(text.to_string(), "a = '''1\nc = 3");
+        assert_eq!(restyled, 1..2);
+        assert_eq!(cache.state(1), TomlHighlighter::LITERAL_STRING);
+    }
+}
@@ -0,0 +1,1430 @@
+mod buffer;
+mod caret;
+mod diagnostics;
+mod highlight;
+mod search;
+
+use std::{collections::VecDeque, iter, mem, ops::Range};
+
+use itertools::Itertools;
+use unicode_segmentation::UnicodeSegmentation;
+
+pub use buffer::TextBuffer;
+pub use caret::Caret;
+pub use diagnostics::{Diagnostic, Severity};
+pub use highlight::{
+    HighlightCache, HighlightState, HighlightTheme, Highlighter, JsonHighlighter, TokenKind,
+    TomlHighlighter,
+};
+pub use search::SearchQuery;
+
+use crate::text::CursorLocation;
+
+/// Incremental change to the text of an editor.
+///
+/// Changes are sent as they are made, and are relative to the text after any previous change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextChange {
+    /// Text was inserted at the first location and now ends at the second.
+    ///
+    /// The rows following the first row up to and including the last row are newly inserted
+    /// lines.
+    Insert(CursorLocation, CursorLocation),
+    /// Text between the two locations was deleted.
+    ///
+    /// The rows following the first row up to and including the last row were removed, and the
+    /// remainder of the last row is joined to the first.
+    Delete(CursorLocation, CursorLocation),
+}
+
+pub type OnChange = Box<dyn Send + Sync + FnMut(&TextBuffer, TextChange)>;
+
+/// Reformats the whole text given the byte offset of the cursor, returning the new text and
+/// cursor offset
+pub type Formatter = Box<dyn Send + Sync + Fn(&str, usize) -> (String, usize)>;
+
+/// Default upper bound for the memory used by the undo history, in bytes
+pub const DEFAULT_HISTORY_LIMIT: usize = 1024 * 1024;
+
+/// The core text editor buffer
+pub struct TextEditorCore {
+    text: TextBuffer,
+    /// The current cursor position
+    ///
+    cursor: CursorLocation,
+    selection: Option<CursorLocation>,
+    /// Additional carets, which edits and cursor movements are applied to along with the primary
+    /// cursor
+    carets: Vec<Caret>,
+    on_change: OnChange,
+    history: EditHistory,
+    /// Operations made since the last undo step was recorded
+    pending: Vec<EditOp>,
+    /// Diagnostics along with the byte range of the text they refer to
+    diagnostics: Vec<(Range<usize>, Diagnostic)>,
+    formatter: Option<Formatter>,
+}
+
+/// Kind of an edit, used to coalesce consecutive edits into a single undo step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditKind {
+    Insert,
+    Delete,
+    Other,
+}
+
+/// Replacement of `removed` with `inserted` at a byte offset of the text
+#[derive(Debug, Clone)]
+struct EditOp {
+    at: usize,
+    removed: String,
+    inserted: String,
+}
+
+impl EditOp {
+    /// Returns where a byte offset ends up after the operation
+    fn shift(&self, pos: usize) -> usize {
+        if pos >= self.at + self.removed.len() {
+            pos - self.removed.len() + self.inserted.len()
+        } else {
+            pos.min(self.at)
+        }
+    }
+}
+
+/// A reversible group of operations
+#[derive(Debug, Clone)]
+struct EditRecord {
+    ops: Vec<EditOp>,
+    /// Carets before the edit, starting with the primary caret
+    before: Vec<Caret>,
+    /// Carets after the edit
+    after: Vec<Caret>,
+    kind: EditKind,
+}
+
+impl EditRecord {
+    fn memory(&self) -> usize {
+        mem::size_of::<Self>()
+            + (self.before.capacity() + self.after.capacity()) * mem::size_of::<Caret>()
+            + self
+                .ops
+                .iter()
+                .map(|v| mem::size_of::<EditOp>() + v.removed.capacity() + v.inserted.capacity())
+                .sum::<usize>()
+    }
+
+    /// Appends operations, merging consecutive insertions and backward deletions
+    fn extend(&mut self, ops: Vec<EditOp>) {
+        for op in ops {
+            if let Some(last) = self.ops.last_mut() {
+                if last.removed.is_empty()
+                    && op.removed.is_empty()
+                    && op.at == last.at + last.inserted.len()
+                {
+                    last.inserted.push_str(&op.inserted);
+                    continue;
+                }
+
+                if last.inserted.is_empty()
+                    && op.inserted.is_empty()
+                    && op.at + op.removed.len() == last.at
+                {
+                    last.removed.insert_str(0, &op.removed);
+                    last.at = op.at;
+                    continue;
+                }
+            }
+
+            self.ops.push(op);
+        }
+    }
+}
+
+/// Undo and redo stacks, bounded by the memory used by the undo stack
+struct EditHistory {
+    undo: VecDeque<EditRecord>,
+    redo: Vec<EditRecord>,
+    memory: usize,
+    limit: usize,
+    /// Prevents the next edit from being merged into the last undo step
+    sealed: bool,
+    /// Whether the last grapheme typed or deleted was whitespace
+    last_whitespace: bool,
+}
+
+impl EditHistory {
+    fn new(limit: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            memory: 0,
+            limit,
+            sealed: false,
+            last_whitespace: false,
+        }
+    }
+
+    fn push(&mut self, record: EditRecord, whitespace: bool) {
+        self.redo.clear();
+
+        // Typing or deleting is grouped into words, which begin where whitespace ends when typing, and
+        // where whitespace begins when deleting backwards
+        let word_boundary = match record.kind {
+            EditKind::Insert => self.last_whitespace && !whitespace,
+            EditKind::Delete => !self.last_whitespace && whitespace,
+            EditKind::Other => true,
+        };
+
+        self.last_whitespace = whitespace;
+
+        if !self.sealed && !word_boundary {
+            if let Some(last) = self.undo.back_mut().filter(|v| v.kind == record.kind) {
+                self.memory -= last.memory();
+                last.extend(record.ops);
+                last.after = record.after;
+                self.memory += last.memory();
+                self.evict();
+                return;
+            }
+        }
+
+        self.sealed = record.kind == EditKind::Other;
+        self.push_undo(record);
+    }
+
+    fn push_undo(&mut self, record: EditRecord) {
+        self.memory += record.memory();
+        self.undo.push_back(record);
+        self.evict();
+    }
+
+    fn pop_undo(&mut self) -> Option<EditRecord> {
+        let record = self.undo.pop_back()?;
+        self.memory -= record.memory();
+        Some(record)
+    }
+
+    fn evict(&mut self) {
+        while self.memory > self.limit {
+            let Some(record) = self.undo.pop_front() else {
+                break;
+            };
+
+            self.memory -= record.memory();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.memory = 0;
+        self.sealed = false;
+    }
+}
+
+/// Movement action for the cursor
+#[derive(Debug, Clone, Copy)]
+pub enum CursorMove {
+    Up,
+    Down,
+    Left,
+    Right,
+    ForwardWord,
+    BackwardWord,
+    /// Start of the current row
+    LineStart,
+    /// End of the current row
+    LineEnd,
+    TextStart,
+    TextEnd,
+    SetPosition(CursorLocation),
+}
+
+pub enum EditAction<S = String> {
+    InsertText(S),
+    DeleteBackwardChar,
+    DeleteForwardChar,
+    DeleteBackwardWord,
+    InsertLine,
+    DeleteLine,
+}
+
+pub enum EditorAction<S = String> {
+    CursorMove(CursorMove),
+    SelectionMove(CursorMove),
+    SelectionStart,
+    SelectionClear,
+    SelectAll,
+    Edit(EditAction<S>),
+    SetText(Vec<S>),
+    Undo,
+    Redo,
+    /// Adds a caret at the given location
+    AddCaret(CursorLocation),
+    /// Selects the word at the cursor, or adds a caret selecting the next occurrence of the
+    /// selected text
+    AddNextOccurrence,
+    /// Replaces all carets, the first becoming the primary caret
+    SetCarets(Vec<Caret>),
+    /// Removes all carets but the primary
+    ClearCarets,
+}
+
+impl TextEditorCore {
+    pub fn new(on_change: impl 'static + Send + Sync + FnMut(&TextBuffer, TextChange)) -> Self {
+        Self {
+            cursor: CursorLocation { row: 0, col: 0 },
+            text: TextBuffer::new(),
+            selection: None,
+            carets: Vec::new(),
+            on_change: Box::new(on_change),
+            history: EditHistory::new(DEFAULT_HISTORY_LIMIT),
+            pending: Vec::new(),
+            diagnostics: Vec::new(),
+            formatter: None,
+        }
+    }
+
+    /// Reformats the text after each edit, such as to enforce an input mask.
+    ///
+    /// The formatting is part of the same undo step as the edit.
+    pub fn set_formatter(
+        &mut self,
+        formatter: impl 'static + Send + Sync + Fn(&str, usize) -> (String, usize),
+    ) {
+        self.formatter = Some(Box::new(formatter));
+    }
+
+    /// Set the maximum memory used by the undo history, in bytes.
+    ///
+    /// The oldest undo steps are discarded when the limit is exceeded.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history.limit = limit;
+        self.history.evict();
+    }
+
+    pub fn move_cursor(&mut self, m: CursorMove) {
+        self.cursor = self.get_new_cursor(m, self.cursor);
+
+        for i in 0..self.carets.len() {
+            let cursor = self.get_new_cursor(m, self.carets[i].cursor);
+            self.carets[i].cursor = cursor;
+        }
+
+        self.merge_carets();
+        self.history.sealed = true;
+    }
+
+    pub fn move_selection(&mut self, m: CursorMove) {
+        self.selection = Some(self.get_new_cursor(m, self.selection.unwrap_or(self.cursor)));
+
+        for i in 0..self.carets.len() {
+            let caret = self.carets[i];
+            let selection = self.get_new_cursor(m, caret.selection.unwrap_or(caret.cursor));
+            self.carets[i].selection = Some(selection);
+        }
+
+        self.merge_carets();
+        self.history.sealed = true;
+    }
+
+    /// Returns all carets, starting with the primary caret
+    pub fn carets(&self) -> impl Iterator<Item = Caret> + '_ {
+        iter::once(self.primary_caret()).chain(self.carets.iter().copied())
+    }
+
+    fn primary_caret(&self) -> Caret {
+        Caret {
+            cursor: self.cursor,
+            selection: self.selection,
+        }
+    }
+
+    /// Replaces all carets, the first becoming the primary caret
+    pub fn set_carets(&mut self, carets: impl IntoIterator<Item = Caret>) {
+        let mut carets = carets.into_iter();
+        let Some(primary) = carets.next() else {
+            return;
+        };
+
+        self.cursor = primary.cursor;
+        self.selection = primary.selection;
+        self.carets = carets.collect();
+        self.merge_carets();
+        self.history.sealed = true;
+    }
+
+    /// Adds a caret at the given location, which becomes the primary caret
+    pub fn add_caret(&mut self, loc: CursorLocation) {
+        let primary = self.primary_caret();
+        self.carets.push(primary);
+        self.cursor = self.text.clamp(loc);
+        self.selection = None;
+        self.merge_carets();
+        self.history.sealed = true;
+    }
+
+    /// Removes all carets except the primary caret
+    pub fn clear_carets(&mut self) {
+        self.carets.clear();
+    }
+
+    /// Selects the word at the cursor if nothing is selected.
+    ///
+    /// Otherwise, adds a caret selecting the next occurrence of the selected text, which becomes
+    /// the primary caret.
+    pub fn add_next_occurrence(&mut self) {
+        self.history.sealed = true;
+
+        let Some((start, end)) = self.selection_bounds().filter(|(start, end)| start != end) else {
+            let line = self.text.line(self.cursor.row);
+            let word = line
+                .unicode_word_indices()
+                .find(|(i, word)| *i <= self.cursor.col && self.cursor.col <= i + word.len());
+
+            if let Some((i, word)) = word {
+                self.selection = Some(CursorLocation::new(self.cursor.row, i));
+                self.cursor = CursorLocation::new(self.cursor.row, i + word.len());
+            }
+
+            return;
+        };
+
+        let query = SearchQuery::new(self.text.slice(start, end)).with_case_sensitive(true);
+        let from = self.text.to_byte(end);
+
+        let found = self
+            .find_from(&query, from)
+            .or_else(|| self.find_from(&query, 0));
+
+        if let Some(found) = found {
+            let caret = Caret::new(self.text.to_location(found.end))
+                .with_selection(self.text.to_location(found.start));
+
+            let primary = self.primary_caret();
+            self.carets.push(primary);
+            self.cursor = caret.cursor;
+            self.selection = caret.selection;
+            self.merge_carets();
+        }
+    }
+
+    /// Returns all carets in the order of the text, with the primary caret flagged
+    fn sorted_carets(&self) -> Vec<(Caret, bool)> {
+        caret::merge_carets(
+            iter::once((self.primary_caret(), true))
+                .chain(self.carets.iter().map(|&v| (v, false)))
+                .collect(),
+        )
+    }
+
+    /// Clamps the carets to the text and merges overlapping carets
+    fn merge_carets(&mut self) {
+        let carets = self
+            .carets()
+            .map(|v| Caret {
+                cursor: self.text.clamp(v.cursor),
+                selection: v.selection.map(|v| self.text.clamp(v)),
+            })
+            .collect_vec();
+
+        self.assign_carets(carets.into_iter().enumerate().map(|(i, v)| (v, i == 0)));
+    }
+
+    fn assign_carets(&mut self, carets: impl IntoIterator<Item = (Caret, bool)>) {
+        let carets = caret::merge_carets(carets.into_iter().collect());
+
+        self.carets.clear();
+        for (caret, primary) in carets {
+            if primary {
+                self.cursor = caret.cursor;
+                self.selection = caret.selection;
+            } else {
+                self.carets.push(caret);
+            }
+        }
+    }
+
+    /// Runs an edit at each caret, from the first to the last in the text.
+    ///
+    /// Carets following an edited caret are shifted by the operations of the edit.
+    fn edit_carets(&mut self, mut edit: impl FnMut(&mut Self)) {
+        let mut carets = self
+            .sorted_carets()
+            .into_iter()
+            .map(|(caret, primary)| {
+                (
+                    self.text.to_byte(caret.cursor),
+                    caret.selection.map(|v| self.text.to_byte(v)),
+                    primary,
+                )
+            })
+            .collect_vec();
+
+        for i in 0..carets.len() {
+            let (cursor, selection, _) = carets[i];
+            self.cursor = self.text.to_location(cursor);
+            self.selection = selection.map(|v| self.text.to_location(v));
+
+            let ops_start = self.pending.len();
+            edit(self);
+
+            carets[i].0 = self.text.to_byte(self.cursor);
+            carets[i].1 = self.selection.map(|v| self.text.to_byte(v));
+
+            for op in &self.pending[ops_start..] {
+                for (cursor, selection, _) in &mut carets[i + 1..] {
+                    *cursor = op.shift(*cursor);
+                    *selection = selection.map(|v| op.shift(v));
+                }
+            }
+        }
+
+        let carets = carets
+            .into_iter()
+            .map(|(cursor, selection, primary)| {
+                let caret = Caret {
+                    cursor: self.text.to_location(cursor),
+                    selection: selection.map(|v| self.text.to_location(v)),
+                };
+
+                (caret, primary)
+            })
+            .collect_vec();
+
+        self.assign_carets(carets);
+    }
+
+    /// Returns where `cursor` ends up after a movement, without moving any caret
+    pub fn get_new_cursor(&self, m: CursorMove, cursor: CursorLocation) -> CursorLocation {
+        match m {
+            CursorMove::Up => CursorLocation {
+                row: cursor.row.saturating_sub(1),
+                col: cursor.col,
+            },
+            CursorMove::Down => CursorLocation {
+                row: (cursor.row + 1).min(self.text.len_lines() - 1),
+                col: cursor.col,
+            },
+            CursorMove::Left => {
+                let line = self.text.line(cursor.row);
+                if let Some((i, _)) = find_before(line.grapheme_indices(true), cursor.col) {
+                    CursorLocation {
+                        row: cursor.row,
+                        col: i,
+                    }
+                } else if cursor.row > 0 {
+                    CursorLocation {
+                        row: cursor.row - 1,
+                        col: self.text.line_len(cursor.row - 1),
+                    }
+                } else {
+                    cursor
+                }
+            }
+            CursorMove::Right => {
+                let line = self.text.line(cursor.row);
+                let next_glyph = line.grapheme_indices(true).find(|(i, _)| *i == cursor.col);
+
+                if let Some((i, g)) = next_glyph {
+                    CursorLocation {
+                        row: cursor.row,
+                        col: i + g.len(),
+                    }
+                } else if cursor.row < self.text.len_lines() - 1 {
+                    CursorLocation {
+                        row: cursor.row + 1,
+                        col: 0,
+                    }
+                } else {
+                    cursor
+                }
+            }
+            CursorMove::ForwardWord => {
+                let line = self.text.line(cursor.row);
+                let word = line
+                    .unicode_word_indices()
+                    .find_or_last(|(i, _)| *i >= cursor.col);
+                tracing::debug!(?word, "current word");
+                if let Some((i, word)) = word {
+                    CursorLocation {
+                        row: cursor.row,
+                        col: i + word.len(),
+                    }
+                } else {
+                    cursor
+                }
+            }
+            CursorMove::BackwardWord => {
+                if cursor.col > 0 {
+                    let line = self.text.line(cursor.row);
+                    let word = line
+                        .unicode_word_indices()
+                        .rev()
+                        .find(|(i, _)| *i < cursor.col);
+                    tracing::debug!(?word, "current word");
+                    if let Some((i, _)) = word {
+                        CursorLocation {
+                            row: cursor.row,
+                            col: i,
+                        }
+                    } else {
+                        cursor
+                    }
+                } else if cursor.row > 0 {
+                    CursorLocation {
+                        row: cursor.row - 1,
+                        col: self.text.line_len(cursor.row - 1),
+                    }
+                } else {
+                    cursor
+                }
+            }
+            CursorMove::LineStart => CursorLocation {
+                row: cursor.row,
+                col: 0,
+            },
+            CursorMove::LineEnd => CursorLocation {
+                row: cursor.row,
+                col: self.text.line_len(cursor.row),
+            },
+            CursorMove::TextStart => CursorLocation { row: 0, col: 0 },
+            CursorMove::TextEnd => self.text.end(),
+            CursorMove::SetPosition(pos) => {
+                if (pos.row > self.text.len_lines() - 1) || (pos.col > self.text.line_len(pos.row))
+                {
+                    tracing::error!(?pos, "invalid cursor position");
+                    cursor
+                } else {
+                    pos
+                }
+            }
+        }
+    }
+
+    pub fn on_change(&mut self, change: TextChange) {
+        (self.on_change)(&self.text, change.clone());
+    }
+
+    /// Inserts text at the given location and returns the location of the end of the inserted
+    /// text
+    fn insert(&mut self, at: CursorLocation, text: &str) -> CursorLocation {
+        if text.is_empty() {
+            return at;
+        }
+
+        let text = buffer::normalize_line_breaks(text);
+        let byte = self.text.to_byte(at);
+        self.text.insert(byte, &text);
+
+        let start = self.text.to_location(byte);
+        let end = self.text.to_location(byte + text.len());
+
+        let op = EditOp {
+            at: byte,
+            removed: String::new(),
+            inserted: text.into_owned(),
+        };
+
+        self.shift_diagnostics(&op);
+        self.pending.push(op);
+
+        self.on_change(TextChange::Insert(start, end));
+        end
+    }
+
+    /// Removes the text between two locations
+    fn remove(&mut self, start: CursorLocation, end: CursorLocation) {
+        let start_byte = self.text.to_byte(start);
+        let end_byte = self.text.to_byte(end);
+        if start_byte >= end_byte {
+            return;
+        }
+
+        let start = self.text.to_location(start_byte);
+        let end = self.text.to_location(end_byte);
+        let removed = self.text.byte_slice(start_byte, end_byte).into_owned();
+
+        self.text.remove(start_byte, end_byte);
+
+        let op = EditOp {
+            at: start_byte,
+            removed,
+            inserted: String::new(),
+        };
+
+        self.shift_diagnostics(&op);
+        self.pending.push(op);
+
+        self.on_change(TextChange::Delete(start, end));
+    }
+
+    /// Replaces `len` bytes at the given byte offset
+    fn replace(&mut self, at: usize, len: usize, text: &str) {
+        let start = self.text.to_location(at);
+        let end = self.text.to_location(at + len);
+        self.remove(start, end);
+        self.insert(start, text);
+    }
+
+    /// Applies an edit at every caret
+    pub fn edit<S: AsRef<str>>(&mut self, action: EditAction<S>) {
+        let (kind, whitespace) = self.edit_kind(&action);
+        let before = self.carets().collect_vec();
+
+        self.edit_carets(|this| this.apply_edit(&action));
+        self.apply_formatter();
+        self.record_edit(before, kind, whitespace);
+    }
+
+    fn apply_formatter(&mut self) {
+        let Some(formatter) = &self.formatter else {
+            return;
+        };
+
+        let text = self.text.to_string();
+        let cursor = self.text.to_byte(self.insert_location());
+        let (new_text, new_cursor) = formatter(&text, cursor);
+
+        if new_text != text {
+            let (prefix, suffix) = common_affixes(&self.text, &new_text);
+            self.replace(
+                prefix,
+                text.len() - suffix - prefix,
+                &new_text[prefix..new_text.len() - suffix],
+            );
+        }
+
+        self.carets.clear();
+        self.selection = None;
+        self.cursor = self.text.to_location(new_cursor.min(self.text.len_bytes()));
+    }
+
+    /// Returns how an edit is grouped in the undo history, and whether it types or deletes
+    /// whitespace
+    fn edit_kind<S: AsRef<str>>(&self, action: &EditAction<S>) -> (EditKind, bool) {
+        if self.selection.is_some() {
+            return (EditKind::Other, false);
+        }
+
+        let is_whitespace = |v: &str| v.chars().all(char::is_whitespace);
+
+        match action {
+            EditAction::InsertText(text)
+                if !text.as_ref().contains('\n') && text.as_ref().graphemes(true).count() == 1 =>
+            {
+                (EditKind::Insert, is_whitespace(text.as_ref()))
+            }
+            EditAction::DeleteBackwardChar => {
+                let line = self.text.line(self.cursor.row);
+                match find_before(line.grapheme_indices(true), self.cursor.col) {
+                    Some((_, g)) => (EditKind::Delete, is_whitespace(g)),
+                    None => (EditKind::Other, false),
+                }
+            }
+            _ => (EditKind::Other, false),
+        }
+    }
+
+    /// Records the operations made since the last undo step in the undo history
+    fn record_edit(&mut self, before: Vec<Caret>, kind: EditKind, whitespace: bool) {
+        let ops = mem::take(&mut self.pending);
+        if ops.is_empty() {
+            return;
+        }
+
+        self.history.push(
+            EditRecord {
+                ops,
+                before,
+                after: self.carets().collect(),
+                kind,
+            },
+            whitespace,
+        );
+    }
+
+    /// Reverts the last group of edits.
+    ///
+    /// Returns true if the text changed
+    pub fn undo(&mut self) -> bool {
+        let Some(record) = self.history.pop_undo() else {
+            return false;
+        };
+
+        for op in record.ops.iter().rev() {
+            self.replace(op.at, op.inserted.len(), &op.removed);
+        }
+
+        self.pending.clear();
+        self.set_carets(record.before.iter().copied());
+
+        self.history.redo.push(record);
+        self.history.sealed = true;
+        true
+    }
+
+    /// Reapplies the last undone group of edits.
+    ///
+    /// Returns true if the text changed
+    pub fn redo(&mut self) -> bool {
+        let Some(record) = self.history.redo.pop() else {
+            return false;
+        };
+
+        for op in &record.ops {
+            self.replace(op.at, op.removed.len(), &op.inserted);
+        }
+
+        self.pending.clear();
+        self.set_carets(record.after.iter().copied());
+
+        self.history.push_undo(record);
+        self.history.sealed = true;
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
+    }
+
+    /// Discards all undo and redo steps
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    fn apply_edit<S: AsRef<str>>(&mut self, action: &EditAction<S>) {
+        match action {
+            EditAction::InsertText(text) => {
+                self.delete_selection();
+                let at = self.insert_location();
+                self.cursor = self.insert(at, text.as_ref());
+            }
+            EditAction::DeleteBackwardChar => {
+                if self.delete_selection() {
+                    return;
+                }
+
+                let cursor = self.insert_location();
+                tracing::debug!(?cursor);
+
+                if cursor.col > 0 {
+                    let line = self.text.line(cursor.row);
+                    let current_grapheme = find_before(line.grapheme_indices(true), cursor.col);
+
+                    if let Some((i, _)) = current_grapheme {
+                        tracing::debug!("deleting grapheme at {}..{}", i, cursor.col);
+                        self.cursor = CursorLocation::new(cursor.row, i);
+                        self.remove(self.cursor, cursor);
+                    }
+                }
+                // Deleting the beginning of the line
+                else if cursor.row > 0 {
+                    tracing::debug!("deleting line {}", cursor.row);
+                    self.cursor =
+                        CursorLocation::new(cursor.row - 1, self.text.line_len(cursor.row - 1));
+                    self.remove(self.cursor, cursor);
+                }
+            }
+            EditAction::DeleteForwardChar => {
+                if self.delete_selection() {
+                    return;
+                }
+
+                let cursor = self.insert_location();
+                let end = self.get_new_cursor(CursorMove::Right, cursor);
+                self.cursor = cursor;
+                self.remove(cursor, end);
+            }
+            EditAction::DeleteBackwardWord => {
+                if self.delete_selection() {
+                    return;
+                }
+
+                let cursor = self.insert_location();
+                if cursor.col > 0 {
+                    let line = self.text.line(cursor.row);
+                    let mut word_begin = 0;
+                    let mut in_word = false;
+                    for (i, g) in line.grapheme_indices(true) {
+                        if i >= cursor.col {
+                            break;
+                        }
+                        if !g.chars().all(char::is_whitespace) {
+                            if !in_word {
+                                word_begin = i;
+                            }
+                            in_word = true;
+                        } else {
+                            in_word = false;
+                        }
+                    }
+
+                    self.cursor = CursorLocation::new(cursor.row, word_begin);
+                    self.remove(self.cursor, cursor);
+                } else if cursor.row > 0 {
+                    self.cursor =
+                        CursorLocation::new(cursor.row - 1, self.text.line_len(cursor.row - 1));
+                    self.remove(self.cursor, cursor);
+                }
+            }
+            EditAction::InsertLine => {
+                self.delete_selection();
+                let at = self.insert_location();
+                self.cursor = self.insert(at, "\n");
+            }
+            EditAction::DeleteLine => {
+                if self.delete_selection() {
+                    return;
+                }
+
+                let row = self.cursor.row;
+                if self.text.len_lines() == 1 {
+                    self.remove(CursorLocation::new(0, 0), self.text.end());
+                    self.cursor.col = 0;
+                } else if row + 1 < self.text.len_lines() {
+                    self.remove(CursorLocation::new(row, 0), CursorLocation::new(row + 1, 0));
+                } else {
+                    self.remove(
+                        CursorLocation::new(row - 1, self.text.line_len(row - 1)),
+                        self.text.end(),
+                    );
+                    self.cursor.row = row - 1;
+                }
+
+                self.cursor.col = self.cursor.col.min(self.text.line_len(self.cursor.row));
+            }
+        }
+
+        tracing::debug!(text = ?self.text, "text after edit");
+    }
+
+    pub fn apply_action<S: AsRef<str>>(&mut self, action: EditorAction<S>) -> bool {
+        match action {
+            EditorAction::CursorMove(m) => {
+                self.move_cursor(m);
+                false
+            }
+            EditorAction::SelectionMove(m) => {
+                self.move_selection(m);
+                false
+            }
+            EditorAction::Edit(e) => {
+                self.edit(e);
+                true
+            }
+            EditorAction::SetText(v) => {
+                self.set_text(v.iter().map(|v| v.as_ref()));
+                true
+            }
+            EditorAction::Undo => self.undo(),
+            EditorAction::Redo => self.redo(),
+            EditorAction::AddCaret(loc) => {
+                self.add_caret(loc);
+                false
+            }
+            EditorAction::AddNextOccurrence => {
+                self.add_next_occurrence();
+                false
+            }
+            EditorAction::SetCarets(carets) => {
+                self.set_carets(carets);
+                false
+            }
+            EditorAction::ClearCarets => {
+                self.clear_carets();
+                false
+            }
+            EditorAction::SelectionClear => {
+                self.clear_selection();
+                false
+            }
+            EditorAction::SelectionStart => {
+                if self.selection.is_none() {
+                    self.selection = Some(self.cursor);
+                }
+
+                for caret in &mut self.carets {
+                    if caret.selection.is_none() {
+                        caret.selection = Some(caret.cursor);
+                    }
+                }
+                false
+            }
+            EditorAction::SelectAll => {
+                self.history.sealed = true;
+                self.carets.clear();
+                self.cursor = CursorLocation { row: 0, col: 0 };
+                self.selection = Some(self.text.end());
+                false
+            }
+        }
+    }
+
+    /// Returns the underlying text buffer
+    pub fn text(&self) -> &TextBuffer {
+        &self.text
+    }
+
+    pub fn lines_str(&self) -> impl Iterator<Item = std::borrow::Cow<'_, str>> {
+        self.text.lines()
+    }
+
+    /// Replaces the whole text.
+    ///
+    /// Only the differing part of the text is replaced. The undo history is cleared if the new
+    /// text differs, as it no longer applies.
+    pub fn set_text<'a>(&mut self, text: impl IntoIterator<Item = &'a str>) {
+        let new_text = text.into_iter().join("\n");
+
+        if self.text == *new_text {
+            return;
+        }
+
+        let at_end_col = self.cursor.col >= self.text.line_len(self.cursor.row);
+        let at_end_row = self.cursor.row >= self.text.len_lines() - 1;
+
+        let (prefix, suffix) = common_affixes(&self.text, &new_text);
+
+        self.replace(
+            prefix,
+            self.text.len_bytes() - suffix - prefix,
+            &new_text[prefix..new_text.len() - suffix],
+        );
+
+        self.pending.clear();
+        self.history.clear();
+
+        self.merge_carets();
+
+        if at_end_row {
+            self.cursor.row = self.text.len_lines() - 1;
+        }
+
+        if at_end_col {
+            self.cursor.col = self.text.line_len(self.cursor.row);
+        }
+    }
+
+    /// Moves the cursor, removing any additional carets
+    pub fn set_cursor(&mut self, row: usize, col: usize) {
+        self.cursor = self.text.clamp(CursorLocation::new(row, col));
+        self.carets.clear();
+        self.history.sealed = true;
+    }
+
+    pub fn set_cursor_at_end(&mut self) {
+        self.cursor = self.text.end();
+        self.carets.clear();
+        self.history.sealed = true;
+    }
+
+    pub fn cursor(&self) -> CursorLocation {
+        self.cursor
+    }
+
+    pub fn past_eol(&self) -> bool {
+        self.cursor.col >= self.text.line_len(self.cursor.row)
+    }
+
+    /// The cursor clamped to the text
+    fn insert_location(&self) -> CursorLocation {
+        self.text.clamp(self.cursor)
+    }
+
+    pub fn selection_bounds(&self) -> Option<(CursorLocation, CursorLocation)> {
+        let sel = self.selection?;
+        if sel < self.cursor {
+            Some((sel, self.cursor))
+        } else {
+            Some((self.cursor, sel))
+        }
+    }
+
+    /// Returns the selected text of all carets, separated by line breaks
+    pub fn selected_text(&self) -> Option<String> {
+        let selections = self
+            .sorted_carets()
+            .into_iter()
+            .filter_map(|(v, _)| v.selection_bounds())
+            .collect_vec();
+
+        if selections.is_empty() {
+            return None;
+        }
+
+        Some(
+            selections
+                .into_iter()
+                .map(|(start, end)| self.text.slice(start, end))
+                .join("\n"),
+        )
+    }
+
+    /// Deletes the selected text of all carets as a single undo step.
+    ///
+    /// Returns false if nothing was selected
+    pub fn delete_selected_text(&mut self) -> bool {
+        let before = self.carets().collect_vec();
+        let deleted = before.iter().any(|v| v.selection.is_some());
+
+        self.edit_carets(|this| {
+            this.delete_selection();
+        });
+
+        self.record_edit(before, EditKind::Other, false);
+        deleted
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection_bounds() else {
+            return false;
+        };
+
+        self.remove(start, end);
+
+        self.cursor = self.text.clamp(start);
+        self.clear_selection();
+
+        true
+    }
+
+    pub fn set_selection(&mut self, sel: Option<CursorLocation>) {
+        self.selection = sel;
+    }
+
+    /// Clears the selection of all carets
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+        for caret in &mut self.carets {
+            caret.selection = None;
+        }
+    }
+
+    pub fn selection(&self) -> Option<CursorLocation> {
+        self.selection
+    }
+}
+
+/// Returns the length in bytes of the common prefix and suffix of the text and a string, which do
+/// not overlap.
+///
+/// The text is compared in place, without copying it.
+fn common_affixes(old: &TextBuffer, new: &str) -> (usize, usize) {
+    let old = old.rope();
+
+    let mut prefix = old
+        .bytes()
+        .zip(new.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    // The common bytes are the same in both texts, so a char boundary in `new` is one in `old`
+    while !new.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+
+    let mut suffix = old
+        .bytes_at(old.len_bytes())
+        .reversed()
+        .zip(new[prefix..].bytes().rev())
+        .take_while(|(a, b)| a == b)
+        .count()
+        .min(old.len_bytes() - prefix);
+
+    while !new.is_char_boundary(new.len() - suffix) {
+        suffix -= 1;
+    }
+
+    (prefix, suffix)
+}
+
+fn find_before<T>(
+    iter: impl DoubleEndedIterator<Item = (usize, T)>,
+    col: usize,
+) -> Option<(usize, T)> {
+    iter.rev().find(|(i, _)| *i < col)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editor() {
+        let mut editor = TextEditorCore::new(|_, _| {});
+        editor.edit(EditAction::InsertText("This is some text"));
+        assert_eq!(editor.lines_str().collect_vec(), &["This is some text"]);
+
+        editor.move_cursor(CursorMove::BackwardWord);
+        assert_eq!(editor.cursor().col, "This is some ".len());
+
+        editor.edit(EditAction::InsertText("other "));
+        assert_eq!(
+            editor.lines_str().collect_vec(),
+            &["This is some other text"]
+        );
+
+        editor.edit(EditAction::<String>::DeleteBackwardWord);
+
+        assert_eq!(editor.lines_str().collect_vec(), &["This is some text"]);
+
+        editor.edit(EditAction::InsertText(
+            "other text,\nand a new line for the previous ",
+        ));
+
+        assert_eq!(
+            editor.lines_str().collect_vec(),
+            &[
+                "This is some other text,",
+                "and a new line for the previous text"
+            ]
+        );
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut editor = TextEditorCore::new(|_, _| {});
+        for c in "Hello wor".graphemes(true) {
+            editor.edit(EditAction::InsertText(c));
+        }
+
+        editor.edit(EditAction::<String>::InsertLine);
+        editor.edit(EditAction::InsertText("ld"));
+        assert_eq!(editor.lines_str().collect_vec(), &["Hello wor", "ld"]);
+
+        assert!(editor.undo());
+        assert_eq!(editor.lines_str().collect_vec(), &["Hello wor", ""]);
+
+        assert!(editor.undo());
+        assert_eq!(editor.lines_str().collect_vec(), &["Hello wor"]);
+        assert_eq!(editor.cursor(), CursorLocation { row: 0, col: 9 });
+
+        // Typing is grouped by words
+        assert!(editor.undo());
+        assert_eq!(editor.lines_str().collect_vec(), &["Hello "]);
+
+        assert!(editor.redo());
+        assert!(editor.redo());
+        assert_eq!(editor.lines_str().collect_vec(), &["Hello wor", ""]);
+        assert_eq!(editor.cursor(), CursorLocation { row: 1, col: 0 });
+
+        editor.edit(EditAction::<String>::DeleteBackwardChar);
+        assert!(!editor.can_redo());
+
+        editor.edit(EditAction::<String>::DeleteBackwardChar);
+        editor.edit(EditAction::<String>::DeleteBackwardChar);
+        assert_eq!(editor.lines_str().collect_vec(), &["Hello w"]);
+
+        assert!(editor.undo());
+        assert_eq!(editor.lines_str().collect_vec(), &["Hello wor"]);
+
+        for _ in 0..4 {
+            assert!(editor.undo());
+        }
+
+        assert!(!editor.undo());
+        assert_eq!(editor.lines_str().collect_vec(), &[""]);
+    }
+
+    #[test]
+    fn history_limit() {
+        let edit = |editor: &mut TextEditorCore, i: usize| {
+            if i % 2 == 0 {
+                editor.edit(EditAction::InsertText(format!("Pasted text {i}")));
+            } else {
+                editor.edit(EditAction::<String>::InsertLine);
+            }
+        };
+
+        // Measure each undo step without a limit
+        let mut unbounded = TextEditorCore::new(|_, _| {});
+        let mut texts = vec![unbounded.lines_str().join("\n")];
+        for i in 0..64 {
+            edit(&mut unbounded, i);
+            texts.push(unbounded.lines_str().join("\n"));
+        }
+
+        let memory = unbounded
+            .history
+            .undo
+            .iter()
+            .map(|v| v.memory())
+            .collect_vec();
+        assert_eq!(memory.len(), 64);
+
+        // The newest steps which fit in the limit are kept
+        let mut total = 0;
+        let expected = memory
+            .iter()
+            .rev()
+            .take_while(|&&v| {
+                total += v;
+                total <= 1024
+            })
+            .count();
+        assert!(expected > 0 && expected < 64);
+
+        let mut editor = TextEditorCore::new(|_, _| {});
+        editor.set_history_limit(1024);
+        for i in 0..64 {
+            edit(&mut editor, i);
+        }
+
+        assert_eq!(editor.history.undo.len(), expected);
+
+        let mut steps = 0;
+        while editor.undo() {
+            steps += 1;
+        }
+
+        assert_eq!(steps, expected);
+        assert_eq!(editor.lines_str().join("\n"), texts[64 - expected]);
+    }
+
+    #[test]
+    fn multiple_carets() {
+        let mut editor = TextEditorCore::new(|_, _| {});
+        editor.set_text(["let a = 1;", "let b = a;", "a + b"]);
+
+        editor.set_cursor(0, 4);
+        editor.add_next_occurrence();
+        assert_eq!(editor.selected_text().as_deref(), Some("a"));
+
+        editor.add_next_occurrence();
+        editor.add_next_occurrence();
+        assert_eq!(editor.carets().count(), 3);
+
+        editor.edit(EditAction::InsertText("value"));
+        assert_eq!(
+            editor.lines_str().collect_vec(),
+            &["let value = 1;", "let b = value;", "value + b"]
+        );
+
+        assert!(editor.undo());
+        assert_eq!(
+            editor.lines_str().collect_vec(),
+            &["let a = 1;", "let b = a;", "a + b"]
+        );
+
+        // Carets on the same line are merged when they meet
+        editor.set_cursor(0, 0);
+        editor.add_caret(CursorLocation::new(0, 1));
+        editor.move_cursor(CursorMove::Left);
+        assert_eq!(editor.carets().count(), 1);
+
+        // Column selection
+        editor.set_carets((0..3).map(|row| {
+            Caret::new(CursorLocation::new(row, 3)).with_selection(CursorLocation::new(row, 0))
+        }));
+        editor.edit(EditAction::<String>::DeleteBackwardChar);
+        assert_eq!(
+            editor.lines_str().collect_vec(),
+            &[" a = 1;", " b = a;", " b"]
+        );
+    }
+
+    #[test]
+    fn line_movement() {
+        let mut editor = TextEditorCore::new(|_, _| {});
+        editor.set_text(["first line", "second"]);
+        editor.set_cursor(0, 3);
+
+        editor.move_cursor(CursorMove::LineEnd);
+        assert_eq!(editor.cursor(), CursorLocation::new(0, 10));
+        editor.move_cursor(CursorMove::LineStart);
+        assert_eq!(editor.cursor(), CursorLocation::new(0, 0));
+        editor.move_cursor(CursorMove::TextEnd);
+        assert_eq!(editor.cursor(), CursorLocation::new(1, 6));
+
+        editor.move_cursor(CursorMove::TextStart);
+        editor.edit(EditAction::<String>::DeleteForwardChar);
+        assert_eq!(editor.lines_str().collect_vec(), &["irst line", "second"]);
+
+        // Joins the following line
+        editor.move_cursor(CursorMove::LineEnd);
+        editor.edit(EditAction::<String>::DeleteForwardChar);
+        assert_eq!(editor.lines_str().collect_vec(), &["irst linesecond"]);
+        assert_eq!(editor.cursor(), CursorLocation::new(0, 9));
+    }
+
+    #[test]
+    fn formatter() {
+        let mut editor = TextEditorCore::new(|_, _| {});
+        editor.set_formatter(|text, cursor| {
+            let digits: String = text.chars().filter(|v| v.is_ascii_digit()).collect();
+            let before = text[..cursor]
+                .chars()
+                .filter(|v| v.is_ascii_digit())
+                .count();
+            (digits, before)
+        });
+
+        editor.edit(EditAction::InsertText("1a2"));
+        assert_eq!(editor.lines_str().collect_vec(), &["12"]);
+        assert_eq!(editor.cursor(), CursorLocation::new(0, 2));
+
+        editor.set_cursor(0, 1);
+        editor.edit(EditAction::InsertText("b3"));
+        assert_eq!(editor.lines_str().collect_vec(), &["132"]);
+        assert_eq!(editor.cursor(), CursorLocation::new(0, 2));
+
+        // The formatting is undone together with the edit
+        assert!(editor.undo());
+        assert_eq!(editor.lines_str().collect_vec(), &["12"]);
+    }
+
+    #[test]
+    fn incremental_changes() {
+        let changes = std::sync::Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let mut editor = TextEditorCore::new({
+            let changes = changes.clone();
+            move |_, change| changes.lock().push(change)
+        });
+
+        editor.set_text(["first", "second", "third"]);
+        changes.lock().clear();
+
+        editor.set_text(["first", "other", "third"]);
+        assert_eq!(
+            changes.lock().drain(..).collect_vec(),
+            [
+                TextChange::Delete(CursorLocation::new(1, 0), CursorLocation::new(1, 6)),
+                TextChange::Insert(CursorLocation::new(1, 0), CursorLocation::new(1, 5)),
+            ]
+        );
+
+        editor.set_cursor(0, 5);
+        editor.edit(EditAction::<String>::InsertLine);
+        assert_eq!(
+            changes.lock().drain(..).collect_vec(),
+            [TextChange::Insert(
+                CursorLocation::new(0, 5),
+                CursorLocation::new(1, 0)
+            )]
+        );
+    }
+
+    #[test]
+    fn affixes() {
+        let text = |v: &str| {
+            let mut text = TextBuffer::new();
+            text.insert(0, v);
+            text
+        };
+
+        assert_eq!(common_affixes(&text("héllo wörld"), "héllo wörld"), (13, 0));
+        assert_eq!(common_affixes(&text("héllo wörld"), "hällo wörld"), (1, 10));
+        assert_eq!(common_affixes(&text("aa"), "aaa"), (2, 0));
+        assert_eq!(common_affixes(&text("aaa"), "aa"), (2, 0));
+
+        // Chars sharing leading or trailing bytes are not split
+        assert_eq!(common_affixes(&text("xé"), "xè"), (1, 0));
+        assert_eq!(common_affixes(&text("é"), "ɩ"), (0, 0));
+    }
+}
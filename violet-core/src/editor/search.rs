@@ -0,0 +1,379 @@
+use std::{iter, ops::Range};
+
+use itertools::Itertools;
+
+use super::{EditKind, TextEditorCore};
+use crate::text::CursorLocation;
+
+/// Text to search for in an editor, along with how it is matched
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub pattern: String,
+    /// Match letters of the same case only
+    pub case_sensitive: bool,
+    /// Only match whole words, which are not preceded or followed by a letter, digit or `_`
+    pub whole_word: bool,
+}
+
+impl SearchQuery {
+    /// Creates a case-insensitive query
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    pub fn with_whole_word(mut self, whole_word: bool) -> Self {
+        self.whole_word = whole_word;
+        self
+    }
+
+    /// An empty query matches nothing
+    pub fn is_empty(&self) -> bool {
+        self.pattern.is_empty()
+    }
+
+    /// Returns the byte range of the first match starting at or after `from`
+    pub fn find_from(&self, text: &str, from: usize) -> Option<Range<usize>> {
+        self.find_in(text[from..].chars(), text[..from].chars().next_back(), from)
+    }
+
+    /// Iterates the byte ranges of all non-overlapping matches in the text
+    pub fn find_iter<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        let mut from = 0;
+        iter::from_fn(move || {
+            let range = self.find_from(text, from)?;
+            from = range.end;
+            Some(range)
+        })
+    }
+
+    /// Returns the first match in `chars`, which start at the byte offset `offset` and follow
+    /// `prev`.
+    ///
+    /// Works on any source of characters, so that text does not need to be contiguous.
+    fn find_in(
+        &self,
+        mut chars: impl Clone + Iterator<Item = char>,
+        mut prev: Option<char>,
+        offset: usize,
+    ) -> Option<Range<usize>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let pattern = self.fold(&self.pattern).collect_vec();
+        let mut start = offset;
+
+        loop {
+            if let Some((len, next)) = self.match_at(chars.clone(), &pattern) {
+                let is_word_boundary =
+                    !prev.is_some_and(is_word_char) && !next.is_some_and(is_word_char);
+
+                if !self.whole_word || is_word_boundary {
+                    return Some(start..start + len);
+                }
+            }
+
+            let c = chars.next()?;
+            prev = Some(c);
+            start += c.len_utf8();
+        }
+    }
+
+    /// Returns the length in bytes of a match at the start of `chars`, along with the character
+    /// following it
+    fn match_at(
+        &self,
+        chars: impl Iterator<Item = char>,
+        pattern: &[char],
+    ) -> Option<(usize, Option<char>)> {
+        let mut matched = 0;
+        let mut len = 0;
+
+        for c in chars {
+            if matched == pattern.len() {
+                return Some((len, Some(c)));
+            }
+
+            // A folded character may expand to several, which must all match
+            for c in self.fold_char(c) {
+                if pattern.get(matched) != Some(&c) {
+                    return None;
+                }
+
+                matched += 1;
+            }
+
+            len += c.len_utf8();
+        }
+
+        (matched == pattern.len()).then_some((len, None))
+    }
+
+    fn fold<'a>(&'a self, text: &'a str) -> impl Iterator<Item = char> + 'a {
+        text.chars().flat_map(|c| self.fold_char(c))
+    }
+
+    fn fold_char(&self, c: char) -> impl Iterator<Item = char> {
+        let lowercase = (!self.case_sensitive).then(|| c.to_lowercase());
+        let exact = self.case_sensitive.then_some(c);
+
+        lowercase.into_iter().flatten().chain(exact)
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl TextEditorCore {
+    /// Returns the byte range of the first match starting at or after `from`.
+    ///
+    /// The text is searched in place, without copying it out of the rope.
+    pub(super) fn find_from(&self, query: &SearchQuery, from: usize) -> Option<Range<usize>> {
+        let rope = self.text.rope();
+        let start = rope.byte_to_char(from);
+        let prev = start.checked_sub(1).map(|v| rope.char(v));
+
+        query.find_in(rope.chars_at(start), prev, from)
+    }
+
+    fn find_ranges<'a>(
+        &'a self,
+        query: &'a SearchQuery,
+    ) -> impl Iterator<Item = Range<usize>> + 'a {
+        let mut from = 0;
+        iter::from_fn(move || {
+            let range = self.find_from(query, from)?;
+            from = range.end;
+            Some(range)
+        })
+    }
+
+    /// Iterates all matches of the query in the text, in order.
+    ///
+    /// Each match is given as the locations of its start and end, in the same form as
+    /// [`Self::selection_bounds`].
+    pub fn find_matches<'a>(
+        &'a self,
+        query: &'a SearchQuery,
+    ) -> impl Iterator<Item = (CursorLocation, CursorLocation)> + 'a {
+        self.find_ranges(query).map(|range| {
+            (
+                self.text.to_location(range.start),
+                self.text.to_location(range.end),
+            )
+        })
+    }
+
+    /// Selects the first match after the primary caret, wrapping around to the start of the
+    /// text.
+    ///
+    /// Additional carets are removed. Returns false if there are no matches.
+    pub fn select_next_match(&mut self, query: &SearchQuery) -> bool {
+        let (_, end) = self.primary_caret().bounds();
+        let from = self.text.to_byte(end);
+
+        let found = self
+            .find_from(query, from)
+            .or_else(|| self.find_from(query, 0));
+
+        self.select_match(found)
+    }
+
+    /// Selects the last match before the primary caret, wrapping around to the end of the text.
+    ///
+    /// Additional carets are removed. Returns false if there are no matches.
+    pub fn select_previous_match(&mut self, query: &SearchQuery) -> bool {
+        let (start, _) = self.primary_caret().bounds();
+        let to = self.text.to_byte(start);
+
+        let matches = self.find_ranges(query).collect_vec();
+        let found = matches
+            .iter()
+            .rev()
+            .find(|v| v.end <= to)
+            .or(matches.last())
+            .cloned();
+
+        self.select_match(found)
+    }
+
+    fn select_match(&mut self, found: Option<Range<usize>>) -> bool {
+        let Some(range) = found else {
+            return false;
+        };
+
+        self.carets.clear();
+        self.selection = Some(self.text.to_location(range.start));
+        self.cursor = self.text.to_location(range.end);
+        self.history.sealed = true;
+        true
+    }
+
+    /// Returns the index of the match selected by the primary caret, if any
+    pub fn selected_match(&self, query: &SearchQuery) -> Option<usize> {
+        let (start, end) = self.selection_bounds()?;
+        self.find_matches(query)
+            .take_while(|&(v, _)| v <= start)
+            .position(|v| v == (start, end))
+    }
+
+    /// Replaces the match selected by the primary caret and selects the next match.
+    ///
+    /// If the selection is not a match, the next match is selected without replacing anything.
+    /// Returns true if a match was replaced.
+    pub fn replace_match(&mut self, query: &SearchQuery, replacement: &str) -> bool {
+        let selected = self.selection_bounds().and_then(|(start, end)| {
+            let range = self.text.to_byte(start)..self.text.to_byte(end);
+            self.find_from(query, range.start).filter(|v| *v == range)
+        });
+
+        let Some(range) = selected else {
+            self.select_next_match(query);
+            return false;
+        };
+
+        let before = self.carets().collect_vec();
+
+        self.replace(range.start, range.len(), replacement);
+        self.carets.clear();
+        self.selection = None;
+        self.cursor = self
+            .text
+            .to_location(self.pending.iter().fold(range.end, |pos, op| op.shift(pos)));
+
+        self.record_edit(before, EditKind::Other, false);
+        self.select_next_match(query);
+        true
+    }
+
+    /// Replaces all matches as a single undo step.
+    ///
+    /// Returns the number of replaced matches.
+    pub fn replace_all(&mut self, query: &SearchQuery, replacement: &str) -> usize {
+        let matches = self.find_ranges(query).collect_vec();
+        if matches.is_empty() {
+            return 0;
+        }
+
+        let before = self.carets().collect_vec();
+        let cursor = self.text.to_byte(self.cursor);
+
+        // Replacing from the end keeps the offsets of the remaining matches valid
+        for range in matches.iter().rev() {
+            self.replace(range.start, range.len(), replacement);
+        }
+
+        self.carets.clear();
+        self.selection = None;
+        self.cursor = self
+            .text
+            .to_location(self.pending.iter().fold(cursor, |pos, op| op.shift(pos)));
+
+        self.record_edit(before, EditKind::Other, false);
+        matches.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn find() {
+        let text = "Foo food, foo_bar FOO";
+
+        let ranges = |query: &SearchQuery| query.find_iter(text).collect_vec();
+
+        assert_eq!(
+            ranges(&SearchQuery::new("foo")),
+            [0..3, 4..7, 10..13, 18..21]
+        );
+        assert_eq!(
+            ranges(&SearchQuery::new("foo").with_case_sensitive(true)),
+            [4..7, 10..13]
+        );
+        assert_eq!(
+            ranges(&SearchQuery::new("foo").with_whole_word(true)),
+            [0..3, 18..21]
+        );
+        assert!(ranges(&SearchQuery::new("")).is_empty());
+
+        // Matches do not overlap
+        assert_eq!(
+            SearchQuery::new("aa").find_iter("aaaaa").collect_vec(),
+            [0..2, 2..4]
+        );
+        // Letters outside of ASCII are folded as well
+        assert_eq!(
+            SearchQuery::new("äb").find_iter("xÄB").collect_vec(),
+            [1..4]
+        );
+    }
+
+    #[test]
+    fn select_matches() {
+        let mut editor = TextEditorCore::new(|_, _| {});
+        editor.set_text(["one two", "two one two"]);
+        editor.set_cursor(0, 0);
+
+        let query = SearchQuery::new("two");
+        assert_eq!(
+            editor.find_matches(&query).collect_vec(),
+            [
+                (CursorLocation::new(0, 4), CursorLocation::new(0, 7)),
+                (CursorLocation::new(1, 0), CursorLocation::new(1, 3)),
+                (CursorLocation::new(1, 8), CursorLocation::new(1, 11)),
+            ]
+        );
+
+        assert!(editor.select_next_match(&query));
+        assert_eq!(editor.selected_match(&query), Some(0));
+        assert!(editor.select_next_match(&query));
+        assert!(editor.select_next_match(&query));
+        assert_eq!(editor.selected_match(&query), Some(2));
+
+        // Wraps around to the start
+        assert!(editor.select_next_match(&query));
+        assert_eq!(editor.selected_match(&query), Some(0));
+        assert!(editor.select_previous_match(&query));
+        assert_eq!(editor.selected_match(&query), Some(2));
+
+        assert!(!editor.select_next_match(&SearchQuery::new("three")));
+    }
+
+    #[test]
+    fn replace() {
+        let mut editor = TextEditorCore::new(|_, _| {});
+        editor.set_text(["one two", "two one two"]);
+        editor.set_cursor(0, 0);
+
+        let query = SearchQuery::new("two").with_whole_word(true);
+
+        // The first replacement only selects a match
+        assert!(!editor.replace_match(&query, "2"));
+        assert!(editor.replace_match(&query, "2"));
+        assert_eq!(editor.lines_str().collect_vec(), ["one 2", "two one two"]);
+        assert_eq!(editor.selected_match(&query), Some(0));
+
+        assert_eq!(editor.replace_all(&query, "three"), 2);
+        assert_eq!(
+            editor.lines_str().collect_vec(),
+            ["one 2", "three one three"]
+        );
+
+        // Replacing all is a single undo step
+        assert!(editor.undo());
+        assert_eq!(editor.lines_str().collect_vec(), ["one 2", "two one two"]);
+        assert!(editor.undo());
+        assert_eq!(editor.lines_str().collect_vec(), ["one two", "two one two"]);
+    }
+}
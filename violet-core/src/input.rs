@@ -1,10 +1,16 @@
-use flax::{component::ComponentValue, components::child_of, Component, Entity, EntityRef, World};
+use std::{collections::HashSet, mem, path::PathBuf};
+
+use flax::{
+    component::ComponentValue,
+    components::{child_of, entity_ids},
+    Component, Entity, EntityRef, Query, World,
+};
 use flume::Receiver;
 use glam::{Vec2, Vec3Swizzles};
 /// NOTE: maybe redefine these types ourselves
-pub use winit::{event, keyboard};
+pub use winit::{event, keyboard, window::CursorIcon};
 use winit::{
-    event::{ElementState, MouseButton},
+    event::{ElementState, Ime, MouseButton},
     keyboard::{Key, ModifiersState, SmolStr},
 };
 
@@ -13,7 +19,7 @@ use crate::{
     declare_atom,
     hierarchy::find_widget_intersect,
     scope::ScopeRef,
-    Frame,
+    Frame, Rect,
 };
 
 pub struct Input {}
@@ -34,20 +40,42 @@ pub struct InputState {
     pos: Vec2,
     modifiers: ModifiersState,
     external_focus_rx: Receiver<Entity>,
+    ime_cursor_area_rx: Receiver<Rect>,
+    ime_allowed: bool,
 
     hovered_item: Option<Entity>,
+    cursor_icon: CursorIcon,
+    /// Mouse buttons currently held, which capture the cursor to the focused widget
+    pressed_buttons: HashSet<MouseButton>,
+
+    /// Files currently dragged over the window from outside the application
+    hovered_files: Vec<PathBuf>,
+    file_hover_target: Option<Entity>,
+    dropped_files: Vec<PathBuf>,
 }
 
 impl InputState {
-    pub fn new(root: Entity, pos: Vec2, external_focus_rx: Receiver<Entity>) -> Self {
+    pub fn new(
+        root: Entity,
+        pos: Vec2,
+        external_focus_rx: Receiver<Entity>,
+        ime_cursor_area_rx: Receiver<Rect>,
+    ) -> Self {
         Self {
             focused: None,
             pos,
             modifiers: Default::default(),
             root,
             external_focus_rx,
+            ime_cursor_area_rx,
+            ime_allowed: false,
             last_sticky: None,
             hovered_item: None,
+            cursor_icon: CursorIcon::Default,
+            pressed_buttons: HashSet::new(),
+            hovered_files: Vec::new(),
+            file_hover_target: None,
+            dropped_files: Vec::new(),
         }
     }
 
@@ -67,6 +95,11 @@ impl InputState {
         button: MouseButton,
     ) -> bool {
         let cursor_pos = self.pos;
+        match state {
+            ElementState::Pressed => self.pressed_buttons.insert(button),
+            ElementState::Released => self.pressed_buttons.remove(&button),
+        };
+
         let intersect = self.find_intersect(frame, cursor_pos, |_| true);
         let id = match (state, &self.focused, intersect) {
             // Focus changed
@@ -87,7 +120,9 @@ impl InputState {
         };
 
         // Send the event to the intersected entity
-        if let Some(entity) = id.and_then(|id| frame.world().entity(id).ok()) {
+        let entity = id.and_then(|id| frame.world().entity(id).ok());
+        let handled = entity.is_some();
+        if let Some(entity) = entity {
             let screen_transform = entity.get_copy(screen_transform()).unwrap_or_default();
             let rect = entity.get_copy(rect()).unwrap_or_default();
             let local_pos = screen_transform
@@ -113,11 +148,12 @@ impl InputState {
                     button,
                 },
             );
-
-            return true;
         }
 
-        false
+        // Releasing the cursor from the focused widget may change the icon without moving
+        self.update_cursor_icon(frame);
+
+        handled
     }
 
     fn propagate_event<'a, T: ComponentValue>(
@@ -150,6 +186,10 @@ impl InputState {
     pub fn on_cursor_move(&mut self, frame: &mut Frame, pos: Vec2) -> bool {
         self.pos = pos;
 
+        if !self.hovered_files.is_empty() {
+            self.update_file_hover(frame);
+        }
+
         let target = self.get_focused_or_intersecting(frame, pos);
 
         let new_hover = self.hovered_item != target.map(|v| v.id());
@@ -194,6 +234,7 @@ impl InputState {
         }
 
         self.hovered_item = target.map(|v| v.id());
+        self.update_cursor_icon(frame);
 
         if let &Some(entity) = &self.get_focused(&frame.world) {
             let transform = entity.get_copy(screen_transform()).unwrap_or_default();
@@ -215,6 +256,38 @@ impl InputState {
         false
     }
 
+    fn update_cursor_icon(&mut self, frame: &Frame) {
+        // The focused widget keeps its cursor while dragging, even when the cursor leaves it
+        let icon_target = self
+            .get_focused(&frame.world)
+            .filter(|_| !self.pressed_buttons.is_empty())
+            .or_else(|| self.find_intersect(frame, self.pos, |_| true).map(|v| v.0));
+
+        self.cursor_icon = icon_target
+            .map(|v| Self::resolve_cursor_icon(frame, v))
+            .unwrap_or_default();
+    }
+
+    /// Finds the closest cursor icon in the ancestors of the hovered entity
+    fn resolve_cursor_icon(frame: &Frame, mut entity: EntityRef) -> CursorIcon {
+        loop {
+            if let Ok(icon) = entity.get_copy(cursor_icon()) {
+                return icon;
+            }
+
+            let Some((parent, _)) = entity.relations(child_of).next() else {
+                return CursorIcon::Default;
+            };
+
+            entity = frame.world().entity(parent).unwrap();
+        }
+    }
+
+    /// Returns the cursor icon requested by the currently hovered widget
+    pub fn cursor_icon(&self) -> CursorIcon {
+        self.cursor_icon
+    }
+
     pub fn on_scroll(&mut self, frame: &mut Frame, delta: Vec2) -> bool {
         let intersect = self.find_intersect(frame, self.pos, |v| v.has(interactive()));
 
@@ -246,16 +319,90 @@ impl InputState {
         state: ElementState,
         text: Option<SmolStr>,
     ) -> bool {
+        let input = KeyboardInput {
+            modifiers: self.modifiers,
+            state,
+            key,
+            text,
+        };
+
         if let &Some(entity) = &self.get_focused(frame.world()) {
+            if Self::propagate_event(entity, frame, on_keyboard_input(), input.clone()) {
+                return true;
+            }
+        }
+
+        Self::dispatch_shortcut(frame, input)
+    }
+
+    /// Offers keyboard input not consumed by the focused widget to all shortcut handlers
+    fn dispatch_shortcut(frame: &Frame, input: KeyboardInput) -> bool {
+        let targets = Query::new(entity_ids())
+            .with(on_shortcut())
+            .borrow(&frame.world)
+            .iter()
+            .collect::<Vec<_>>();
+
+        let mut input = input;
+        for id in targets {
+            let Ok(entity) = frame.world.entity(id) else {
+                continue;
+            };
+
+            if let Ok(mut on_shortcut) = entity.get_mut(on_shortcut()) {
+                let s = ScopeRef::new(frame, entity);
+                match on_shortcut(&s, input) {
+                    Some(v) => input = v,
+                    None => return true,
+                }
+            }
+        }
+
+        false
+    }
+
+    /// A file is dragged over the window from outside the application
+    pub fn on_file_hovered(&mut self, frame: &mut Frame, path: PathBuf) -> bool {
+        self.hovered_files.push(path);
+        self.update_file_hover(frame)
+    }
+
+    /// The files dragged over the window left it without being dropped
+    pub fn on_file_hover_cancelled(&mut self, frame: &mut Frame) -> bool {
+        self.hovered_files.clear();
+        self.update_file_hover(frame)
+    }
+
+    /// Queues a file dropped onto the window.
+    ///
+    /// The platform reports each file separately, so files are collected and delivered
+    /// together by [`Self::flush_dropped_files`].
+    pub fn on_file_dropped(&mut self, path: PathBuf) {
+        self.dropped_files.push(path);
+    }
+
+    /// Delivers the files dropped since the last call to the drop target under the cursor
+    pub fn flush_dropped_files(&mut self, frame: &mut Frame) -> bool {
+        if self.dropped_files.is_empty() {
+            return false;
+        }
+
+        let paths = mem::take(&mut self.dropped_files);
+        self.hovered_files.clear();
+        self.update_file_hover(frame);
+
+        let pos = self.pos;
+        if let Some((entity, local_pos)) =
+            find_widget_intersect(self.root, frame, pos, |v| v.has(on_file_drop()))
+        {
             return Self::propagate_event(
                 entity,
                 frame,
-                on_keyboard_input(),
-                KeyboardInput {
-                    modifiers: self.modifiers,
-                    state,
-                    key,
-                    text,
+                on_file_drop(),
+                FileDrop {
+                    paths,
+                    absolute_pos: pos,
+                    local_pos,
                 },
             );
         }
@@ -263,6 +410,85 @@ impl InputState {
         false
     }
 
+    /// Notifies drop targets of files entering, moving within, or leaving their bounds
+    fn update_file_hover(&mut self, frame: &mut Frame) -> bool {
+        let pos = self.pos;
+        let target = if self.hovered_files.is_empty() {
+            None
+        } else {
+            find_widget_intersect(self.root, frame, pos, |v| v.has(on_file_drop()))
+        };
+
+        let target_id = target.as_ref().map(|v| v.0.id());
+        let new_hover = self.file_hover_target != target_id;
+
+        if new_hover {
+            if let Some(prev) = self
+                .file_hover_target
+                .and_then(|v| frame.world.entity(v).ok())
+            {
+                let transform = prev.get_copy(screen_transform()).unwrap_or_default();
+                let rect = prev.get_copy(rect()).unwrap_or_default();
+
+                Self::propagate_event(
+                    prev,
+                    frame,
+                    on_file_hover(),
+                    FileHover {
+                        state: HoverState::Exited,
+                        paths: Vec::new(),
+                        absolute_pos: pos,
+                        local_pos: transform.inverse().transform_point3(pos.extend(0.0)).xy()
+                            - rect.min,
+                    },
+                );
+            }
+        }
+
+        self.file_hover_target = target_id;
+
+        if let Some((entity, local_pos)) = target {
+            return Self::propagate_event(
+                entity,
+                frame,
+                on_file_hover(),
+                FileHover {
+                    state: if new_hover {
+                        HoverState::Entered
+                    } else {
+                        HoverState::Moved
+                    },
+                    paths: self.hovered_files.clone(),
+                    absolute_pos: pos,
+                    local_pos,
+                },
+            );
+        }
+
+        false
+    }
+
+    /// Forwards input method composition events to the focused widget
+    pub fn on_ime(&mut self, frame: &mut Frame, ime: Ime) -> bool {
+        if let &Some(entity) = &self.get_focused(frame.world()) {
+            return Self::propagate_event(entity, frame, on_ime(), ime);
+        }
+
+        false
+    }
+
+    /// Returns true if the focused widget accepts input method composition
+    pub fn ime_allowed(&self) -> bool {
+        self.ime_allowed
+    }
+
+    /// Returns the latest screen space area of the text cursor requested by the focused widget.
+    ///
+    /// The input method uses this to place the candidate window next to the composed text.
+    pub fn update_ime_cursor_area(&mut self) -> Option<Rect> {
+        self.ime_cursor_area_rx.drain().last()
+    }
+
     pub fn focused(&self) -> Option<&FocusedEntity> {
         self.focused.as_ref()
     }
@@ -317,18 +543,19 @@ impl InputState {
             }
 
             let sticky = entity.has(keep_focus());
+            self.ime_allowed = entity.has(on_ime());
             self.focused = Some(FocusedEntity { id: new, sticky });
             if sticky {
                 self.last_sticky = Some(new);
             }
         } else {
-            self.focused = self
-                .last_sticky
-                .and_then(|v| frame.world.entity(v).ok())
-                .map(|v| FocusedEntity {
-                    id: v.id(),
-                    sticky: true,
-                });
+            let last_sticky = self.last_sticky.and_then(|v| frame.world.entity(v).ok());
+
+            self.ime_allowed = last_sticky.is_some_and(|v| v.has(on_ime()));
+            self.focused = last_sticky.map(|v| FocusedEntity {
+                id: v.id(),
+                sticky: true,
+            });
         }
     }
 }
@@ -372,6 +599,29 @@ pub struct Scroll {
     pub modifiers: ModifiersState,
 }
 
+/// Files dragged over a drop target from outside the application
+#[derive(Debug, Clone)]
+pub struct FileHover {
+    pub state: HoverState,
+    /// The dragged files. Empty when the files leave the widget
+    pub paths: Vec<PathBuf>,
+    /// Mouse cursor relative to the screen
+    pub absolute_pos: Vec2,
+    /// Mouse cursor relative to the bounds of the widget
+    pub local_pos: Vec2,
+}
+
+/// Files dropped onto a drop target from outside the application
+#[derive(Debug, Clone)]
+pub struct FileDrop {
+    pub paths: Vec<PathBuf>,
+    /// Mouse cursor relative to the screen
+    pub absolute_pos: Vec2,
+    /// Mouse cursor relative to the bounds of the widget
+    pub local_pos: Vec2,
+}
+
+#[derive(Debug, Clone)]
 pub struct KeyboardInput {
     pub modifiers: ModifiersState,
     pub state: ElementState,
@@ -385,13 +635,103 @@ declare_atom! {
     pub request_focus_sender: flume::Sender<Entity>,
 }
 
+declare_atom! {
+    /// Reports the screen space area of the text cursor to the platform input method
+    pub ime_cursor_area_sender: flume::Sender<Rect>,
+}
+
 flax::component! {
     pub keep_focus: (),
     pub interactive: (),
+    /// The mouse cursor to show when hovering the widget or any of its children
+    pub cursor_icon: CursorIcon,
     pub on_focus: InputEventHandler<bool>,
     pub on_cursor_move: InputEventHandler<CursorMove>,
     pub on_cursor_hover: InputEventHandler<CursorOver>,
     pub on_mouse_input: InputEventHandler<MouseInput>,
     pub on_keyboard_input: InputEventHandler<KeyboardInput>,
+    /// Keyboard input not consumed by the focused widget, received regardless of focus.
+    ///
+    /// Used for application wide shortcuts.
+    pub on_shortcut: InputEventHandler<KeyboardInput>,
+    /// Input method composition events. Presence enables the input method while focused.
+    pub on_ime: InputEventHandler<Ime>,
+    /// Files dropped onto the widget. Presence makes the widget a target for external files
+    pub on_file_drop: InputEventHandler<FileDrop>,
+    pub on_file_hover: InputEventHandler<FileHover>,
     pub on_scroll: InputEventHandler<Scroll>,
 }
+
+#[cfg(test)]
+mod test {
+    use glam::Mat4;
+
+    use super::*;
+    use crate::{
+        assets::AssetCache,
+        components::{children, screen_clip_mask},
+        executor::Executor,
+    };
+
+    fn spawn_widget(frame: &mut Frame, bounds: Rect, icon: CursorIcon) -> Entity {
+        Entity::builder()
+            .set(rect(), bounds)
+            .set(screen_transform(), Mat4::IDENTITY)
+            .set(screen_clip_mask(), Rect::from_size(Vec2::splat(1000.0)))
+            .set(cursor_icon(), icon)
+            .set(interactive(), ())
+            .spawn(frame.world_mut())
+    }
+
+    #[test]
+    fn cursor_icon_follows_pointer() {
+        let executor = Executor::new();
+        let mut frame = Frame::new(executor.spawner(), AssetCache::new(), World::new());
+
+        let input = spawn_widget(
+            &mut frame,
+            Rect::from_size_pos(Vec2::splat(100.0), Vec2::ZERO),
+            CursorIcon::Text,
+        );
+        frame.world_mut().set(input, keep_focus(), ()).unwrap();
+
+        let button = spawn_widget(
+            &mut frame,
+            Rect::from_size_pos(Vec2::splat(100.0), Vec2::new(200.0, 0.0)),
+            CursorIcon::Pointer,
+        );
+
+        let root = Entity::builder()
+            .set(children(), vec![input, button])
+            .spawn(frame.world_mut());
+
+        let (_, focus_rx) = flume::unbounded();
+        let (_, ime_rx) = flume::unbounded();
+        let mut state = InputState::new(root, Vec2::ZERO, focus_rx, ime_rx);
+
+        // Focus the text input, which keeps focus after the button is released
+        state.on_cursor_move(&mut frame, Vec2::splat(50.0));
+        state.on_mouse_input(&mut frame, ElementState::Pressed, MouseButton::Left);
+        state.on_mouse_input(&mut frame, ElementState::Released, MouseButton::Left);
+        assert_eq!(state.cursor_icon(), CursorIcon::Text);
+
+        state.on_cursor_move(&mut frame, Vec2::new(250.0, 50.0));
+        assert_eq!(state.cursor_icon(), CursorIcon::Pointer);
+
+        state.on_cursor_move(&mut frame, Vec2::new(150.0, 50.0));
+        assert_eq!(state.cursor_icon(), CursorIcon::Default);
+
+        // Dragging out of the focused widget keeps its cursor, even when another button is
+        // released meanwhile
+        state.on_cursor_move(&mut frame, Vec2::splat(50.0));
+        state.on_mouse_input(&mut frame, ElementState::Pressed, MouseButton::Left);
+        state.on_mouse_input(&mut frame, ElementState::Pressed, MouseButton::Right);
+        state.on_mouse_input(&mut frame, ElementState::Released, MouseButton::Right);
+        state.on_cursor_move(&mut frame, Vec2::new(250.0, 50.0));
+        assert_eq!(state.cursor_icon(), CursorIcon::Text);
+
+        // Releasing the capture shows the cursor of the hovered widget without moving
+        state.on_mouse_input(&mut frame, ElementState::Released, MouseButton::Left);
+        assert_eq!(state.cursor_icon(), CursorIcon::Pointer);
+    }
+}
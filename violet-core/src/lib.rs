@@ -1,6 +1,7 @@
 pub mod animation;
 pub mod assets;
 pub mod atom;
+pub mod command;
 pub mod components;
 pub mod constraints;
 pub mod editor;
@@ -16,6 +16,7 @@ use crate::{
     },
     input::interactive,
     shape::shape_rectangle,
+    text::FontFamily,
     unit::Unit,
     Edges, Scope,
 };
@@ -335,6 +336,10 @@ pub struct IconSet {
     pub check: String,
     pub ellipsis: String,
     pub search: String,
+    /// Reveals hidden content, such as a password
+    pub show: String,
+    /// Hides revealed content
+    pub hide: String,
 }
 
 impl Default for IconSet {
@@ -348,6 +353,8 @@ impl Default for IconSet {
             check: "✓".to_string(),
             ellipsis: "⋯".to_string(),
             search: ">".to_string(),
+            show: "◉".to_string(),
+            hide: "◎".to_string(),
         }
     }
 }
@@ -358,6 +365,8 @@ pub struct StylesheetOptions {
     pub colors: ColorSchemeConfig,
     pub base_spacing: f32,
     pub base_text_size: f32,
+    /// Fonts to use for characters which are missing from the font of the text, in order
+    pub font_fallback: Vec<FontFamily>,
 }
 
 impl StylesheetOptions {
@@ -385,6 +394,14 @@ impl StylesheetOptions {
         self
     }
 
+    pub fn with_font_fallback(
+        mut self,
+        font_fallback: impl IntoIterator<Item = FontFamily>,
+    ) -> Self {
+        self.font_fallback = font_fallback.into_iter().collect();
+        self
+    }
+
     /// Build the stylesheet entity
     pub fn build(self) -> EntityBuilder {
         let mut builder = Entity::builder();
@@ -415,6 +432,7 @@ impl StylesheetOptions {
             .set(text_small(), self.base_text_size)
             .set(text_medium(), self.base_text_size * 1.25)
             .set(text_large(), self.base_text_size * 1.5)
+            .set(font_fallback(), self.font_fallback)
             // icons
             .set(icon_chevron(), self.icons.chevron)
             .set(icon_spinner(), self.icons.spinner)
@@ -423,7 +441,9 @@ impl StylesheetOptions {
             .set(icon_info(), self.icons.info)
             .set(icon_check(), self.icons.check)
             .set(icon_ellipsis(), self.icons.ellipsis)
-            .set(icon_search(), self.icons.search);
+            .set(icon_search(), self.icons.search)
+            .set(icon_show(), self.icons.show)
+            .set(icon_hide(), self.icons.hide);
 
         builder
     }
@@ -436,6 +456,7 @@ impl Default for StylesheetOptions {
             colors: ColorSchemeConfig::default(),
             base_spacing: 4.0,
             base_text_size: 16.0,
+            font_fallback: Vec::new(),
         }
     }
 }
@@ -468,10 +489,13 @@ flax::component! {
     pub icon_check: String,
     pub icon_ellipsis: String,
     pub icon_search: String,
+    pub icon_show: String,
+    pub icon_hide: String,
 
     pub text_small: f32,
     pub text_medium: f32,
     pub text_large: f32,
+    pub font_fallback: Vec<FontFamily>,
 
 
 }
@@ -0,0 +1,60 @@
+//! Fonts loaded at runtime through the asset system.
+//!
+//! ```ignore
+//! let font: Asset<FontData> = scope.assets_mut().try_load(Path::new("assets/NotoSansJP.ttf"))?;
+//! load_font(scope.frame(), font);
+//!
+//! Text::formatted([TextSegment::new("こんにちは").with_family("Noto Sans JP")])
+//! ```
+//!
+//! Text using [`FontFamily::named`](super::FontFamily::named) is laid out again once the font
+//! arrives.
+
+use std::{path::Path, sync::Arc};
+
+use crate::{
+    assets::{Asset, AssetCache, AssetKey},
+    declare_atom, Frame,
+};
+
+/// The contents of a font file, such as a `.ttf` or `.otf`
+pub struct FontData {
+    data: Arc<Vec<u8>>,
+}
+
+impl FontData {
+    pub fn new(data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            data: Arc::new(data.into()),
+        }
+    }
+
+    pub fn data(&self) -> &Arc<Vec<u8>> {
+        &self.data
+    }
+}
+
+impl AssetKey<FontData> for Path {
+    type Error = std::io::Error;
+
+    fn load(&self, assets: &AssetCache) -> Result<Asset<FontData>, Self::Error> {
+        Ok(assets.insert(FontData::new(std::fs::read(self)?)))
+    }
+}
+
+declare_atom! {
+    /// Fonts to add to the text system
+    pub load_font_sender: flume::Sender<Asset<FontData>>,
+}
+
+/// Adds a font to the text system.
+///
+/// The font becomes available by the family names it declares, and as a fallback.
+pub fn load_font(frame: &Frame, font: Asset<FontData>) {
+    match frame.get_atom(load_font_sender()) {
+        Some(sender) => {
+            sender.send(font).ok();
+        }
+        None => tracing::warn!("No text system to load fonts into"),
+    }
+}
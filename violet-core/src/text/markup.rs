@@ -0,0 +1,449 @@
+//! Lightweight markup for styling text without building segments by hand.
+//!
+//! Tags use square brackets and are closed in the reverse order they were opened:
+//!
+//! - `[b]bold[/b]`, `[i]italic[/i]` and `[weight=300]light[/weight]`
+//! - `[u]underlined[/u]` and `[s]struck through[/s]`
+//! - `[color=#e0a040]colored[/color]`, with an optional alpha component
+//! - `[font=monospace]code[/font]`, using a generic family or a font name
+//! - `[size=20]large[/size]`
+//! - `[url=https://example.com]link[/url]`
+//!
+//! A literal `[` is written as `[[`. Values containing `]` are quoted, such as
+//! `[font="Name [Bold]"]`, where `\"` and `\\` stand for a literal quote and backslash.
+
+use std::{
+    borrow::Cow,
+    fmt::{Display, Write},
+};
+
+use palette::Srgba;
+
+use super::{FontFamily, Style, TextSegment, UnderlineStyle, Weight};
+use crate::style::ValueOrRef;
+
+/// Error produced when parsing invalid markup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkupError {
+    /// Byte offset of the error in the markup
+    pub position: usize,
+    pub message: String,
+}
+
+impl MarkupError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for MarkupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for MarkupError {}
+
+/// Parses markup into segments of rich text.
+///
+/// Each run of text between two tags becomes a separate segment, even when neighbouring runs
+/// have the same style.
+pub fn parse(markup: &str) -> Result<Vec<TextSegment>, MarkupError> {
+    let mut segments = Vec::new();
+    // Open tags and the style of the text within them
+    let mut stack: Vec<(&str, TextSegment)> = Vec::new();
+    let mut text = String::new();
+    let mut i = 0;
+
+    while let Some(start) = markup[i..].find('[').map(|v| i + v) {
+        text.push_str(&markup[i..start]);
+
+        if markup[start + 1..].starts_with('[') {
+            text.push('[');
+            i = start + 2;
+            continue;
+        }
+
+        let end = tag_end(&markup[start..])
+            .map(|v| start + v)
+            .ok_or_else(|| MarkupError::new(start, "Unterminated tag"))?;
+
+        let tag = &markup[start + 1..end];
+        i = end + 1;
+
+        if !text.is_empty() {
+            segments.push(TextSegment {
+                text: std::mem::take(&mut text),
+                ..current_style(&stack)
+            });
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            match stack.pop() {
+                Some((open, _)) if open == name => {}
+                Some((open, _)) => {
+                    return Err(MarkupError::new(
+                        start,
+                        format!("Expected [/{open}], found [/{name}]"),
+                    ))
+                }
+                None => {
+                    return Err(MarkupError::new(
+                        start,
+                        format!("Unexpected closing tag [/{name}]"),
+                    ))
+                }
+            }
+        } else {
+            let (name, value) = match tag.split_once('=') {
+                Some((name, value)) => (
+                    name,
+                    Some(unquote(value).map_err(|message| MarkupError::new(start, message))?),
+                ),
+                None => (tag, None),
+            };
+
+            let style = apply_tag(current_style(&stack), name, value.as_deref())
+                .map_err(|message| MarkupError::new(start, message))?;
+
+            stack.push((name, style));
+        }
+    }
+
+    if let Some((open, _)) = stack.last() {
+        return Err(MarkupError::new(
+            markup.len(),
+            format!("Unclosed tag [{open}]"),
+        ));
+    }
+
+    text.push_str(&markup[i..]);
+    if !text.is_empty() {
+        segments.push(TextSegment::new(text));
+    }
+
+    Ok(segments)
+}
+
+/// Returns the offset of the `]` closing the tag at the start of `tag`, skipping quoted values
+fn tag_end(tag: &str) -> Option<usize> {
+    // A value is quoted if it starts with a quote
+    let value_start = tag.find('=').map(|v| v + 1);
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (i, c) in tag.char_indices() {
+        if quoted {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                quoted = false;
+            }
+        } else if c == '"' && Some(i) == value_start {
+            quoted = true;
+        } else if c == ']' {
+            return Some(i);
+        }
+    }
+
+    None
+}
+
+/// Removes the quotes and escapes of a quoted tag value
+fn unquote(value: &str) -> Result<Cow<'_, str>, String> {
+    let Some(quoted) = value.strip_prefix('"') else {
+        return Ok(Cow::Borrowed(value));
+    };
+
+    let invalid = || format!("Invalid quoted value {value:?}");
+
+    let mut result = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.push(chars.next().ok_or_else(invalid)?),
+            '"' if chars.as_str().is_empty() => return Ok(Cow::Owned(result)),
+            '"' => return Err(invalid()),
+            c => result.push(c),
+        }
+    }
+
+    Err(invalid())
+}
+
+/// Quotes a tag value if it would otherwise end the tag early
+fn quote(value: &str) -> Cow<'_, str> {
+    if !value.contains(']') && !value.starts_with('"') {
+        return Cow::Borrowed(value);
+    }
+
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    Cow::Owned(format!("\"{escaped}\""))
+}
+
+fn current_style(stack: &[(&str, TextSegment)]) -> TextSegment {
+    stack
+        .last()
+        .map(|(_, v)| v.clone())
+        .unwrap_or_else(|| TextSegment::new(""))
+}
+
+fn apply_tag(segment: TextSegment, name: &str, value: Option<&str>) -> Result<TextSegment, String> {
+    let value = || value.ok_or_else(|| format!("Missing value for [{name}]"));
+
+    let segment = match name {
+        "b" => segment.with_weight(Weight::BOLD),
+        "i" => segment.with_style(Style::Italic),
+        "u" => segment.with_underline(UnderlineStyle::Solid),
+        "s" => segment.with_strikethrough(),
+        "weight" => {
+            let weight = value()?
+                .parse()
+                .map_err(|_| format!("Invalid weight {:?}", value().unwrap_or_default()))?;
+            segment.with_weight(Weight(weight))
+        }
+        "color" => segment.with_color(parse_color(value()?)?),
+        "font" => segment.with_family(parse_family(value()?)),
+        "size" => {
+            let size = value()?
+                .parse()
+                .map_err(|_| format!("Invalid size {:?}", value().unwrap_or_default()))?;
+            segment.with_font_size(size)
+        }
+        "url" => segment.with_link(value()?),
+        _ => return Err(format!("Unknown tag [{name}]")),
+    };
+
+    Ok(segment)
+}
+
+/// Parses a `#rrggbb` or `#rrggbbaa` color
+fn parse_color(value: &str) -> Result<Srgba, String> {
+    let invalid = || format!("Invalid color {value:?}");
+
+    let hex = value.strip_prefix('#').ok_or_else(invalid)?;
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+        return Err(invalid());
+    }
+
+    let channels = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Srgba::new(
+        channels[0],
+        channels[1],
+        channels[2],
+        channels.get(3).copied().unwrap_or(255),
+    )
+    .into_format())
+}
+
+fn parse_family(value: &str) -> FontFamily {
+    match value {
+        "serif" => FontFamily::Serif,
+        "sans-serif" => FontFamily::SansSerif,
+        "cursive" => FontFamily::Cursive,
+        "fantasy" => FontFamily::Fantasy,
+        "monospace" => FontFamily::Monospace,
+        name => FontFamily::named(name.to_string()),
+    }
+}
+
+/// Converts segments into markup which parses back into the same segments.
+///
+/// Colors referring to the stylesheet, highlights and dotted or wavy underlines can not be
+/// expressed in markup and are left out.
+pub fn to_markup(segments: &[TextSegment]) -> String {
+    let mut markup = String::new();
+
+    for segment in segments {
+        let mut tags = Vec::new();
+
+        if segment.weight == Weight::BOLD {
+            tags.push(("b", None));
+        } else if segment.weight != Weight::NORMAL {
+            tags.push(("weight", Some(segment.weight.0.to_string())));
+        }
+
+        if segment.style != Style::Normal {
+            tags.push(("i", None));
+        }
+
+        if segment.underline == Some(UnderlineStyle::Solid) {
+            tags.push(("u", None));
+        }
+
+        if segment.strikethrough {
+            tags.push(("s", None));
+        }
+
+        if let Some(ValueOrRef::Value(color)) = segment.color {
+            let color: palette::Srgba<u8> = color.into_format();
+            let mut hex = format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue);
+            if color.alpha != 255 {
+                write!(hex, "{:02x}", color.alpha).unwrap();
+            }
+
+            tags.push(("color", Some(hex)));
+        }
+
+        if !matches!(segment.family, FontFamily::SansSerif) {
+            tags.push(("font", Some(segment.family.to_string())));
+        }
+
+        if let Some(size) = segment.font_size {
+            tags.push(("size", Some(size.to_string())));
+        }
+
+        if let Some(link) = &segment.link {
+            tags.push(("url", Some(link.clone())));
+        }
+
+        for (name, value) in &tags {
+            match value {
+                Some(value) => write!(markup, "[{name}={}]", quote(value)).unwrap(),
+                None => write!(markup, "[{name}]").unwrap(),
+            }
+        }
+
+        markup.push_str(&segment.text.replace('[', "[["));
+
+        for (name, _) in tags.iter().rev() {
+            write!(markup, "[/{name}]").unwrap();
+        }
+    }
+
+    markup
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn describe(segments: &[TextSegment]) -> Vec<String> {
+        segments
+            .iter()
+            .map(|v| {
+                format!(
+                    "{:?} {:?} {} {:?} {} {:?} {:?} {:?} {}",
+                    v.text,
+                    v.style,
+                    v.weight.0,
+                    v.color,
+                    v.family,
+                    v.font_size,
+                    v.link,
+                    v.underline,
+                    v.strikethrough
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_tags() {
+        let segments = parse(
+            "Hello [b]bold [i]and italic[/i][/b] [[not a tag] [color=#ff000080]red[/color] \
+             [font=monospace][size=20]code[/size][/font] [url=https://example.com]link[/url]",
+        )
+        .unwrap();
+
+        let expected = [
+            TextSegment::new("Hello "),
+            TextSegment::new("bold ").with_weight(Weight::BOLD),
+            TextSegment::new("and italic")
+                .with_weight(Weight::BOLD)
+                .with_style(Style::Italic),
+            TextSegment::new(" [not a tag] "),
+            TextSegment::new("red")
+                .with_color(Srgba::new(255u8, 0, 0, 128).into_format::<f32, f32>()),
+            TextSegment::new(" "),
+            TextSegment::new("code")
+                .with_family(FontFamily::Monospace)
+                .with_font_size(20.0),
+            TextSegment::new(" "),
+            TextSegment::new("link").with_link("https://example.com"),
+        ];
+
+        assert_eq!(describe(&segments), describe(&expected));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("[b]bold").unwrap_err().message, "Unclosed tag [b]");
+        assert_eq!(
+            parse("[b][i]x[/b][/i]").unwrap_err(),
+            MarkupError::new(7, "Expected [/i], found [/b]")
+        );
+        assert_eq!(parse("x[/b]").unwrap_err().position, 1);
+        assert_eq!(
+            parse("[color=red]x[/color]").unwrap_err().message,
+            "Invalid color \"red\""
+        );
+        assert_eq!(
+            parse("[blink]x[/blink]").unwrap_err().message,
+            "Unknown tag [blink]"
+        );
+        assert_eq!(parse("[b").unwrap_err().message, "Unterminated tag");
+    }
+
+    #[test]
+    fn round_trip() {
+        let segments = [
+            TextSegment::new("plain [text] "),
+            TextSegment::new("styled")
+                .with_weight(Weight::LIGHT)
+                .with_style(Style::Italic)
+                .with_color(Srgba::new(0x33u8, 0x66, 0x99, 0xff).into_format::<f32, f32>())
+                .with_family("Fira Code")
+                .with_font_size(12.5),
+            TextSegment::new(" and a ").with_weight(Weight::BOLD),
+            TextSegment::new("struck").with_strikethrough(),
+            TextSegment::new("underlined").with_underline(UnderlineStyle::Solid),
+            TextSegment::new("link").with_link("https://example.com/?q=1"),
+        ];
+
+        let markup = to_markup(&segments);
+        assert_eq!(
+            markup,
+            "plain [[text] [weight=300][i][color=#336699][font=Fira Code][size=12.5]styled\
+             [/size][/font][/color][/i][/weight][b] and a [/b][s]struck[/s][u]underlined[/u]\
+             [url=https://example.com/?q=1]link[/url]"
+        );
+
+        assert_eq!(describe(&parse(&markup).unwrap()), describe(&segments));
+    }
+
+    #[test]
+    fn quoted_values() {
+        let segments = [
+            TextSegment::new("link").with_link("https://example.com/?q=[a]"),
+            TextSegment::new("font").with_family(r#""Quoted" \ [Bold]"#),
+            TextSegment::new("plain").with_link(r#"a"b\c"#),
+        ];
+
+        let markup = to_markup(&segments);
+        assert_eq!(
+            markup,
+            r#"[url="https://example.com/?q=[a]"]link[/url][font="\"Quoted\" \\ [Bold]"]font[/font][url=a"b\c]plain[/url]"#
+        );
+
+        assert_eq!(describe(&parse(&markup).unwrap()), describe(&segments));
+
+        assert_eq!(
+            parse(r#"[url="a]x[/url]"#).unwrap_err().message,
+            "Unterminated tag"
+        );
+        assert_eq!(
+            parse(r#"[url="a"b"]x[/url]"#).unwrap_err().message,
+            r#"Invalid quoted value "\"a\"b\"""#
+        );
+    }
+}
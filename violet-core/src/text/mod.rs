@@ -0,0 +1,775 @@
+pub mod font;
+pub mod markup;
+
+use std::{
+    borrow::{Borrow, Cow},
+    fmt::Display,
+    ops::Index,
+};
+
+pub use cosmic_text::{fontdb::Source, Style, Weight, Wrap};
+use glam::{vec2, Vec2};
+use itertools::Itertools;
+use palette::Srgba;
+
+use crate::{style::ValueOrRef, Rect};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+// Inspired by: https://github.com/pop-os/cosmic-text
+pub enum FontFamily {
+    Named(Cow<'static, str>),
+
+    /// Serif fonts represent the formal text style for a script.
+    Serif,
+
+    /// Glyphs in sans-serif fonts, as the term is used in CSS, are generally low contrast
+    /// and have stroke endings that are plain — without any flaring, cross stroke,
+    /// or other ornamentation.
+    SansSerif,
+
+    /// Glyphs in cursive fonts generally use a more informal script style,
+    /// and the result looks more like handwritten pen or brush writing than printed letterwork.
+    Cursive,
+
+    /// Fantasy fonts are primarily decorative or expressive fonts that
+    /// contain decorative or expressive representations of characters.
+    Fantasy,
+
+    /// The sole criterion of a monospace font is that all glyphs have the same fixed width.
+    Monospace,
+}
+
+impl Display for FontFamily {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FontFamily::Named(name) => write!(f, "{name}"),
+            FontFamily::Serif => write!(f, "serif"),
+            FontFamily::SansSerif => write!(f, "sans-serif"),
+            FontFamily::Cursive => write!(f, "cursive"),
+            FontFamily::Fantasy => write!(f, "fantasy"),
+            FontFamily::Monospace => write!(f, "monospace"),
+        }
+    }
+}
+
+impl FontFamily {
+    pub fn named(name: impl Into<Cow<'static, str>>) -> Self {
+        Self::Named(name.into())
+    }
+}
+
+impl<T> From<T> for FontFamily
+where
+    T: Into<Cow<'static, str>>,
+{
+    fn from(value: T) -> Self {
+        Self::named(value)
+    }
+}
+
+/// How text which does not fit within its bounds is shortened
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextOverflow {
+    /// The text is not shortened and may overflow its bounds.
+    ///
+    /// Lines past the maximum line count are still cut off.
+    #[default]
+    Visible,
+    /// Replace the end of the text with an ellipsis
+    EllipsisEnd,
+    /// Replace the middle of the text with an ellipsis, keeping both ends visible.
+    ///
+    /// Useful for file paths
+    EllipsisMiddle,
+    /// Replace the start of the text with an ellipsis
+    EllipsisStart,
+}
+
+/// Style of a line drawn under text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnderlineStyle {
+    Solid,
+    Dotted,
+    /// A wavy squiggle, such as for spelling errors
+    Wavy,
+}
+
+/// An OpenType feature applied when shaping text, such as `tnum` for tabular numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FontFeature {
+    pub tag: [u8; 4],
+    /// `0` disables the feature, while `1` enables it. Some features select between several
+    /// alternates using higher values.
+    pub value: u32,
+}
+
+impl FontFeature {
+    /// Digits of equal width, which keeps numbers aligned in columns
+    pub const TABULAR_NUMBERS: Self = Self::enable(b"tnum");
+    /// Digits of varying width
+    pub const PROPORTIONAL_NUMBERS: Self = Self::enable(b"pnum");
+    pub const SMALL_CAPS: Self = Self::enable(b"smcp");
+    pub const STANDARD_LIGATURES: Self = Self::enable(b"liga");
+    pub const CONTEXTUAL_LIGATURES: Self = Self::enable(b"clig");
+    /// Slashed zero
+    pub const SLASHED_ZERO: Self = Self::enable(b"zero");
+
+    pub const fn new(tag: &[u8; 4], value: u32) -> Self {
+        Self { tag: *tag, value }
+    }
+
+    pub const fn enable(tag: &[u8; 4]) -> Self {
+        Self::new(tag, 1)
+    }
+
+    pub const fn disable(tag: &[u8; 4]) -> Self {
+        Self::new(tag, 0)
+    }
+
+    /// Returns the same feature with a different value
+    pub const fn with_value(self, value: u32) -> Self {
+        Self { value, ..self }
+    }
+}
+
+/// A segment of rich text
+#[derive(Debug, Clone)]
+pub struct TextSegment {
+    pub text: String,
+    pub family: FontFamily,
+    pub style: Style,
+    pub weight: Weight,
+    pub color: Option<ValueOrRef<Srgba>>,
+    /// Overrides the font size of the text
+    pub font_size: Option<f32>,
+    /// Target of a hyperlink
+    pub link: Option<String>,
+    pub underline: Option<UnderlineStyle>,
+    pub strikethrough: bool,
+    /// Background color behind the text
+    pub highlight: Option<ValueOrRef<Srgba>>,
+    /// Color of the underline and strikethrough, defaults to the text color
+    pub decoration_color: Option<ValueOrRef<Srgba>>,
+    /// Extra space between characters, in multiples of the font size
+    pub letter_spacing: Option<f32>,
+    /// OpenType features to enable or disable. Later features take precedence.
+    pub features: Vec<FontFeature>,
+}
+
+impl TextSegment {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            family: FontFamily::SansSerif,
+            style: Style::Normal,
+            weight: Weight::NORMAL,
+            color: None,
+            font_size: None,
+            link: None,
+            underline: None,
+            strikethrough: false,
+            highlight: None,
+            decoration_color: None,
+            letter_spacing: None,
+            features: Vec::new(),
+        }
+    }
+
+    pub fn with_family(mut self, family: impl Into<FontFamily>) -> Self {
+        self.family = family.into();
+        self
+    }
+
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_weight(mut self, weight: Weight) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn with_color(mut self, color: impl Into<ValueOrRef<Srgba>>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = Some(font_size);
+        self
+    }
+
+    pub fn with_link(mut self, link: impl Into<String>) -> Self {
+        self.link = Some(link.into());
+        self
+    }
+
+    pub fn with_underline(mut self, underline: UnderlineStyle) -> Self {
+        self.underline = Some(underline);
+        self
+    }
+
+    pub fn with_strikethrough(mut self) -> Self {
+        self.strikethrough = true;
+        self
+    }
+
+    pub fn with_highlight(mut self, color: impl Into<ValueOrRef<Srgba>>) -> Self {
+        self.highlight = Some(color.into());
+        self
+    }
+
+    pub fn with_decoration_color(mut self, color: impl Into<ValueOrRef<Srgba>>) -> Self {
+        self.decoration_color = Some(color.into());
+        self
+    }
+
+    /// Set the extra space between characters, in multiples of the font size
+    pub fn with_letter_spacing(mut self, letter_spacing: f32) -> Self {
+        self.letter_spacing = Some(letter_spacing);
+        self
+    }
+
+    pub fn with_feature(mut self, feature: FontFeature) -> Self {
+        self.features.push(feature);
+        self
+    }
+
+    /// Use digits of equal width, so that numbers don't jitter when changing
+    pub fn with_tabular_numbers(self) -> Self {
+        self.with_feature(FontFeature::TABULAR_NUMBERS)
+    }
+
+    pub fn with_small_caps(self) -> Self {
+        self.with_feature(FontFeature::SMALL_CAPS)
+    }
+
+    /// Enable or disable standard and contextual ligatures
+    pub fn with_ligatures(self, enabled: bool) -> Self {
+        let value = enabled as u32;
+        self.with_feature(FontFeature::STANDARD_LIGATURES.with_value(value))
+            .with_feature(FontFeature::CONTEXTUAL_LIGATURES.with_value(value))
+    }
+}
+
+/// Returns the index of the segment containing the text at `loc`.
+///
+/// Rows and columns refer to the text of all segments joined together, as laid out.
+pub fn segment_at(segments: &[TextSegment], loc: CursorLocation) -> Option<usize> {
+    let mut row = 0;
+    let mut col = 0;
+
+    for (i, segment) in segments.iter().enumerate() {
+        for (j, line) in segment.text.split('\n').enumerate() {
+            if j > 0 {
+                row += 1;
+                col = 0;
+            }
+
+            if row == loc.row && (col..col + line.len()).contains(&loc.col) {
+                return Some(i);
+            }
+
+            col += line.len();
+        }
+
+        if row > loc.row {
+            break;
+        }
+    }
+
+    None
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutGlyph {
+    /// Index within a row.
+    ///
+    /// As a row may be broken into multiple lines, the vector index may no be the same as the
+    /// glyphs index withing the row.
+    pub index: usize,
+    pub start: usize,
+    pub end: usize,
+    pub bounds: Rect,
+    /// The glyph is part of right-to-left text, meaning its logical start is on the right
+    pub rtl: bool,
+}
+
+impl LayoutGlyph {
+    /// Returns the text offset of the left edge of the glyph
+    pub fn left_col(&self) -> usize {
+        if self.rtl {
+            self.end
+        } else {
+            self.start
+        }
+    }
+
+    /// Returns the text offset of the right edge of the glyph
+    pub fn right_col(&self) -> usize {
+        if self.rtl {
+            self.start
+        } else {
+            self.end
+        }
+    }
+}
+
+/// Returns the horizontal position following the glyph in text order
+fn trailing_edge(glyph: &LayoutGlyph) -> f32 {
+    if glyph.rtl {
+        glyph.bounds.min.x
+    } else {
+        glyph.bounds.max.x
+    }
+}
+
+/// Returns the edges of each glyph in visual order along with their text offset, followed by the
+/// end of the line
+fn caret_stops(line: &LayoutLineGlyphs) -> impl Iterator<Item = (f32, usize)> + '_ {
+    line.glyphs
+        .iter()
+        .flat_map(|v| {
+            [
+                (v.bounds.min.x, v.left_col()),
+                (v.bounds.max.x, v.right_col()),
+            ]
+        })
+        .chain([(line.bounds.max.x, line.end)])
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutLineGlyphs {
+    pub row: usize,
+    /// Bounds relative to the line itself
+    pub bounds: Rect,
+    pub start: usize,
+    pub end: usize,
+    pub glyphs: Vec<LayoutGlyph>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutGlyphs {
+    pub rows: Vec<Vec<LayoutLineGlyphs>>,
+    pub line_height: f32,
+}
+
+impl Default for LayoutGlyphs {
+    fn default() -> Self {
+        Self {
+            rows: Vec::new(),
+            line_height: 0.0,
+        }
+    }
+}
+
+impl LayoutGlyphs {
+    pub fn new(rows: Vec<Vec<LayoutLineGlyphs>>, line_height: f32) -> Self {
+        Self { rows, line_height }
+    }
+
+    pub fn set_row(&mut self, row: usize, mut lines: Vec<LayoutLineGlyphs>) {
+        self.rows
+            .extend((self.rows.len()..=row).map(|_| Vec::new()));
+
+        lines.iter_mut().for_each(|v| v.row = row);
+        self.rows[row] = lines;
+    }
+
+    /// Inserts an empty row, shifting the following rows down
+    pub fn insert_row(&mut self, row: usize) {
+        if row <= self.rows.len() {
+            self.rows.insert(row, Vec::new());
+            self.renumber_rows(row);
+        }
+    }
+
+    /// Removes a row, shifting the following rows up
+    pub fn remove_row(&mut self, row: usize) {
+        if row < self.rows.len() {
+            self.rows.remove(row);
+            self.renumber_rows(row);
+        }
+    }
+
+    fn renumber_rows(&mut self, start: usize) {
+        for (row, lines) in self.rows.iter_mut().enumerate().skip(start) {
+            lines.iter_mut().for_each(|v| v.row = row);
+        }
+    }
+
+    pub fn hit(&self, pos: Vec2) -> Option<CursorLocation> {
+        self.rows
+            .iter()
+            .flatten()
+            .enumerate()
+            .find(|&(ln, _)| {
+                let h = ln as f32 * self.line_height;
+                pos.y >= h && pos.y <= h + self.line_height
+            })
+            .map(|(_, line)| {
+                if let Some(glyph) = line
+                    .glyphs
+                    .iter()
+                    .find(|v| pos.x >= v.bounds.min.x && pos.x <= v.bounds.max.x)
+                {
+                    let (x, col) = if pos.x > glyph.bounds.min.x + glyph.bounds.size().x / 2.0 {
+                        (glyph.bounds.max.x, glyph.right_col())
+                    } else {
+                        (glyph.bounds.min.x, glyph.left_col())
+                    };
+
+                    // Offsets at a direction boundary are only displayed on one side of it, so
+                    // prefer the offset which is displayed at the edge
+                    let col = caret_stops(line)
+                        .filter(|&(stop, _)| (stop - x).abs() < 0.5)
+                        .map(|(_, col)| col)
+                        .find(|&col| {
+                            self.caret_position(CursorLocation::new(line.row, col))
+                                .is_some_and(|v| (v.x - x).abs() < 0.5)
+                        })
+                        .unwrap_or(col);
+
+                    CursorLocation::new(line.row, col)
+                } else if pos.x > line.bounds.max.x {
+                    // place eol
+                    CursorLocation::new(line.row, line.end)
+                } else {
+                    CursorLocation::new(line.row, line.start)
+                }
+            })
+    }
+
+    /// Returns the location of the glyph directly under `pos`, if any
+    pub fn glyph_at(&self, pos: Vec2) -> Option<CursorLocation> {
+        let (_, line) = self.lines().enumerate().find(|&(ln, _)| {
+            let h = ln as f32 * self.line_height;
+            pos.y >= h && pos.y <= h + self.line_height
+        })?;
+
+        let glyph = line
+            .glyphs
+            .iter()
+            .find(|v| pos.x >= v.bounds.min.x && pos.x <= v.bounds.max.x)?;
+
+        Some(CursorLocation::new(line.row, glyph.start))
+    }
+
+    /// Returns the line and glyph index for the given cursor location
+    pub fn to_glyph_boundary(&self, cursor: CursorLocation) -> Option<Vec2> {
+        for (ln, line) in self.find_lines_indices(cursor.row) {
+            let y = ln as f32 * self.line_height;
+            let prev = line.glyphs.iter().find(|v| v.end == cursor.col);
+
+            if let Some(glyph) = line.glyphs.iter().find(|v| v.start == cursor.col) {
+                // Between two runs of different direction the offset is at both ends of the
+                // boundary. Place it after the preceding text, which is where typed text appears.
+                let x = match prev {
+                    Some(prev) if prev.rtl != glyph.rtl => trailing_edge(prev),
+                    _ if glyph.rtl => glyph.bounds.max.x,
+                    _ => glyph.bounds.min.x,
+                };
+
+                return Some(vec2(x, y));
+            }
+
+            // The end of right-to-left text is on the left side of its last glyph, except at the
+            // end of a line starting with left-to-right text, which is on the right of the line
+            if let Some(prev) = prev.filter(|v| v.rtl) {
+                let ltr_line = line.glyphs.first().is_some_and(|v| !v.rtl);
+                let x = if ltr_line && cursor.col == line.end {
+                    line.bounds.max.x
+                } else {
+                    trailing_edge(prev)
+                };
+
+                return Some(vec2(x, y));
+            }
+
+            // Account for end-of-run whitespace which are not present as glyphs in the final
+            // layout.
+            if let (Some(last_glyph), Some(next_line)) =
+                (line.glyphs.last(), self.lines().nth(ln + 1))
+            {
+                if next_line
+                    .glyphs
+                    .first()
+                    .is_some_and(|v| v.start == cursor.col + 1)
+                {
+                    return Some(vec2(last_glyph.bounds.max.x, y));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the position of the caret at `cursor`, placing it at the end of the row if it is
+    /// not on a glyph boundary
+    fn caret_position(&self, cursor: CursorLocation) -> Option<Vec2> {
+        self.to_glyph_boundary(cursor).or_else(|| {
+            self.find_lines_indices(cursor.row)
+                .last()
+                .map(|(ln, line)| vec2(line.bounds.max.x, ln as f32 * self.line_height))
+        })
+    }
+
+    /// Moves the cursor to the next glyph boundary to the left or right on screen.
+    ///
+    /// Continues on the following or preceding visual line when there are no more boundaries
+    /// in that direction.
+    ///
+    /// Returns `None` if the row contains no right-to-left text, as visual and logical movement
+    /// are then the same.
+    pub fn visual_move(&self, cursor: CursorLocation, right: bool) -> Option<CursorLocation> {
+        if !self
+            .find_lines(cursor.row)
+            .flat_map(|v| &v.glyphs)
+            .any(|v| v.rtl)
+        {
+            return None;
+        }
+
+        let pos = self.caret_position(cursor)?;
+        let ln = (pos.y / self.line_height.max(1.0)).round() as usize;
+        let line = self.lines().nth(ln)?;
+
+        let is_beyond = |x: f32| {
+            if right {
+                x > pos.x + 0.5
+            } else {
+                x < pos.x - 0.5
+            }
+        };
+
+        // Caret stops in visual order. As offsets at a direction boundary are only displayed on
+        // one side of it, stops are skipped unless the caret would actually move that way.
+        let stops = caret_stops(line).collect_vec();
+
+        let is_next = |&&(x, col): &&(f32, usize)| {
+            col != cursor.col
+                && is_beyond(x)
+                && self
+                    .caret_position(CursorLocation::new(line.row, col))
+                    .is_some_and(|v| is_beyond(v.x))
+        };
+
+        let next = if right {
+            stops.iter().find(is_next)
+        } else {
+            stops.iter().rev().find(is_next)
+        };
+
+        if let Some(&(_, col)) = next {
+            return Some(CursorLocation::new(line.row, col));
+        }
+
+        let adjacent = if right {
+            self.lines().nth(ln + 1)
+        } else {
+            ln.checked_sub(1).and_then(|ln| self.lines().nth(ln))
+        };
+
+        let Some(adjacent) = adjacent else {
+            return Some(cursor);
+        };
+
+        let col = if right {
+            adjacent.glyphs.first().map(LayoutGlyph::left_col)
+        } else {
+            adjacent.glyphs.last().map(LayoutGlyph::right_col)
+        };
+
+        Some(CursorLocation::new(
+            adjacent.row,
+            col.unwrap_or(adjacent.start),
+        ))
+    }
+
+    /// Returns all layout lines for the specified row
+    pub fn find_lines(&self, row: usize) -> impl Iterator<Item = &LayoutLineGlyphs> {
+        self.rows.get(row).into_iter().flatten()
+    }
+
+    pub fn find_lines_indices(
+        &self,
+        row: usize,
+    ) -> impl Iterator<Item = (usize, &LayoutLineGlyphs)> {
+        self.rows
+            .iter()
+            .flatten()
+            .enumerate()
+            .skip_while(move |(_, v)| v.row < row)
+            .take_while(move |(_, v)| v.row == row)
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &LayoutLineGlyphs> {
+        self.rows.iter().flatten()
+    }
+}
+
+impl Index<usize> for LayoutLineGlyphs {
+    type Output = LayoutGlyph;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.glyphs[index]
+    }
+}
+
+// impl Index<usize> for LayoutGlyphs {
+//     type Output = LayoutLineGlyphs;
+
+//     fn index(&self, index: usize) -> &Self::Output {
+//         &self.lines[index]
+//     }
+// }
+
+impl Index<LayoutCursorLocation> for LayoutGlyphs {
+    type Output = LayoutGlyph;
+
+    fn index(&self, index: LayoutCursorLocation) -> &Self::Output {
+        &self.lines().nth(index.line_index).unwrap()[index.index]
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CursorLocation {
+    /// The row index of the non-wrapped original text
+    pub row: usize,
+    /// Byte offset to the column in the row
+    pub col: usize,
+}
+
+impl CursorLocation {
+    pub fn new(row: usize, col: usize) -> Self {
+        Self { row, col }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutCursorLocation {
+    /// Wrapped line index
+    pub line_index: usize,
+    /// Glyph index within the line
+    pub index: usize,
+}
+
+impl LayoutCursorLocation {
+    pub fn new(line_index: usize, index: usize) -> Self {
+        Self { line_index, index }
+    }
+}
+
+impl<'a> From<&'a FontFamily> for cosmic_text::Family<'a> {
+    fn from(value: &'a FontFamily) -> Self {
+        match value {
+            FontFamily::Named(name) => cosmic_text::Family::Name(name.borrow()),
+            FontFamily::Serif => cosmic_text::Family::Serif,
+            FontFamily::SansSerif => cosmic_text::Family::SansSerif,
+            FontFamily::Cursive => cosmic_text::Family::Cursive,
+            FontFamily::Fantasy => cosmic_text::Family::Fantasy,
+            FontFamily::Monospace => cosmic_text::Family::Monospace,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn glyph(start: usize, x: f32, rtl: bool) -> LayoutGlyph {
+        LayoutGlyph {
+            index: 0,
+            start,
+            end: start + 1,
+            bounds: Rect::new(vec2(x, 0.0), vec2(x + 10.0, 10.0)),
+            rtl,
+        }
+    }
+
+    /// `ab` followed by the right-to-left `cd`, displayed as `abdc`
+    fn mixed_line() -> LayoutGlyphs {
+        let line = LayoutLineGlyphs {
+            row: 0,
+            bounds: Rect::new(Vec2::ZERO, vec2(40.0, 10.0)),
+            start: 0,
+            end: 4,
+            glyphs: vec![
+                glyph(0, 0.0, false),
+                glyph(1, 10.0, false),
+                glyph(3, 20.0, true),
+                glyph(2, 30.0, true),
+            ],
+        };
+
+        LayoutGlyphs::new(vec![vec![line]], 10.0)
+    }
+
+    #[test]
+    fn bidi_caret_position() {
+        let glyphs = mixed_line();
+
+        let x = |col| {
+            glyphs
+                .to_glyph_boundary(CursorLocation::new(0, col))
+                .unwrap()
+                .x
+        };
+
+        assert_eq!(x(0), 0.0);
+        assert_eq!(x(1), 10.0);
+        // After `b` rather than before `c`
+        assert_eq!(x(2), 20.0);
+        assert_eq!(x(3), 30.0);
+        // At the end of the line rather than left of the right-to-left `d`
+        assert_eq!(x(4), 40.0);
+    }
+
+    #[test]
+    fn bidi_hit() {
+        let glyphs = mixed_line();
+
+        assert_eq!(glyphs.hit(vec2(32.0, 5.0)), Some(CursorLocation::new(0, 3)));
+        // The offsets which are displayed at the edge closest to the hit
+        assert_eq!(glyphs.hit(vec2(38.0, 5.0)), Some(CursorLocation::new(0, 4)));
+        assert_eq!(glyphs.hit(vec2(22.0, 5.0)), Some(CursorLocation::new(0, 2)));
+    }
+
+    #[test]
+    fn bidi_visual_move() {
+        let glyphs = mixed_line();
+
+        let walk = |start: usize, right: bool| {
+            let mut cursor = CursorLocation::new(0, start);
+            let mut cols = vec![start];
+            loop {
+                let next = glyphs.visual_move(cursor, right).unwrap();
+                if next == cursor {
+                    break cols;
+                }
+
+                cols.push(next.col);
+                cursor = next;
+            }
+        };
+
+        assert_eq!(walk(0, true), [0, 1, 2, 3, 4]);
+        assert_eq!(walk(3, false), [3, 2, 1, 0]);
+        assert_eq!(walk(4, false), [4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn ltr_visual_move_is_logical() {
+        let line = LayoutLineGlyphs {
+            row: 0,
+            bounds: Rect::new(Vec2::ZERO, vec2(20.0, 10.0)),
+            start: 0,
+            end: 2,
+            glyphs: vec![glyph(0, 0.0, false), glyph(1, 10.0, false)],
+        };
+
+        let glyphs = LayoutGlyphs::new(vec![vec![line]], 10.0);
+        assert_eq!(glyphs.visual_move(CursorLocation::new(0, 0), true), None);
+    }
+}
@@ -1,20 +1,29 @@
+use std::sync::Arc;
+
 use cosmic_text::{Style, Weight};
 use glam::Vec2;
 use palette::Srgba;
+use winit::event::{ElementState, MouseButton};
 
 use crate::{
-    components::{self, color, draw_shape, text, text_wrap},
+    components::{
+        self, color, draw_shape, layout_glyphs, text, text_max_lines, text_overflow, text_wrap,
+    },
+    input::{cursor_icon, interactive, on_cursor_hover, on_mouse_input, CursorIcon, HoverState},
     shape,
     style::{
-        element_primary, element_secondary, spacing_medium, spacing_small, text_large, text_medium,
-        text_small, ResolvableStyle, SizeExt, StyleExt, ValueOrRef, WidgetSizeProps,
+        element_hover_accent, element_primary, element_secondary, spacing_medium, spacing_small,
+        text_large, text_medium, text_small, ResolvableStyle, SizeExt, StyleExt, ValueOrRef,
+        WidgetSizeProps,
+    },
+    text::{
+        markup::MarkupError, segment_at, FontFeature, LayoutGlyphs, TextOverflow, TextSegment, Wrap,
     },
-    text::{TextSegment, Wrap},
     unit::Unit,
-    Scope, Widget,
+    Scope, ScopeRef, Widget,
 };
 
-use super::Stack;
+use super::{interactive::selectable::SelectableText, Stack};
 
 /// A rectangular widget
 #[derive(Debug, Clone)]
@@ -64,6 +73,19 @@ pub struct TextStyle {
     pub font_size: ValueOrRef<f32>,
     pub wrap: Wrap,
     pub color: ValueOrRef<Srgba>,
+    pub overflow: TextOverflow,
+    /// Clamp the text to at most this many lines
+    pub max_lines: Option<usize>,
+    /// Color of the link under the cursor
+    pub link_hover_color: ValueOrRef<Srgba>,
+    /// Distance between lines, in multiples of the font size
+    pub line_height: f32,
+    /// Extra space between characters, in multiples of the font size.
+    ///
+    /// Used for segments which do not specify their own letter spacing.
+    pub letter_spacing: Option<f32>,
+    /// OpenType features applied to all segments, before the features of each segment
+    pub features: Vec<FontFeature>,
 }
 
 impl Default for TextStyle {
@@ -72,15 +94,37 @@ impl Default for TextStyle {
             font_size: text_small().into(),
             wrap: Wrap::None,
             color: element_primary().into(),
+            overflow: TextOverflow::Visible,
+            max_lines: None,
+            link_hover_color: element_hover_accent().into(),
+            line_height: 1.0,
+            letter_spacing: None,
+            features: Vec::new(),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+/// Invoked with the target of a clicked link
+pub type LinkCallback = Arc<dyn Send + Sync + Fn(&ScopeRef<'_>, &str)>;
+
+#[derive(Clone)]
 pub struct Text {
     text: Vec<TextSegment>,
     style: TextStyle,
     size: WidgetSizeProps,
+    selectable: bool,
+    on_link_click: Option<LinkCallback>,
+}
+
+impl std::fmt::Debug for Text {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Text")
+            .field("text", &self.text)
+            .field("style", &self.style)
+            .field("size", &self.size)
+            .field("selectable", &self.selectable)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Text {
@@ -112,6 +156,11 @@ impl Text {
         Self::formatted([TextSegment::new(text.into()).with_style(Style::Italic)])
     }
 
+    /// Creates rich text from markup, see [`markup`](crate::text::markup) for the syntax
+    pub fn markup(markup: &str) -> Result<Self, MarkupError> {
+        Ok(Self::formatted(crate::text::markup::parse(markup)?))
+    }
+
     pub fn formatted(text: impl IntoIterator<Item = TextSegment>) -> Self {
         Self {
             text: text.into_iter().collect(),
@@ -120,6 +169,8 @@ impl Text {
                 margin: Some(spacing_small().into()),
                 ..Default::default()
             },
+            selectable: false,
+            on_link_click: None,
         }
     }
 
@@ -159,6 +210,62 @@ impl Text {
         self.style.wrap = Wrap::WordOrGlyph;
         self
     }
+
+    /// Shorten the text with an ellipsis when it does not fit
+    pub fn with_overflow(mut self, overflow: TextOverflow) -> Self {
+        self.style.overflow = overflow;
+        self
+    }
+
+    /// Clamp the text to at most `max_lines` lines
+    pub fn with_max_lines(mut self, max_lines: usize) -> Self {
+        self.style.max_lines = Some(max_lines);
+        self
+    }
+
+    /// Set the distance between lines, in multiples of the font size
+    pub fn with_line_height(mut self, line_height: f32) -> Self {
+        self.style.line_height = line_height;
+        self
+    }
+
+    /// Set the extra space between characters, in multiples of the font size
+    pub fn with_letter_spacing(mut self, letter_spacing: f32) -> Self {
+        self.style.letter_spacing = Some(letter_spacing);
+        self
+    }
+
+    /// Apply an OpenType feature to all of the text
+    pub fn with_feature(mut self, feature: FontFeature) -> Self {
+        self.style.features.push(feature);
+        self
+    }
+
+    /// Use digits of equal width, so that changing numbers don't jitter
+    pub fn with_tabular_numbers(self) -> Self {
+        self.with_feature(FontFeature::TABULAR_NUMBERS)
+    }
+
+    /// Allow selecting and copying the text
+    pub fn with_selectable(mut self, selectable: bool) -> Self {
+        self.selectable = selectable;
+        self
+    }
+
+    /// Invoked with the link of a clicked segment, see [`TextSegment::with_link`].
+    ///
+    /// Links are highlighted and show a pointer cursor when hovered.
+    pub fn on_link_click(
+        mut self,
+        on_link_click: impl 'static + Send + Sync + Fn(&ScopeRef<'_>, &str),
+    ) -> Self {
+        self.on_link_click = Some(Arc::new(on_link_click));
+        self
+    }
+
+    pub(crate) fn segments(&self) -> &[TextSegment] {
+        &self.text
+    }
 }
 
 impl StyleExt for Text {
@@ -178,21 +285,156 @@ impl SizeExt for Text {
 
 impl Widget for Text {
     fn mount(self, scope: &mut Scope) {
+        if self.selectable {
+            // The selection is drawn alongside the text, so the size applies to both
+            let size = self.size;
+            let on_link_click = self.on_link_click.clone();
+            let text = Text {
+                size: Default::default(),
+                selectable: false,
+                ..self
+            };
+
+            SelectableText::new(text, size, on_link_click).mount(scope);
+            return;
+        }
+
         self.size.mount(scope);
 
         let stylesheet = scope.stylesheet();
         let font_size = self.style.font_size.resolve(stylesheet);
 
         let font_color = self.style.color.resolve(stylesheet);
+        let hover_color = self.style.link_hover_color.resolve(stylesheet);
+
+        let on_link_click = self
+            .on_link_click
+            .filter(|_| self.text.iter().any(|v| v.link.is_some()));
+
+        let text = apply_typography(self.text, &self.style);
+
+        if let Some(on_link_click) = on_link_click {
+            mount_links(scope, text.clone(), hover_color, on_link_click);
+        }
+
         scope
             .set(draw_shape(shape::shape_text()), ())
             .set(components::font_size(), font_size)
+            .set(components::line_height(), self.style.line_height)
             .set(text_wrap(), self.style.wrap)
-            .set(text(), self.text)
+            .set(text_overflow(), self.style.overflow)
+            .set_opt(text_max_lines(), self.style.max_lines)
+            .set(text(), text)
             .set(color(), font_color);
     }
 }
 
+/// Applies the letter spacing and features of the style to each segment
+fn apply_typography(mut text: Vec<TextSegment>, style: &TextStyle) -> Vec<TextSegment> {
+    if style.letter_spacing.is_none() && style.features.is_empty() {
+        return text;
+    }
+
+    for segment in &mut text {
+        segment.letter_spacing = segment.letter_spacing.or(style.letter_spacing);
+        segment.features = style
+            .features
+            .iter()
+            .chain(&segment.features)
+            .copied()
+            .collect();
+    }
+
+    text
+}
+
+/// Returns the link of the segment directly under `pos`
+pub(crate) fn find_link<'a>(
+    glyphs: &LayoutGlyphs,
+    segments: &'a [TextSegment],
+    pos: Vec2,
+) -> Option<&'a str> {
+    let loc = glyphs.glyph_at(pos)?;
+    segments[segment_at(segments, loc)?].link.as_deref()
+}
+
+fn link_at(scope: &ScopeRef<'_>, pos: Vec2) -> Option<String> {
+    let entity = scope.entity();
+    let glyphs = entity.get(layout_glyphs()).ok()?;
+    let segments = entity.get(text()).ok()?;
+
+    find_link(&glyphs, &segments, pos).map(ToOwned::to_owned)
+}
+
+fn mount_links(
+    scope: &mut Scope<'_>,
+    segments: Vec<TextSegment>,
+    hover_color: Srgba,
+    on_link_click: LinkCallback,
+) {
+    let (hover_tx, hover_rx) = flume::unbounded::<Option<String>>();
+
+    // Cursor icon of the text itself, which is restored when leaving a link
+    let mut restore_icon = None;
+    scope.spawn_stream(hover_rx.into_stream(), move |scope, hovered| {
+        let segments = segments
+            .iter()
+            .map(|v| match &hovered {
+                Some(link) if v.link.as_ref() == Some(link) => v.clone().with_color(hover_color),
+                _ => v.clone(),
+            })
+            .collect();
+
+        scope.set(text(), segments);
+        if hovered.is_some() {
+            if restore_icon.is_none() {
+                restore_icon = Some(scope.entity().get_copy(cursor_icon()).ok());
+            }
+
+            scope.set(cursor_icon(), CursorIcon::Pointer);
+        } else {
+            match restore_icon.take().flatten() {
+                Some(icon) => {
+                    scope.set(cursor_icon(), icon);
+                }
+                None => {
+                    scope.entity_mut().remove(cursor_icon()).ok();
+                }
+            }
+        }
+    });
+
+    let mut hovered = None;
+    scope
+        .set(interactive(), ())
+        .on_event(on_cursor_hover(), move |scope, input| {
+            let link = match input.state {
+                HoverState::Exited => None,
+                _ => link_at(scope, input.local_pos),
+            };
+
+            if link != hovered {
+                hovered = link.clone();
+                hover_tx.send(link).ok();
+            }
+
+            Some(input)
+        })
+        .on_event(on_mouse_input(), move |scope, input| {
+            if input.state != ElementState::Released || input.button != MouseButton::Left {
+                return Some(input);
+            }
+
+            match link_at(scope, input.cursor.local_pos) {
+                Some(link) => {
+                    on_link_click(scope, &link);
+                    None
+                }
+                None => Some(input),
+            }
+        });
+}
+
 /// A text with a margin
 pub fn label(text: impl Into<String>) -> Text {
     Text::new(text).with_margin(spacing_small())
@@ -4,7 +4,7 @@ use winit::event::ElementState;
 
 use crate::{
     components::{anchor, layout, offset, rect, LayoutAlignment},
-    input::{interactive, on_cursor_move, on_mouse_input},
+    input::{cursor_icon, interactive, on_cursor_move, on_mouse_input, CursorIcon},
     layout::{Align, Direction, FloatLayout, FlowLayout, Layout, StackLayout},
     scope::ScopeRef,
     style::{
@@ -232,6 +232,7 @@ pub struct Movable<W> {
     content: W,
     on_move: OnMove,
     on_drop: OnDrop,
+    cursor_icon: CursorIcon,
     size: WidgetSizeProps,
 }
 
@@ -241,10 +242,17 @@ impl<W> Movable<W> {
             content,
             on_move: Box::new(|_, v| v),
             on_drop: Box::new(|_, _| {}),
+            cursor_icon: CursorIcon::Move,
             size: Default::default(),
         }
     }
 
+    /// Set the cursor icon shown when hovering or dragging the widget
+    pub fn with_cursor_icon(mut self, cursor_icon: CursorIcon) -> Self {
+        self.cursor_icon = cursor_icon;
+        self
+    }
+
     pub fn on_move(
         mut self,
         on_move: impl 'static + Send + Sync + FnMut(&ScopeRef<'_>, Vec2) -> Vec2,
@@ -268,6 +276,7 @@ impl<W: Widget> Widget for Movable<W> {
 
         scope
             .set(interactive(), ())
+            .set(cursor_icon(), self.cursor_icon)
             .set(offset(), Unit::default())
             .on_event(on_mouse_input(), {
                 let start_offset = start_offset.clone();
@@ -3,7 +3,7 @@ use palette::Srgba;
 
 use crate::{
     components::{color, LayoutAlignment},
-    input::MouseInput,
+    input::{cursor_icon, CursorIcon, MouseInput},
     layout::Align,
     scope::ScopeRef,
     state::{StateDuplex, StateExt, StateStream, WatchState},
@@ -362,7 +362,9 @@ impl<W: Widget> Widget for Button<W> {
             .with_horizontal_alignment(self.style.align.horizontal)
             .with_vertical_alignment(self.style.align.vertical);
 
-        scope.set_default(tweens());
+        scope
+            .set_default(tweens())
+            .set(cursor_icon(), CursorIcon::Pointer);
 
         InteractiveWidget::new(inner)
             .with_size_props(self.style.size)
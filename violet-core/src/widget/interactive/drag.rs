@@ -1,14 +1,19 @@
-use std::cell::RefCell;
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+};
 
 use flax::{component, EntityRef};
 use futures_signals::signal::{Mutable, MutableSignal, SignalExt};
 use glam::Vec2;
+use palette::Srgba;
 use winit::event::ElementState;
 
 use crate::{
-    components::{offset, visible},
+    components::{color, offset, visible},
     hierarchy::find_widget_intersect,
-    input::{interactive, on_cursor_move, on_mouse_input},
+    input::{interactive, on_cursor_move, on_file_drop, on_file_hover, on_mouse_input, HoverState},
+    style::{surface_hover_accent, Background, ResolvableStyle, ValueOrRef},
     unit::Unit,
     widget::Stack,
     ScopeRef, Widget,
@@ -131,6 +136,107 @@ impl<T: Widget, P: 'static + Send + Widget> Widget for Draggable<T, P> {
     }
 }
 
+type OnFileDropFn = Box<dyn Fn(&ScopeRef<'_>, Vec<PathBuf>)>;
+type FileFilterFn = Box<dyn Fn(&Path) -> bool>;
+
+/// Accepts files dragged into the window from outside the application.
+///
+/// The widget is highlighted while accepted files are hovering it. Files which are not accepted
+/// are passed on to the closest parent drop target.
+pub struct FileDropTarget<W> {
+    widget: W,
+    on_drop: OnFileDropFn,
+    filter: Option<FileFilterFn>,
+    highlight: ValueOrRef<Srgba>,
+}
+
+impl<W> FileDropTarget<W> {
+    pub fn new(widget: W, on_drop: impl 'static + Fn(&ScopeRef<'_>, Vec<PathBuf>)) -> Self {
+        Self {
+            widget,
+            on_drop: Box::new(on_drop),
+            filter: None,
+            highlight: surface_hover_accent().into(),
+        }
+    }
+
+    /// Only accept files matching the filter
+    pub fn with_filter(mut self, filter: impl 'static + Fn(&Path) -> bool) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Only accept files with one of the given extensions, ignoring case
+    pub fn with_extensions(self, extensions: &[&str]) -> Self {
+        let extensions: Vec<String> = extensions.iter().map(|v| v.to_lowercase()).collect();
+        self.with_filter(move |path| {
+            path.extension()
+                .and_then(|v| v.to_str())
+                .is_some_and(|ext| extensions.contains(&ext.to_lowercase()))
+        })
+    }
+
+    /// Set the background color shown while files are hovering
+    pub fn with_highlight(mut self, highlight: impl Into<ValueOrRef<Srgba>>) -> Self {
+        self.highlight = highlight.into();
+        self
+    }
+}
+
+impl<W: Widget> Widget for FileDropTarget<W> {
+    fn mount(self, scope: &mut crate::Scope<'_>) {
+        let highlight = self.highlight.resolve(scope.stylesheet());
+        let normal = Srgba::new(0.0, 0.0, 0.0, 0.0);
+
+        let filter = scope.store(self.filter);
+        let accepts = move |scope: &ScopeRef<'_>, path: &Path| {
+            scope
+                .read(filter)
+                .as_ref()
+                .map_or(true, |filter| filter(path))
+        };
+
+        let on_drop = scope.store(self.on_drop);
+
+        scope
+            .on_event(on_file_hover(), move |scope, event| {
+                let accepted = event.state != HoverState::Exited
+                    && event.paths.iter().any(|path| accepts(scope, path));
+
+                scope.update_dedup(color(), if accepted { highlight } else { normal });
+
+                if accepted {
+                    None
+                } else {
+                    Some(event)
+                }
+            })
+            .on_event(on_file_drop(), move |scope, mut event| {
+                scope.update_dedup(color(), normal);
+
+                let (accepted, rejected) = event
+                    .paths
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|path| accepts(scope, path));
+
+                if !accepted.is_empty() {
+                    scope.read(on_drop)(scope, accepted);
+                }
+
+                if rejected.is_empty() {
+                    None
+                } else {
+                    event.paths = rejected;
+                    Some(event)
+                }
+            });
+
+        Stack::new(self.widget)
+            .with_background(Background::new(normal))
+            .mount(scope);
+    }
+}
+
 pub(crate) struct DragOverlay {
     position: MutableSignal<Vec2>,
     widget: Box<dyn Send + Widget>,
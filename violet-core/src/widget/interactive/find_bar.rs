@@ -0,0 +1,183 @@
+use futures_signals::signal::{Mutable, SignalExt};
+
+use crate::{
+    editor::SearchQuery,
+    state::StateExt,
+    to_owned,
+    widget::{col, label, row, Button, Checkbox, SignalWidget, TextInput},
+    Scope, Widget,
+};
+
+/// Command sent from a [`FindBar`] to the text area being searched
+#[derive(Debug, Clone)]
+pub(crate) enum SearchCommand {
+    Next,
+    Previous,
+    Replace(String),
+    ReplaceAll(String),
+}
+
+/// Position of the selected match among all matches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStatus {
+    /// Index of the match selected by the primary caret
+    pub current: Option<usize>,
+    pub count: usize,
+}
+
+/// Connects a [`FindBar`] to the [`TextArea`](super::TextArea) it searches.
+///
+/// Matches of the query are highlighted in the text area, which selects the first match after the
+/// cursor as the query changes.
+#[derive(Clone)]
+pub struct TextSearch {
+    pub(crate) query: Mutable<SearchQuery>,
+    /// `None` while the query is empty
+    pub(crate) status: Mutable<Option<SearchStatus>>,
+    pub(crate) commands_tx: flume::Sender<SearchCommand>,
+    pub(crate) commands_rx: flume::Receiver<SearchCommand>,
+}
+
+impl TextSearch {
+    pub fn new() -> Self {
+        let (commands_tx, commands_rx) = flume::unbounded();
+        Self {
+            query: Mutable::new(SearchQuery::default()),
+            status: Mutable::new(None),
+            commands_tx,
+            commands_rx,
+        }
+    }
+
+    pub fn query(&self) -> &Mutable<SearchQuery> {
+        &self.query
+    }
+
+    pub fn status(&self) -> &Mutable<Option<SearchStatus>> {
+        &self.status
+    }
+
+    /// Selects the next match, wrapping around to the start of the text
+    pub fn next(&self) {
+        self.commands_tx.send(SearchCommand::Next).ok();
+    }
+
+    /// Selects the previous match, wrapping around to the end of the text
+    pub fn previous(&self) {
+        self.commands_tx.send(SearchCommand::Previous).ok();
+    }
+
+    /// Replaces the selected match and selects the next one
+    pub fn replace(&self, replacement: impl Into<String>) {
+        self.commands_tx
+            .send(SearchCommand::Replace(replacement.into()))
+            .ok();
+    }
+
+    /// Replaces all matches as a single undo step
+    pub fn replace_all(&self, replacement: impl Into<String>) {
+        self.commands_tx
+            .send(SearchCommand::ReplaceAll(replacement.into()))
+            .ok();
+    }
+}
+
+impl Default for TextSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Search input with match navigation and optional replacement, for a text area using the same
+/// [`TextSearch`]
+pub struct FindBar {
+    search: TextSearch,
+    replace: bool,
+}
+
+impl FindBar {
+    pub fn new(search: TextSearch) -> Self {
+        Self {
+            search,
+            replace: false,
+        }
+    }
+
+    /// Show an input and buttons for replacing matches
+    pub fn with_replace(mut self, replace: bool) -> Self {
+        self.replace = replace;
+        self
+    }
+}
+
+impl Widget for FindBar {
+    fn mount(self, scope: &mut Scope<'_>) {
+        let query = &self.search.query;
+
+        let status = self.search.status.signal().map(|status| {
+            label(match status {
+                Some(SearchStatus {
+                    current: Some(current),
+                    count,
+                }) => format!("{} of {count}", current + 1),
+                Some(SearchStatus { count: 0, .. }) => "No results".to_string(),
+                Some(SearchStatus { count, .. }) => format!("{count} results"),
+                None => String::new(),
+            })
+        });
+
+        let find = row((
+            TextInput::new(
+                query
+                    .clone()
+                    .project_ref(|v| &v.pattern, |v| &mut v.pattern),
+            )
+            .with_placeholder("Find"),
+            Checkbox::with_label(
+                label("Aa"),
+                query
+                    .clone()
+                    .project_ref(|v| &v.case_sensitive, |v| &mut v.case_sensitive),
+            )
+            .with_tooltip_text("Match case"),
+            Checkbox::with_label(
+                label("ab"),
+                query
+                    .clone()
+                    .project_ref(|v| &v.whole_word, |v| &mut v.whole_word),
+            )
+            .with_tooltip_text("Match whole word"),
+            SignalWidget::new(status),
+            Button::label("↑")
+                .with_tooltip_text("Previous match")
+                .on_click({
+                    to_owned![search = self.search];
+                    move |_| search.previous()
+                }),
+            Button::label("↓")
+                .with_tooltip_text("Next match")
+                .on_click({
+                    to_owned![search = self.search];
+                    move |_| search.next()
+                }),
+        ));
+
+        let replace = self.replace.then(|| {
+            let replacement = Mutable::new(String::new());
+
+            row((
+                TextInput::new(replacement.clone()).with_placeholder("Replace"),
+                Button::label("Replace").on_click({
+                    to_owned![search = self.search, replacement];
+                    move |_| search.replace(replacement.get_cloned())
+                }),
+                Button::label("Replace all").on_click({
+                    to_owned![search = self.search];
+                    move |_| search.replace_all(replacement.get_cloned())
+                }),
+            ))
+        });
+
+        col((find, replace)).mount(scope)
+    }
+}
@@ -1,36 +1,52 @@
 use core::panic;
-use std::{fmt::Display, future::Future, str::FromStr, sync::Arc};
+use std::{
+    fmt::Display,
+    future::Future,
+    iter,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
-use futures::StreamExt;
+use flax::Entity;
+use futures::{
+    future::{Fuse, FusedFuture},
+    FutureExt, StreamExt,
+};
 use futures_signals::signal::{Mutable, SignalExt};
 use glam::{vec2, BVec2, Mat4, Vec2, Vec3, Vec3Swizzles};
 use itertools::Itertools;
 use palette::{Srgba, WithAlpha};
+use parking_lot::Mutex;
 use web_time::Duration;
-use winit::{
-    event::ElementState,
-    keyboard::{Key, ModifiersState, NamedKey},
-};
+use winit::event::{ElementState, Ime};
 
 use crate::{
     components::{self, screen_transform, LayoutAlignment},
-    editor::{CursorMove, EditAction, EditorAction, EditorLine, TextChange, TextEditorCore},
+    editor::{
+        Caret, CursorMove, EditAction, EditorAction, HighlightCache, Highlighter, TextBuffer,
+        TextChange, TextEditorCore, DEFAULT_HISTORY_LIMIT,
+    },
     input::{
-        interactive, keep_focus, on_cursor_move, on_focus, on_keyboard_input, on_mouse_input,
-        KeyboardInput,
+        cursor_icon, ime_cursor_area_sender, interactive, keep_focus, on_cursor_move, on_focus,
+        on_ime, on_keyboard_input, on_mouse_input, CursorIcon,
     },
     io::{self, Clipboard},
     layout::Align,
     state::{StateDuplex, StateExt, StateSink, StateStream},
     style::*,
-    text::{CursorLocation, FontFamily, LayoutGlyphs, TextSegment},
-    time::sleep,
+    text::{CursorLocation, FontFamily, LayoutGlyph, LayoutGlyphs, LayoutLineGlyphs, TextSegment},
+    time::{sleep, Sleep},
     to_owned,
     unit::Unit,
     utils::throttle,
     widget::{
-        bold, col, interactive::base::InteractiveWidget, Float, Positioned, Rectangle,
-        SignalWidget, Stack, StreamWidget, Text, TextStyle, WidgetExt,
+        bold, col,
+        interactive::{base::InteractiveWidget, keymap, InputMask, Keymap},
+        row, Float, Positioned, Rectangle, SignalWidget, Stack, StreamWidget, Text, TextStyle,
+        WidgetExt,
     },
     Edges, Rect, Scope, ScopeRef, Widget,
 };
@@ -64,26 +80,104 @@ impl Default for TextInputStyle {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct TextOptions {
-    allow_newlines: bool,
+    pub(crate) allow_newlines: bool,
+    pub(crate) history_limit: usize,
+    pub(crate) max_length: Option<usize>,
+    pub(crate) char_filter: Option<fn(char) -> bool>,
+    pub(crate) mask: Option<InputMask>,
+}
+
+impl TextOptions {
+    /// Allow entering line breaks
+    pub fn with_allow_newlines(mut self, allow_newlines: bool) -> Self {
+        self.allow_newlines = allow_newlines;
+        self
+    }
+
+    /// Set the maximum memory used by the undo history, in bytes
+    pub fn with_history_limit(mut self, history_limit: usize) -> Self {
+        self.history_limit = history_limit;
+        self
+    }
+
+    /// Limit the number of characters which can be entered
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Only allow entering characters accepted by the filter, such as `char::is_alphanumeric`
+    pub fn with_char_filter(mut self, char_filter: fn(char) -> bool) -> Self {
+        self.char_filter = Some(char_filter);
+        self
+    }
+
+    /// Format single line input to a mask, such as `##:##`.
+    ///
+    /// See [`InputMask`] for the syntax.
+    pub fn with_mask(mut self, mask: InputMask) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Removes the disallowed characters from text being inserted, and truncates it to the
+    /// remaining length
+    pub(crate) fn filter_input(&self, editor: &TextEditorCore, text: &str) -> String {
+        let chars = text.chars().filter(|&c| {
+            if c == '\n' {
+                self.allow_newlines
+            } else {
+                self.char_filter.map_or(true, |filter| filter(c))
+            }
+        });
+
+        let Some(max_length) = self.max_length else {
+            return chars.collect();
+        };
+
+        // Inserting replaces the selections
+        let selected: usize = editor
+            .carets()
+            .filter_map(|v| v.selection_bounds())
+            .map(|(start, end)| editor.text().slice(start, end).chars().count())
+            .sum();
+
+        let len = editor.text().rope().len_chars() - selected;
+        let remaining = max_length.saturating_sub(len) / editor.carets().count();
+
+        chars.take(remaining).collect()
+    }
 }
 
 impl Default for TextOptions {
     fn default() -> Self {
         Self {
             allow_newlines: false,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            max_length: None,
+            char_filter: None,
+            mask: None,
         }
     }
 }
 
+/// Validates the text of an input, returning a message describing why it is invalid
+pub type Validator = Arc<dyn Send + Sync + Fn(&str) -> Result<(), String>>;
+
 /// Text field allowing arbitrary user input
 pub struct TextInput {
     style: TextInputStyle,
     content: Arc<dyn Send + Sync + StateDuplex<Item = String>>,
     options: TextOptions,
+    highlighter: Option<Arc<dyn Highlighter>>,
     on_focus_lost: Option<Box<dyn Send + Sync + FnMut(&ScopeRef<'_>)>>,
     request_focus: bool,
+    placeholder: Option<String>,
+    password: bool,
+    validator: Option<Validator>,
+    keymap: Option<Keymap>,
 }
 
 impl TextInputStyle {
@@ -116,8 +210,13 @@ impl TextInput {
             content: Arc::new(content),
             style: Default::default(),
             options: Default::default(),
+            highlighter: None,
             on_focus_lost: None,
             request_focus: false,
+            placeholder: None,
+            password: false,
+            validator: None,
+            keymap: None,
         }
     }
 
@@ -131,13 +230,52 @@ impl TextInput {
         self
     }
 
+    /// Style the text using a syntax highlighter
+    pub fn with_highlighter(mut self, highlighter: impl 'static + Highlighter) -> Self {
+        self.highlighter = Some(Arc::new(highlighter));
+        self
+    }
+
+    /// Use different key bindings than the global [`keymap`]
+    pub fn with_keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = Some(keymap);
+        self
+    }
+
+    /// Display text while the input is empty
+    pub fn with_placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = Some(placeholder.into());
+        self
+    }
+
+    /// Hide the entered text, with a button to reveal it
+    pub fn with_password(mut self) -> Self {
+        self.password = true;
+        self
+    }
+
+    /// Validate the text as it is edited.
+    ///
+    /// Invalid text is not written to the content, and the error is displayed in the input.
+    pub fn with_validator(
+        mut self,
+        validator: impl 'static + Send + Sync + Fn(&str) -> Result<(), String>,
+    ) -> Self {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+
+    /// Edit a value through its string representation, displaying an error if it fails to parse
     pub fn new_parsed<T>(content: impl 'static + Send + Sync + StateDuplex<Item = T>) -> Self
     where
         T: 'static + Send + Sync + ToString + FromStr,
     {
         let content = content.filter_map(|v| Some(v.to_string()), |v| v.parse().ok());
 
-        let mut this = Self::new(content);
+        let mut this = Self::new(content).with_validator(|v| match v.parse::<T>() {
+            Ok(_) => Ok(()),
+            Err(_) => Err(format!("Not a valid {}", tynm::type_name::<T>())),
+        });
         this.style.align = Align::End;
         this
     }
@@ -182,29 +320,108 @@ impl Widget for TextInput {
             .resolve(stylesheet)
             .with_alpha(0.2);
 
+        let mut keys = self
+            .keymap
+            .clone()
+            .or_else(|| scope.get_atom(keymap()).map(|v| v.clone()))
+            .unwrap_or_default()
+            .handler();
+
+        let error_icon = stylesheet.get_clone(icon_error()).unwrap();
+        let show_icon = stylesheet.get_clone(icon_show()).unwrap();
+        let hide_icon = stylesheet.get_clone(icon_hide()).unwrap();
+
         let (tx, actions_rx) = flume::unbounded();
 
         let focused = Mutable::new(false);
 
         let (dirty_tx, dirty_rx) = flume::unbounded();
 
-        let on_change = move |text: &[EditorLine], change| match change {
-            TextChange::Insert(start, end) => {
-                for (row, text) in text.iter().enumerate().take(end.row + 1).skip(start.row) {
-                    dirty_tx.send((row, Some(text.as_str().to_string()))).ok();
+        let highlights = self
+            .highlighter
+            .clone()
+            .map(|v| Arc::new(Mutex::new(HighlightCache::new(v))));
+
+        let on_change = {
+            to_owned![dirty_tx, highlights];
+            move |text: &TextBuffer, change: TextChange| {
+                let restyled = highlights
+                    .as_ref()
+                    .map(|v| v.lock().apply_change(text, &change))
+                    .unwrap_or_default();
+
+                let segments = |row| line_segments(highlights.as_deref(), text, row);
+
+                match change {
+                    TextChange::Insert(start, end) => {
+                        dirty_tx
+                            .send(RowChange::Set(start.row, segments(start.row)))
+                            .ok();
+
+                        for row in start.row + 1..=end.row {
+                            dirty_tx.send(RowChange::Insert(row, segments(row))).ok();
+                        }
+                    }
+                    TextChange::Delete(start, end) => {
+                        for _ in start.row + 1..=end.row {
+                            dirty_tx.send(RowChange::Remove(start.row + 1)).ok();
+                        }
+
+                        dirty_tx
+                            .send(RowChange::Set(start.row, segments(start.row)))
+                            .ok();
+                    }
                 }
-            }
-            TextChange::Delete(start, end) => {
-                for (row, text) in text.iter().enumerate().take(end.row + 1).skip(start.row) {
-                    dirty_tx.send((row, Some(text.as_str().to_string()))).ok();
+
+                for row in restyled {
+                    dirty_tx.send(RowChange::Set(row, segments(row))).ok();
                 }
             }
-            TextChange::DeleteLine(row) => {
-                dirty_tx.send((row, None)).ok();
-            }
         };
 
         let mut editor = TextEditorCore::new(on_change);
+        editor.set_history_limit(self.options.history_limit);
+
+        if let Some(mask) = self.options.mask.clone() {
+            editor.set_formatter(move |text, cursor| mask.format(text, cursor));
+        }
+
+        let revealed = Mutable::new(false);
+        if self.password {
+            dirty_tx.send(RowChange::Mask(Some(PASSWORD_MASK))).ok();
+        }
+
+        let error = Mutable::new(None);
+        let content = match self.validator.clone() {
+            Some(validator) => Arc::new(self.content.clone().filter_map(
+                {
+                    to_owned![error, validator];
+                    move |v: String| {
+                        error.set(validator(&v).err());
+                        Some(v)
+                    }
+                },
+                {
+                    to_owned![error];
+                    move |v: String| match validator(&v) {
+                        Ok(()) => {
+                            error.set(None);
+                            Some(v)
+                        }
+                        Err(err) => {
+                            error.set(Some(err));
+                            None
+                        }
+                    }
+                },
+            )) as Arc<dyn Send + Sync + StateDuplex<Item = String>>,
+            None => self.content.clone(),
+        };
+
+        for row in 0..editor.text().len_lines() {
+            let segments = line_segments(highlights.as_deref(), editor.text(), row);
+            dirty_tx.send(RowChange::Set(row, segments)).ok();
+        }
 
         let layout_glyphs = Mutable::new(Default::default());
         let text_bounds: Mutable<Option<Mat4>> = Mutable::new(None);
@@ -213,7 +430,9 @@ impl Widget for TextInput {
 
         let visual_cursor = Mutable::new(VisualCursor {
             lines: vec![],
-            cursor: Default::default(),
+            carets: vec![],
+            preedit: None,
+            empty: true,
         });
 
         let clipboard = scope
@@ -223,15 +442,23 @@ impl Widget for TextInput {
 
         let clipboard = scope.frame().store().get(&clipboard).clone();
 
+        let ime_cursor_area = scope.get_atom(ime_cursor_area_sender()).map(|v| v.clone());
+
+        let mask_tx = dirty_tx.clone();
+
         scope.spawn(process_edit_commands(
             focused.clone(),
             layout_glyphs.clone(),
+            text_bounds.clone(),
             actions_rx,
             editor,
             clipboard,
-            self.content.clone(),
+            content,
             visual_cursor.clone(),
-            self.options,
+            dirty_tx,
+            highlights,
+            ime_cursor_area,
+            self.options.clone(),
         ));
 
         if self.request_focus {
@@ -240,7 +467,7 @@ impl Widget for TextInput {
             }
         }
 
-        let dragging = Mutable::new(None);
+        let dragging: Mutable<Option<Drag>> = Mutable::new(None);
 
         let mut last_click = None;
         let mut process_double_click = {
@@ -261,6 +488,7 @@ impl Widget for TextInput {
         scope
             .set(interactive(), ())
             .set(keep_focus(), ())
+            .set(cursor_icon(), CursorIcon::Text)
             .on_event(on_focus(), {
                 to_owned![tx];
                 move |scope, focus| {
@@ -268,6 +496,8 @@ impl Widget for TextInput {
 
                     if !focus {
                         tx.send(Action::Editor(EditorAction::SelectionClear)).ok();
+                        // Discard any unfinished composition
+                        tx.send(Action::Ime(Ime::Disabled)).ok();
                         if let Some(focus_lost) = &mut self.on_focus_lost {
                             focus_lost(scope);
                         }
@@ -286,20 +516,30 @@ impl Widget for TextInput {
                             let text_pos = input.cursor.absolute_pos
                                 - text_bounds.transform_point3(Vec3::ZERO).xy();
 
+                            // Alt-clicking adds another caret
+                            let alt = input.modifiers.alt_key();
+
                             // If shift-clicking, start selecting the region between the current
                             // cursor and the new clicked position
                             if input.modifiers.shift_key() {
                                 tx.send(Action::Editor(EditorAction::SelectionStart)).ok();
-                            } else {
+                            } else if !alt {
                                 tx.send(Action::Editor(EditorAction::SelectionClear)).ok();
                             }
 
                             if let Some(hit) = glyphs.hit(text_pos) {
-                                dragging.set(Some(input.cursor.local_pos));
-                                tx.send(Action::Editor(EditorAction::CursorMove(
-                                    CursorMove::SetPosition(hit),
-                                )))
-                                .ok();
+                                dragging.set(Some(Drag {
+                                    start: input.cursor.local_pos,
+                                    column: alt,
+                                }));
+
+                                let action = if alt {
+                                    EditorAction::AddCaret(hit)
+                                } else {
+                                    EditorAction::CursorMove(CursorMove::SetPosition(hit))
+                                };
+
+                                tx.send(Action::Editor(action)).ok();
                             }
 
                             process_double_click();
@@ -314,11 +554,9 @@ impl Widget for TextInput {
             .on_event(on_cursor_move(), {
                 to_owned![layout_glyphs, tx, dragging];
                 move |_, input| {
-                    let dragging = dragging.get();
+                    let drag = dragging.get()?;
 
-                    let drag_start = dragging?;
-
-                    if input.local_pos.distance(drag_start) < 5.0 {
+                    if input.local_pos.distance(drag.start) < 5.0 {
                         return None;
                     }
 
@@ -326,7 +564,13 @@ impl Widget for TextInput {
 
                     let text_pos = input.local_pos;
 
-                    if let Some(hit) = glyphs.hit(text_pos) {
+                    if drag.column {
+                        let carets = column_selection(&glyphs, drag.start, text_pos);
+                        if !carets.is_empty() {
+                            tx.send(Action::Editor(EditorAction::SetCarets(carets)))
+                                .ok();
+                        }
+                    } else if let Some(hit) = glyphs.hit(text_pos) {
                         tx.send(Action::Editor(EditorAction::SelectionStart)).ok();
                         tx.send(Action::Editor(EditorAction::CursorMove(
                             CursorMove::SetPosition(hit),
@@ -338,29 +582,98 @@ impl Widget for TextInput {
                 }
             })
             .on_event(on_keyboard_input(), {
-                to_owned![tx];
+                to_owned![tx, revealed];
+                let password = self.password;
                 move |_, input| {
                     if input.state == ElementState::Pressed {
-                        handle_input(
-                            input,
-                            |v| {
-                                tx.send(v).ok();
-                            },
-                            &self.options,
-                        );
+                        keys.handle(&input, &self.options, &mut |v| {
+                            // Hidden text is not copied
+                            if password
+                                && !revealed.get()
+                                && matches!(v, Action::Copy | Action::Cut)
+                            {
+                                return;
+                            }
+
+                            tx.send(v).ok();
+                        });
                     }
 
+                    None
+                }
+            })
+            .on_event(on_ime(), {
+                to_owned![tx];
+                move |_, ime| {
+                    tx.send(Action::Ime(ime)).ok();
                     None
                 }
             });
 
-        Stack::new((
+        let placeholder = self.placeholder.map(|placeholder| {
+            let text_style = self.style.text_style.clone();
+            let font_family = self.style.font_family.clone();
+
+            Float::new(SignalWidget::new(
+                visual_cursor
+                    .signal_ref(|v| v.empty)
+                    .dedupe()
+                    .map(move |empty| {
+                        empty.then(|| {
+                            Text::formatted([TextSegment::new(placeholder.clone())
+                                .with_family(font_family.clone())])
+                            .with_margin(Edges::ZERO)
+                            .with_style(text_style.clone())
+                            .with_color(element_secondary())
+                        })
+                    }),
+            ))
+        });
+
+        let error_indicator = SignalWidget::new(error.signal_ref(move |err| {
+            let error_icon = &error_icon;
+            err.as_ref().map(move |err| {
+                InteractiveWidget::new(
+                    bold(error_icon)
+                        .with_padding(spacing_small())
+                        .with_color(surface_danger()),
+                )
+                .with_tooltip_text(err)
+            })
+        }));
+
+        let reveal_toggle = self.password.then(|| {
+            SignalWidget::new(revealed.signal().map(move |shown| {
+                to_owned![revealed, mask_tx];
+                let icon = if shown { &hide_icon } else { &show_icon };
+
+                InteractiveWidget::new(
+                    bold(icon)
+                        .with_padding(spacing_small())
+                        .with_color(element_secondary()),
+                )
+                .on_click(move |_| {
+                    revealed.set(!shown);
+                    mask_tx
+                        .send(RowChange::Mask(shown.then_some(PASSWORD_MASK)))
+                        .ok();
+                })
+            }))
+        });
+
+        let indicators = (self.validator.is_some() || self.password).then(|| {
+            Stack::new((row((error_indicator, reveal_toggle)),))
+                .with_item_align(LayoutAlignment::new(Align::End, Align::Center))
+        });
+
+        let input = Stack::new((
             TextContent {
                 rx: dirty_rx,
                 text_bounds: text_bounds.clone(),
                 layout_glyphs: layout_glyphs.clone(),
                 style: self.style.clone(),
             },
+            placeholder,
             Float::new(StreamWidget(
                 visual_cursor
                     .signal_ref(move |v| {
@@ -374,11 +687,20 @@ impl Widget for TextInput {
                                 )
                                 .with_offset(Unit::px(line.pos()))
                             })
-                            .chain([Positioned::new(
-                                Rectangle::new(cursor_color)
-                                    .with_exact_size(Unit::px(v.cursor.size())),
-                            )
-                            .with_offset(Unit::px(v.cursor.pos()))]);
+                            .chain(v.carets.iter().map(move |caret| {
+                                Positioned::new(
+                                    Rectangle::new(cursor_color)
+                                        .with_exact_size(Unit::px(caret.size())),
+                                )
+                                .with_offset(Unit::px(caret.pos()))
+                            }))
+                            .chain(v.preedit.map(|underline| {
+                                Positioned::new(
+                                    Rectangle::new(cursor_color)
+                                        .with_exact_size(Unit::px(underline.size())),
+                                )
+                                .with_offset(Unit::px(underline.pos()))
+                            }));
 
                         Stack::new(lines.collect_vec())
                     })
@@ -388,24 +710,173 @@ impl Widget for TextInput {
         .with_horizontal_alignment(self.style.align)
         .with_background(self.style.background)
         .with_clip(BVec2::TRUE)
-        .with_size_props(self.style.size)
-        .mount(scope)
+        .with_size_props(self.style.size);
+
+        Stack::new((input, indicators)).mount(scope)
     }
 }
 
+/// Character displayed in place of each character of hidden text
+const PASSWORD_MASK: char = '•';
+
+/// Mouse drag within the text
+#[derive(Clone, Copy)]
+struct Drag {
+    start: Vec2,
+    /// Alt-dragging selects a rectangular column of text
+    column: bool,
+}
+
 struct VisualCursor {
     lines: Vec<Rect>,
-    cursor: Rect,
+    carets: Vec<Rect>,
+    /// Underline of the text currently being composed by the input method
+    preedit: Option<Rect>,
+    /// The input is empty and not composing text, showing the placeholder
+    empty: bool,
+}
+
+/// Text being composed by the input method.
+///
+/// The preedit is displayed inline at the cursor but is not part of the editor text until
+/// committed.
+pub(crate) struct Preedit {
+    pub(crate) location: CursorLocation,
+    pub(crate) text: String,
+    /// Byte range of the cursor within the preedit text
+    pub(crate) cursor: Option<(usize, usize)>,
+}
+
+/// Re-sends the row of the preedit with the composed text inlined
+fn update_preedit_row(
+    editor: &TextEditorCore,
+    highlights: Option<&Mutex<HighlightCache>>,
+    dirty_tx: &flume::Sender<RowChange>,
+    preedit: &Preedit,
+) {
+    if preedit.location.row >= editor.text().len_lines() {
+        return;
+    }
+
+    let mut segments = line_segments(highlights, editor.text(), preedit.location.row);
+    insert_segment_text(&mut segments, preedit.location.col, &preedit.text);
+    dirty_tx
+        .send(RowChange::Set(preedit.location.row, segments))
+        .ok();
+}
+
+/// Restores a row of a removed preedit to the editor text
+fn clear_preedit_row(
+    editor: &TextEditorCore,
+    highlights: Option<&Mutex<HighlightCache>>,
+    dirty_tx: &flume::Sender<RowChange>,
+    row: usize,
+) {
+    if row < editor.text().len_lines() {
+        let segments = line_segments(highlights, editor.text(), row);
+        dirty_tx.send(RowChange::Set(row, segments)).ok();
+    }
+}
+
+/// Returns the segments of a row, highlighted if a highlighter is used
+pub(crate) fn line_segments(
+    highlights: Option<&Mutex<HighlightCache>>,
+    text: &TextBuffer,
+    row: usize,
+) -> Vec<TextSegment> {
+    match highlights {
+        Some(highlights) => highlights.lock().line(text, row),
+        None => vec![TextSegment::new(text.line(row))],
+    }
+}
+
+/// Inserts text at a byte offset into the combined text of the segments
+pub(crate) fn insert_segment_text(segments: &mut [TextSegment], at: usize, text: &str) {
+    let mut offset = 0;
+    for segment in segments {
+        if at <= offset + segment.text.len() {
+            segment.text.insert_str(at - offset, text);
+            return;
+        }
+
+        offset += segment.text.len();
+    }
+}
+
+/// Applies an action to the editor, tracking the text being composed by the input method.
+///
+/// Returns true if the text was modified.
+pub(crate) async fn apply_action(
+    editor: &mut TextEditorCore,
+    clipboard: &Clipboard,
+    options: &TextOptions,
+    preedit: &mut Option<Preedit>,
+    action: Action,
+) -> bool {
+    match action {
+        Action::Editor(EditorAction::Edit(EditAction::InsertText(text))) => {
+            let text = options.filter_input(editor, &text);
+            editor.edit(EditAction::InsertText(text));
+            true
+        }
+        Action::Editor(editor_action) => editor.apply_action(editor_action),
+        Action::Copy => {
+            if let Some(sel) = editor.selected_text() {
+                clipboard.set_text(sel).await;
+            }
+            false
+        }
+        Action::Paste => {
+            if let Some(text) = clipboard.get_text().await {
+                let text = options.filter_input(editor, &text);
+                editor.edit(EditAction::InsertText(text));
+            }
+            true
+        }
+        Action::Cut => {
+            if let Some(sel) = editor.selected_text() {
+                clipboard.set_text(sel).await;
+                editor.delete_selected_text();
+            }
+            true
+        }
+        Action::Ime(Ime::Preedit(text, cursor)) => {
+            *preedit = (!text.is_empty()).then(|| Preedit {
+                location: editor.cursor(),
+                text,
+                cursor,
+            });
+
+            false
+        }
+        Action::Ime(Ime::Commit(text)) => {
+            *preedit = None;
+
+            let text = options.filter_input(editor, &text);
+            editor.edit(EditAction::InsertText(text));
+            true
+        }
+        Action::Ime(Ime::Disabled) => {
+            *preedit = None;
+            false
+        }
+        Action::Ime(Ime::Enabled) => false,
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_edit_commands(
     focused: Mutable<bool>,
     layout_glyphs: Mutable<LayoutGlyphs>,
+    text_bounds: Mutable<Option<Mat4>>,
     rx: flume::Receiver<Action>,
     mut editor: TextEditorCore,
     clipboard: Arc<Clipboard>,
     source_content: Arc<dyn Send + Sync + StateDuplex<Item = String>>,
     cursor: Mutable<VisualCursor>,
+    dirty_tx: flume::Sender<RowChange>,
+    highlights: Option<Arc<Mutex<HighlightCache>>>,
+    ime_cursor_area: Option<flume::Sender<Rect>>,
     options: TextOptions,
 ) -> impl Future<Output = ()> {
     let mut layout_glyphs = layout_glyphs.signal_cloned().to_stream().fuse();
@@ -415,54 +886,84 @@ fn process_edit_commands(
 
         let mut glyphs: LayoutGlyphs = LayoutGlyphs::default();
 
-        let source_content = source_content.dedup();
+        // Echoes of the published text are dropped, as they may arrive after newer edits
+        let source_content = source_content.prevent_feedback();
         let mut new_text = throttle(source_content.stream(), || {
             sleep(Duration::from_millis(100))
         })
         .fuse();
 
+        // Publishing copies the whole text, so edits are published at most every 100ms
+        let mut publish: Fuse<Sleep> = Fuse::terminated();
+
         let mut focused = false;
+        let mut preedit: Option<Preedit> = None;
+        let mut last_ime_area = None;
 
         loop {
             futures::select! {
                 focus = focused_signal.select_next_some() => {
                     focused = focus;
+
+                    // Pending edits are published right away when leaving the input
+                    if !focus && !publish.is_terminated() {
+                        publish = Fuse::terminated();
+                        source_content.send(editor.text().to_string());
+                    }
                 }
                 new_text = new_text.select_next_some() => {
                     editor.set_text(new_text.split('\n'));
                 }
+                () = publish => {
+                    source_content.send(editor.text().to_string());
+                }
                 action = rx.select_next_some() => {
-                    let modified_text = match action {
-                        Action::Editor(editor_action) => editor.apply_action(editor_action),
-                        Action::Copy => {
-                            if let Some(sel) = editor.selected_text() {
-                                clipboard.set_text(sel.join("\n")).await;
-                            }
-                            false
-                        }
-                        Action::Paste => {
-                            if let Some(text) = clipboard.get_text().await {
-                                let text = if options.allow_newlines {
-                                    text
-                                } else {
-                                    text.replace('\n', "")
-                                };
+                    let action = match action {
+                        Action::Editor(EditorAction::CursorMove(
+                            m @ (CursorMove::Left | CursorMove::Right),
+                        )) => Action::Editor(EditorAction::SetCarets(
+                            editor
+                                .carets()
+                                .map(|caret| Caret {
+                                    cursor: visual_cursor_move(&editor, &glyphs, caret.cursor, m),
+                                    ..caret
+                                })
+                                .collect(),
+                        )),
+                        Action::Editor(EditorAction::SelectionMove(
+                            m @ (CursorMove::Left | CursorMove::Right),
+                        )) => Action::Editor(EditorAction::SetCarets(
+                            editor
+                                .carets()
+                                .map(|caret| {
+                                    let head = caret.selection.unwrap_or(caret.cursor);
+                                    let selection = visual_cursor_move(&editor, &glyphs, head, m);
+                                    Caret {
+                                        selection: Some(selection),
+                                        ..caret
+                                    }
+                                })
+                                .collect(),
+                        )),
+                        action => action,
+                    };
 
-                                editor.edit(EditAction::InsertText(text));
-                            }
-                            true
-                        }
-                        Action::Cut => {
-                            if let Some(sel) = editor.selected_text() {
-                                clipboard.set_text(sel.join("\n")).await;
-                                editor.delete_selected_text();
-                            }
-                            true
+                    let old_preedit = preedit.as_ref().map(|v| v.location.row);
+                    let modified_text =
+                        apply_action(&mut editor, &clipboard, &options, &mut preedit, action).await;
+
+                    if let Some(row) = old_preedit {
+                        if preedit.as_ref().map(|v| v.location.row) != Some(row) {
+                            clear_preedit_row(&editor, highlights.as_deref(), &dirty_tx, row);
                         }
-                    };
+                    }
 
-                    if modified_text {
-                        source_content.send(editor.lines().iter().map(|v| v.text()).join("\n"));
+                    if let Some(preedit) = &preedit {
+                        update_preedit_row(&editor, highlights.as_deref(), &dirty_tx, preedit);
+                    }
+
+                    if modified_text && publish.is_terminated() {
+                        publish = sleep(Duration::from_millis(100)).fuse();
                     }
                 }
                 new_glyphs = layout_glyphs.select_next_some() => {
@@ -470,106 +971,254 @@ fn process_edit_commands(
                 }
             }
 
-            let cursor_pos = calculate_position(&glyphs, editor.cursor());
+            let cursor_location = match &preedit {
+                Some(preedit) => preedit.cursor.map(|(start, _)| CursorLocation {
+                    row: preedit.location.row,
+                    col: preedit.location.col + start,
+                }),
+                None => Some(editor.cursor()),
+            };
+
+            let cursor_pos = cursor_location
+                .map(|v| calculate_position(&glyphs, v))
+                .unwrap_or_default();
+
+            if let (true, Some(ime_cursor_area)) = (focused, &ime_cursor_area) {
+                let origin = text_bounds
+                    .get()
+                    .map(|v| v.transform_point3(Vec3::ZERO).xy())
+                    .unwrap_or_default();
+
+                let area = Rect::from_size_pos(
+                    vec2(2.0, glyphs.line_height.max(16.0)),
+                    origin + cursor_pos,
+                );
+
+                if last_ime_area != Some(area) {
+                    ime_cursor_area.send(area).ok();
+                    last_ime_area = Some(area);
+                }
+            }
 
             let mut cursor = cursor.lock_mut();
-            if let Some((start, end)) = editor.selection_bounds() {
-                let selected_lines = glyphs
-                    .lines()
-                    .enumerate()
-                    .filter(|(_, v)| v.row >= start.row && v.row <= end.row);
-
-                let lines = selected_lines.filter_map(|(ln, v)| {
-                    let left = if v.row == start.row {
-                        v.glyphs.iter().find(|v| {
-                            v.start >= start.col && (start.row != end.row || v.start < end.col)
-                        })
-                    } else {
-                        // None
-                        v.glyphs.first()
-                    }?;
-                    let right = if v.row == end.row {
-                        v.glyphs.iter().rev().find(|v| {
-                            v.end <= end.col && (start.row != end.row || v.end > start.col)
-                        })
-                    } else {
-                        // None
-                        v.glyphs.last()
-                    }?;
-
-                    let rect = Rect::new(
-                        left.bounds.min + vec2(0.0, ln as f32 * glyphs.line_height),
-                        right.bounds.max + vec2(0.0, ln as f32 * glyphs.line_height),
-                    );
-
-                    Some(
-                        rect, // Positioned::new(
-                             // Rectangle::new(selection_color)
-                             //     .with_min_size(Unit::px(rect.size())),
-                             // )
-                             // .with_offset(Unit::px(rect.pos())),
-                    )
-                });
 
-                cursor.lines.clear();
-                cursor.lines.extend(lines);
-            } else if focused {
-                cursor.lines.clear();
+            cursor.empty = editor.text().is_empty() && preedit.is_none();
+            cursor.preedit = preedit.as_ref().map(|preedit| {
+                let start = calculate_position(&glyphs, preedit.location);
+                let end = calculate_position(
+                    &glyphs,
+                    CursorLocation {
+                        row: preedit.location.row,
+                        col: preedit.location.col + preedit.text.len(),
+                    },
+                );
+
+                let underline_height = 1.5;
+                Rect::from_size_pos(
+                    vec2((end.x - start.x).max(0.0), underline_height),
+                    start + vec2(0.0, glyphs.line_height - underline_height),
+                )
+            });
+            cursor.lines.clear();
+            cursor.carets.clear();
+
+            for (i, caret) in editor.carets().enumerate() {
+                if let Some((start, end)) = caret.selection_bounds() {
+                    cursor.lines.extend(selection_rects(&glyphs, start, end));
+                } else if focused {
+                    // The primary caret is displayed within the text being composed
+                    let pos = match i {
+                        0 if cursor_location.is_none() => continue,
+                        0 => cursor_pos,
+                        _ => calculate_position(&glyphs, caret.cursor),
+                    };
 
-                cursor.cursor = Rect::from_size_pos(vec2(2.0, 16.0), cursor_pos);
-            } else {
-                cursor.cursor = Rect::default();
-                cursor.lines.clear();
-            };
+                    cursor
+                        .carets
+                        .push(Rect::from_size_pos(vec2(2.0, 16.0), pos));
+                }
+            }
         }
     }
 }
 
-struct TextContent {
-    rx: flume::Receiver<(usize, Option<String>)>,
-    text_bounds: Mutable<Option<Mat4>>,
-    layout_glyphs: Mutable<LayoutGlyphs>,
-    style: TextInputStyle,
+/// Change to a displayed row of the text
+pub(crate) enum RowChange {
+    Set(usize, Vec<TextSegment>),
+    Insert(usize, Vec<TextSegment>),
+    Remove(usize),
+    /// Display each character of the text as the given character, or the text itself if `None`
+    Mask(Option<char>),
+}
+
+pub(crate) struct TextContent {
+    pub(crate) rx: flume::Receiver<RowChange>,
+    pub(crate) text_bounds: Mutable<Option<Mat4>>,
+    pub(crate) layout_glyphs: Mutable<LayoutGlyphs>,
+    pub(crate) style: TextInputStyle,
+}
+
+/// Text of a row displayed as mask characters
+struct MaskedText {
+    mask: char,
+    text: String,
+}
+
+impl MaskedText {
+    fn new(mask: char, segments: &[TextSegment]) -> Self {
+        Self {
+            mask,
+            text: segments.iter().map(|v| &*v.text).collect(),
+        }
+    }
+
+    /// Maps an offset in the displayed text to the offset in the original text
+    fn to_source(&self, offset: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(offset / self.mask.len_utf8())
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    fn unmask_glyphs(&self, lines: &mut [LayoutLineGlyphs]) {
+        for line in lines {
+            line.start = self.to_source(line.start);
+            line.end = self.to_source(line.end);
+
+            for glyph in &mut line.glyphs {
+                glyph.start = self.to_source(glyph.start);
+                glyph.end = self.to_source(glyph.end);
+            }
+        }
+    }
+}
+
+struct ContentRow {
+    id: Entity,
+    /// Shared with the glyph monitor, as rows shift when lines are inserted or removed
+    index: Arc<AtomicUsize>,
+    segments: Vec<TextSegment>,
+    masked: Arc<Mutex<Option<MaskedText>>>,
+}
+
+fn display_segments(segments: &[TextSegment], mask: Option<char>) -> Vec<TextSegment> {
+    match mask {
+        Some(mask) => segments
+            .iter()
+            .map(|v| TextSegment {
+                text: iter::repeat(mask).take(v.text.chars().count()).collect(),
+                ..v.clone()
+            })
+            .collect(),
+        None => segments.to_vec(),
+    }
 }
 
 impl Widget for TextContent {
     fn mount(self, scope: &mut Scope<'_>) {
-        let create_row = move |row, text| {
-            let layout_glyphs = self.layout_glyphs.clone();
-            Text::formatted([TextSegment::new(text).with_family(self.style.font_family.clone())])
-                .with_margin(Edges::ZERO)
-                .with_style(self.style.text_style.clone())
-                .monitor(
-                    components::layout_glyphs(),
-                    Box::new(move |glyphs| {
-                        if let Some(new) = glyphs {
-                            let glyphs = &mut *layout_glyphs.lock_mut();
-
-                            glyphs.set_row(row, new.rows[0].clone());
-                            glyphs.line_height = new.line_height;
-                        }
-                    }),
-                )
+        let layout_glyphs = self.layout_glyphs.clone();
+
+        let font_family = self.style.font_family.clone();
+        let with_family = move |segments: Vec<TextSegment>| {
+            segments
+                .into_iter()
+                .map(|v| v.with_family(font_family.clone()))
+                .collect_vec()
         };
 
-        let mut text_items = vec![];
+        let create_row =
+            move |row: Arc<AtomicUsize>, masked: Arc<Mutex<Option<MaskedText>>>, segments| {
+                let layout_glyphs = self.layout_glyphs.clone();
+                Text::formatted(segments)
+                    .with_margin(Edges::ZERO)
+                    .with_style(self.style.text_style.clone())
+                    .monitor(
+                        components::layout_glyphs(),
+                        Box::new(move |glyphs| {
+                            if let Some(new) = glyphs {
+                                let mut lines = new.rows[0].clone();
+                                if let Some(masked) = &*masked.lock() {
+                                    masked.unmask_glyphs(&mut lines);
+                                }
+
+                                let glyphs = &mut *layout_glyphs.lock_mut();
+
+                                glyphs.set_row(row.load(Ordering::Relaxed), lines);
+                                glyphs.line_height = new.line_height;
+                            }
+                        }),
+                    )
+            };
 
-        scope.spawn_stream(self.rx.into_stream(), move |scope, (row, text)| {
-            if let Some(text) = text {
-                if let Some(&id) = text_items.get(row) {
-                    // Access and update the text widget
-                    let scope = scope.frame_mut().scoped(id).unwrap();
+        let mut text_items: Vec<ContentRow> = vec![];
+        let mut mask = None;
+
+        let mut new_row = move |scope: &mut Scope<'_>,
+                                row: usize,
+                                segments: Vec<TextSegment>,
+                                mask: Option<char>| {
+            let index = Arc::new(AtomicUsize::new(row));
+            let masked = Arc::new(Mutex::new(mask.map(|v| MaskedText::new(v, &segments))));
+            let text = display_segments(&segments, mask);
+            let id = scope.attach_at(row, create_row(index.clone(), masked.clone(), text));
+
+            ContentRow {
+                id,
+                index,
+                segments,
+                masked,
+            }
+        };
+
+        scope.spawn_stream(self.rx.into_stream(), move |scope, change| {
+            match change {
+                RowChange::Set(row, segments) => {
+                    let segments = with_family(segments);
+                    if let Some(item) = text_items.get_mut(row) {
+                        *item.masked.lock() = mask.map(|v| MaskedText::new(v, &segments));
 
-                    scope.entity().get_mut(components::text()).unwrap()[0].text = text;
-                } else {
-                    let id = scope.attach(create_row(row, text));
+                        // Access and update the text widget
+                        let scope = scope.frame_mut().scoped(item.id).unwrap();
 
-                    text_items.push(id);
+                        *scope.entity().get_mut(components::text()).unwrap() =
+                            display_segments(&segments, mask);
+                        item.segments = segments;
+                    } else {
+                        let item = new_row(scope, text_items.len(), segments, mask);
+                        text_items.push(item);
+                    }
                 }
-            } else {
-                // Lines were deleted
-                let id = text_items.remove(row);
-                scope.detach(id);
+                RowChange::Insert(row, segments) => {
+                    let row = row.min(text_items.len());
+                    let item = new_row(scope, row, with_family(segments), mask);
+
+                    text_items.insert(row, item);
+                    layout_glyphs.lock_mut().insert_row(row);
+                }
+                RowChange::Remove(row) => {
+                    if row >= text_items.len() {
+                        return;
+                    }
+
+                    let item = text_items.remove(row);
+                    scope.detach(item.id);
+                    layout_glyphs.lock_mut().remove_row(row);
+                }
+                RowChange::Mask(new_mask) => {
+                    mask = new_mask;
+
+                    for item in &text_items {
+                        *item.masked.lock() = mask.map(|v| MaskedText::new(v, &item.segments));
+
+                        let scope = scope.frame_mut().scoped(item.id).unwrap();
+                        *scope.entity().get_mut(components::text()).unwrap() =
+                            display_segments(&item.segments, mask);
+                    }
+                }
+            }
+
+            for (i, item) in text_items.iter().enumerate() {
+                item.index.store(i, Ordering::Relaxed);
             }
         });
 
@@ -579,11 +1228,13 @@ impl Widget for TextContent {
     }
 }
 
-enum Action {
+/// Action of a text input in response to user input
+pub enum Action {
     Editor(EditorAction),
     Copy,
     Paste,
     Cut,
+    Ime(Ime),
 }
 
 pub fn calculate_position(glyphs: &LayoutGlyphs, cursor: CursorLocation) -> Vec2 {
@@ -599,72 +1250,89 @@ pub fn calculate_position(glyphs: &LayoutGlyphs, cursor: CursorLocation) -> Vec2
     }
 }
 
-fn handle_cursor_move(key: NamedKey, mods: ModifiersState) -> Option<CursorMove> {
-    let ctrl = mods.control_key();
-    match key {
-        NamedKey::ArrowLeft if ctrl => Some(CursorMove::BackwardWord),
-        NamedKey::ArrowRight if ctrl => Some(CursorMove::ForwardWord),
-        NamedKey::ArrowLeft => Some(CursorMove::Left),
-        NamedKey::ArrowRight => Some(CursorMove::Right),
-        NamedKey::ArrowUp => Some(CursorMove::Up),
-        NamedKey::ArrowDown => Some(CursorMove::Down),
-        _ => None,
+/// Returns a caret for each visual line between two points, selecting the text between their
+/// horizontal positions.
+///
+/// The caret at the end of the drag is the primary caret.
+pub(crate) fn column_selection(glyphs: &LayoutGlyphs, anchor: Vec2, head: Vec2) -> Vec<Caret> {
+    let line_height = glyphs.line_height.max(1.0);
+    let line_index = |y: f32| (y / line_height).max(0.0) as usize;
+
+    let (first, last) = (line_index(anchor.y), line_index(head.y));
+
+    let mut carets = (first.min(last)..=first.max(last))
+        .filter_map(|ln| {
+            let y = (ln as f32 + 0.5) * line_height;
+            let selection = glyphs.hit(vec2(anchor.x, y))?;
+            let cursor = glyphs.hit(vec2(head.x, y))?;
+            Some(Caret::new(cursor).with_selection(selection))
+        })
+        .collect_vec();
+
+    if last >= first {
+        carets.reverse();
     }
-}
 
-fn handle_input(input: KeyboardInput, send: impl Fn(Action), options: &TextOptions) {
-    let ctrl = input.modifiers.control_key();
-    if let Key::Named(key) = input.key {
-        if let Some(m) = handle_cursor_move(key, input.modifiers) {
-            if input.modifiers.shift_key() {
-                send(Action::Editor(EditorAction::SelectionStart));
-            } else {
-                send(Action::Editor(EditorAction::SelectionClear));
-            }
+    carets
+}
 
-            return send(Action::Editor(EditorAction::CursorMove(m)));
-        }
+/// Moves the cursor left or right on screen, which is the opposite of the text order for
+/// right-to-left text.
+///
+/// Moves in text order when the cursor is at the edge of a line.
+pub(crate) fn visual_cursor_move(
+    editor: &TextEditorCore,
+    glyphs: &LayoutGlyphs,
+    cursor: CursorLocation,
+    m: CursorMove,
+) -> CursorLocation {
+    glyphs
+        .visual_move(cursor, matches!(m, CursorMove::Right))
+        .unwrap_or_else(|| editor.get_new_cursor(m, cursor))
+}
 
-        match key {
-            NamedKey::Backspace if ctrl => {
-                return send(Action::Editor(EditorAction::Edit(
-                    EditAction::DeleteBackwardWord,
-                )))
-            }
-            NamedKey::Backspace => {
-                return send(Action::Editor(EditorAction::Edit(
-                    EditAction::DeleteBackwardChar,
-                )))
-            }
-            NamedKey::Enter if options.allow_newlines => {
-                return send(Action::Editor(EditorAction::Edit(EditAction::InsertLine)))
-            }
-            _ => {}
-        }
-    } else if let Key::Character(c) = input.key {
-        match &*c {
-            "c" if ctrl => return send(Action::Copy),
-            "v" if ctrl => return send(Action::Paste),
-            "x" if ctrl => return send(Action::Cut),
-            _ => {}
-        }
-    }
+/// Returns the highlight of a selection for each visual line.
+///
+/// Lines with mixed text direction may have several disjoint highlights.
+pub(crate) fn selection_rects(
+    glyphs: &LayoutGlyphs,
+    start: CursorLocation,
+    end: CursorLocation,
+) -> impl Iterator<Item = Rect> + '_ {
+    glyphs
+        .lines()
+        .enumerate()
+        .filter(move |(_, v)| v.row >= start.row && v.row <= end.row)
+        .flat_map(move |(ln, v)| {
+            let offset = vec2(0.0, ln as f32 * glyphs.line_height);
+            let is_selected = |glyph: &&LayoutGlyph| {
+                (v.row > start.row || glyph.start >= start.col)
+                    && (v.row < end.row || glyph.end <= end.col)
+            };
 
-    if let Some(text) = input.text {
-        let mut text = text.to_string();
-        if !options.allow_newlines {
-            text = text.replace('\n', "");
-        }
-        send(Action::Editor(EditorAction::Edit(EditAction::InsertText(
-            text,
-        ))));
-    }
+            // Glyphs are in visual order, so consecutive selected glyphs form one highlight
+            v.glyphs
+                .iter()
+                .chunk_by(|glyph| is_selected(glyph))
+                .into_iter()
+                .filter(|(selected, _)| *selected)
+                .filter_map(|(_, mut run)| {
+                    let first = run.next()?;
+                    let last = run.last().unwrap_or(first);
+                    Some(Rect::new(
+                        first.bounds.min + offset,
+                        last.bounds.max + offset,
+                    ))
+                })
+                .collect_vec()
+        })
 }
 
 pub struct InputBox<T> {
     style: TextInputStyle,
     content: Arc<dyn Send + Sync + StateDuplex<Item = T>>,
     options: TextOptions,
+    placeholder: Option<String>,
 }
 
 impl<T> InputBox<T> {
@@ -673,8 +1341,20 @@ impl<T> InputBox<T> {
             content: Arc::new(content),
             style: TextInputStyle::input_box(),
             options: Default::default(),
+            placeholder: None,
         }
     }
+
+    pub fn with_options(mut self, options: TextOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Display text while the input is empty
+    pub fn with_placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = Some(placeholder.into());
+        self
+    }
 }
 
 impl<T> StyleExt for InputBox<T> {
@@ -698,50 +1378,22 @@ where
     T::Err: Display + 'static,
 {
     fn mount(self, scope: &mut Scope<'_>) {
-        let warn_icon = scope.stylesheet().get_clone(icon_warning()).unwrap();
-
-        let parse_state = Mutable::new(Ok(()) as Result<(), String>);
-        let parse_state_content = parse_state.signal_ref(move |v| {
-            let warn_icon = &warn_icon;
-            v.as_ref().err().map(move |err| {
-                InteractiveWidget::new(
-                    bold(warn_icon)
-                        .with_padding(spacing_small())
-                        .with_color(surface_warning()),
-                )
-                .with_tooltip_text(err)
+        let content = self
+            .content
+            .filter_map(|v| Some(v.to_string()), |s| s.trim().parse().ok());
+
+        let mut input = TextInput::new(content)
+            .with_options(self.options)
+            .with_validator(|v| {
+                v.trim()
+                    .parse::<T>()
+                    .map(|_| ())
+                    .map_err(|err| err.to_string())
             })
-        });
+            .with_size_props(self.style.size)
+            .with_style(self.style);
 
-        let content = self.content.filter_map(
-            {
-                to_owned!(parse_state);
-                move |v| {
-                    parse_state.set(Ok(()));
-                    Some(v.to_string())
-                }
-            },
-            move |s| match s.trim().parse() {
-                Ok(v) => {
-                    parse_state.set(Ok(()));
-                    Some(v)
-                }
-                Err(e) => {
-                    parse_state.set(Err(e.to_string()));
-                    None
-                }
-            },
-        );
-
-        Stack::new((
-            TextInput::new(content)
-                .with_options(self.options)
-                .with_size_props(self.style.size)
-                .with_style(self.style),
-            Stack::new((SignalWidget::new(parse_state_content),))
-                .with_padding(spacing_small())
-                .with_item_align(LayoutAlignment::new(Align::End, Align::Center)),
-        ))
-        .mount(scope);
+        input.placeholder = self.placeholder;
+        input.mount(scope);
     }
 }
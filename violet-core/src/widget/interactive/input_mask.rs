@@ -0,0 +1,149 @@
+/// A pattern which text input is formatted to, such as `##:##` for a time.
+///
+/// `#` accepts a digit, `A` a letter and `*` any character. All other characters are literals,
+/// which are inserted as the user types.
+///
+/// ```
+/// # use violet_core::widget::interactive::InputMask;
+/// let mask = InputMask::new("+1 (###) ###-####");
+/// assert_eq!(mask.format("5551234", 7), ("+1 (555) 123-4".to_string(), 14));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputMask {
+    pattern: Vec<char>,
+}
+
+impl InputMask {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.chars().collect(),
+        }
+    }
+
+    /// Returns the number of characters the user can enter
+    pub fn capacity(&self) -> usize {
+        self.pattern.iter().filter(|&&v| !is_literal(v)).count()
+    }
+
+    /// Formats `text` to the mask.
+    ///
+    /// Characters which are not accepted by the mask are dropped, and literals are inserted up
+    /// to the last entered character. Returns the formatted text and the new byte offset of the
+    /// cursor at `cursor`.
+    pub fn format(&self, text: &str, cursor: usize) -> (String, usize) {
+        let mut input = Vec::new();
+        let mut input_before_cursor = 0;
+        // A literal is missing at the cursor, such as after backspacing over it
+        let mut removed_literal = false;
+        let mut slot = 0;
+
+        for (i, c) in text.char_indices() {
+            // Literals which are already present are skipped rather than taken as input
+            if self
+                .pattern
+                .get(slot)
+                .is_some_and(|&v| is_literal(v) && v == c)
+            {
+                slot += 1;
+                continue;
+            }
+
+            if i == cursor && self.pattern.get(slot).is_some_and(|&v| is_literal(v)) {
+                removed_literal = true;
+            }
+
+            while self.pattern.get(slot).is_some_and(|&v| is_literal(v)) {
+                slot += 1;
+            }
+
+            let Some(&placeholder) = self.pattern.get(slot) else {
+                break;
+            };
+
+            if accepts(placeholder, c) {
+                input.push(c);
+                slot += 1;
+
+                if i < cursor {
+                    input_before_cursor += 1;
+                }
+            }
+        }
+
+        let mut formatted = String::new();
+        let mut new_cursor = 0;
+        let mut taken = 0;
+
+        for &slot in &self.pattern {
+            if taken == input.len() {
+                break;
+            }
+
+            if is_literal(slot) {
+                formatted.push(slot);
+            } else {
+                formatted.push(input[taken]);
+                taken += 1;
+            }
+
+            // Keep the cursor after leading literals, so that typing does not go before them.
+            //
+            // The cursor stays before a removed literal, as it would otherwise be restored in
+            // front of the cursor and the deletion undone.
+            let before_removed =
+                removed_literal && taken > 0 && taken == input_before_cursor && is_literal(slot);
+
+            if taken <= input_before_cursor && !before_removed {
+                new_cursor = formatted.len();
+            }
+        }
+
+        (formatted, new_cursor)
+    }
+}
+
+fn is_literal(c: char) -> bool {
+    !matches!(c, '#' | 'A' | '*')
+}
+
+fn accepts(placeholder: char, c: char) -> bool {
+    match placeholder {
+        '#' => c.is_ascii_digit(),
+        'A' => c.is_alphabetic(),
+        _ => !c.is_control(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn format_time() {
+        let mask = InputMask::new("##:##");
+
+        assert_eq!(mask.format("", 0), (String::new(), 0));
+        assert_eq!(mask.format("12", 2), ("12".into(), 2));
+        assert_eq!(mask.format("123", 3), ("12:3".into(), 4));
+        assert_eq!(mask.format("12:345", 6), ("12:34".into(), 5));
+        assert_eq!(mask.format("1a2", 3), ("12".into(), 2));
+        // Backspacing over the last digit leaves a dangling literal
+        assert_eq!(mask.format("12:", 3), ("12".into(), 2));
+        // Backspacing over a literal moves the cursor before it
+        assert_eq!(mask.format("1234", 2), ("12:34".into(), 2));
+        assert_eq!(mask.capacity(), 4);
+    }
+
+    #[test]
+    fn format_phone_number() {
+        let mask = InputMask::new("+1 (###) ###-####");
+
+        assert_eq!(mask.format("5", 1), ("+1 (5".into(), 5));
+        // The literal `1` is not taken as input
+        assert_eq!(mask.format("+1 (55", 6), ("+1 (55".into(), 6));
+        assert_eq!(mask.format("+1 (5556", 8), ("+1 (555) 6".into(), 10));
+        // Editing in the middle keeps the cursor after the inserted digit
+        assert_eq!(mask.format("+1 (5955) 6", 6), ("+1 (595) 56".into(), 6));
+        assert_eq!(mask.format("+1 (555)6", 8), ("+1 (555) 6".into(), 7));
+    }
+}
@@ -0,0 +1,629 @@
+use std::sync::Arc;
+
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+use super::{Action, TextOptions};
+use crate::{
+    declare_atom,
+    editor::{CursorMove, EditAction, EditorAction},
+    input::KeyboardInput,
+};
+
+declare_atom! {
+    /// Key bindings of text inputs which do not set their own
+    pub keymap: Keymap,
+}
+
+const NONE: ModifiersState = ModifiersState::empty();
+const SHIFT: ModifiersState = ModifiersState::SHIFT;
+const CTRL: ModifiersState = ModifiersState::CONTROL;
+const ALT: ModifiersState = ModifiersState::ALT;
+const SUPER: ModifiersState = ModifiersState::SUPER;
+
+/// Handles the key presses of a text input.
+///
+/// A handler is created for each input, and may keep state such as the current mode.
+pub trait KeyHandler: Send + Sync {
+    /// Sends the actions of a pressed key
+    fn handle(
+        &mut self,
+        input: &KeyboardInput,
+        options: &TextOptions,
+        send: &mut dyn FnMut(Action),
+    );
+}
+
+/// Key bindings of text inputs.
+///
+/// Set for a single input using `with_keymap`, or for all inputs using the [`keymap`] atom.
+#[derive(Clone)]
+pub struct Keymap {
+    create: Arc<dyn Send + Sync + Fn() -> Box<dyn KeyHandler>>,
+}
+
+impl Keymap {
+    /// Creates a keymap from a function creating the handler of each input
+    pub fn new<H: 'static + KeyHandler>(create: impl 'static + Send + Sync + Fn() -> H) -> Self {
+        Self {
+            create: Arc::new(move || Box::new(create())),
+        }
+    }
+
+    /// Common desktop bindings, using Ctrl for shortcuts
+    pub fn desktop() -> Self {
+        Bindings::desktop().into()
+    }
+
+    /// macOS bindings, using Cmd for shortcuts and Alt to move by words
+    pub fn macos() -> Self {
+        Bindings::macos().into()
+    }
+
+    /// Emacs-style readline bindings, such as Ctrl+A and Ctrl+E to move to the start and end of
+    /// the line
+    pub fn emacs() -> Self {
+        Bindings::emacs().into()
+    }
+
+    /// Modal vi-style bindings.
+    ///
+    /// Inputs start in insert mode, where the desktop bindings are used. Escape enters normal
+    /// mode.
+    pub fn vi() -> Self {
+        Self::new(ViKeys::default)
+    }
+
+    pub(crate) fn handler(&self) -> Box<dyn KeyHandler> {
+        (self.create)()
+    }
+}
+
+impl Default for Keymap {
+    /// The bindings of the current platform
+    fn default() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::macos()
+        } else {
+            Self::desktop()
+        }
+    }
+}
+
+impl From<Bindings> for Keymap {
+    fn from(bindings: Bindings) -> Self {
+        Self::new(move || bindings.clone())
+    }
+}
+
+/// Editing command bound to a key
+#[derive(Debug, Clone, Copy)]
+pub enum KeyCommand {
+    /// Moves the cursor, extending the selection if shift is held
+    Move(CursorMove),
+    DeleteBackwardChar,
+    DeleteForwardChar,
+    DeleteBackwardWord,
+    /// Deletes the text between the cursor and where it would be moved
+    DeleteTo(CursorMove),
+    /// Cuts the text between the cursor and where it would be moved to the clipboard
+    KillTo(CursorMove),
+    DeleteLine,
+    /// Inserts a line break, if allowed by the input
+    InsertLine,
+    Copy,
+    Cut,
+    Paste,
+    Undo,
+    Redo,
+    SelectAll,
+    AddNextOccurrence,
+    ClearCarets,
+}
+
+impl KeyCommand {
+    /// Sends the actions of the command.
+    ///
+    /// Moving the cursor extends the selection if `select` is set.
+    pub fn send(self, select: bool, options: &TextOptions, send: &mut dyn FnMut(Action)) {
+        let edit = |action| Action::Editor(EditorAction::Edit(action));
+
+        match self {
+            KeyCommand::Move(m) => {
+                let selection = if select {
+                    EditorAction::SelectionStart
+                } else {
+                    EditorAction::SelectionClear
+                };
+
+                send(Action::Editor(selection));
+                send(Action::Editor(EditorAction::CursorMove(m)));
+            }
+            KeyCommand::DeleteBackwardChar => send(edit(EditAction::DeleteBackwardChar)),
+            KeyCommand::DeleteForwardChar => send(edit(EditAction::DeleteForwardChar)),
+            KeyCommand::DeleteBackwardWord => send(edit(EditAction::DeleteBackwardWord)),
+            KeyCommand::DeleteTo(m) | KeyCommand::KillTo(m) => {
+                send(Action::Editor(EditorAction::SelectionClear));
+                send(Action::Editor(EditorAction::SelectionStart));
+                send(Action::Editor(EditorAction::CursorMove(m)));
+
+                if let KeyCommand::KillTo(_) = self {
+                    send(Action::Cut);
+                } else {
+                    // Deletes the selection
+                    send(edit(EditAction::DeleteBackwardChar));
+                }
+            }
+            KeyCommand::DeleteLine => send(edit(EditAction::DeleteLine)),
+            KeyCommand::InsertLine => {
+                if options.allow_newlines {
+                    send(edit(EditAction::InsertLine));
+                }
+            }
+            KeyCommand::Copy => send(Action::Copy),
+            KeyCommand::Cut => send(Action::Cut),
+            KeyCommand::Paste => send(Action::Paste),
+            KeyCommand::Undo => send(Action::Editor(EditorAction::Undo)),
+            KeyCommand::Redo => send(Action::Editor(EditorAction::Redo)),
+            KeyCommand::SelectAll => send(Action::Editor(EditorAction::SelectAll)),
+            KeyCommand::AddNextOccurrence => send(Action::Editor(EditorAction::AddNextOccurrence)),
+            KeyCommand::ClearCarets => send(Action::Editor(EditorAction::ClearCarets)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct KeyBinding {
+    key: Key,
+    modifiers: ModifiersState,
+    command: KeyCommand,
+}
+
+/// A table of key bindings.
+///
+/// Keys which are not bound insert their text.
+#[derive(Debug, Clone, Default)]
+pub struct Bindings {
+    bindings: Vec<KeyBinding>,
+}
+
+impl Bindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds a key to a command, replacing any previous binding of the key.
+    ///
+    /// Characters are matched regardless of case, so use [`ModifiersState::SHIFT`] to bind
+    /// uppercase characters.
+    pub fn bind(mut self, key: Key, modifiers: ModifiersState, command: KeyCommand) -> Self {
+        let key = match key {
+            Key::Character(c) => Key::Character(c.to_lowercase().into()),
+            key => key,
+        };
+
+        self.bindings
+            .retain(|v| v.key != key || v.modifiers != modifiers);
+        self.bindings.push(KeyBinding {
+            key,
+            modifiers,
+            command,
+        });
+        self
+    }
+
+    fn named(self, key: NamedKey, modifiers: ModifiersState, command: KeyCommand) -> Self {
+        self.bind(Key::Named(key), modifiers, command)
+    }
+
+    fn named_move(self, key: NamedKey, modifiers: ModifiersState, m: CursorMove) -> Self {
+        self.named(key, modifiers, KeyCommand::Move(m))
+    }
+
+    fn char(self, c: &str, modifiers: ModifiersState, command: KeyCommand) -> Self {
+        self.bind(Key::Character(c.into()), modifiers, command)
+    }
+
+    /// Common desktop bindings, using Ctrl for shortcuts
+    pub fn desktop() -> Self {
+        Self::common(CTRL, CTRL)
+    }
+
+    /// macOS bindings, using Cmd for shortcuts and Alt to move by words
+    pub fn macos() -> Self {
+        Self::common(SUPER, ALT)
+            .named_move(NamedKey::ArrowLeft, SUPER, CursorMove::LineStart)
+            .named_move(NamedKey::ArrowRight, SUPER, CursorMove::LineEnd)
+            .named_move(NamedKey::ArrowUp, SUPER, CursorMove::TextStart)
+            .named_move(NamedKey::ArrowDown, SUPER, CursorMove::TextEnd)
+            .named(
+                NamedKey::Backspace,
+                SUPER,
+                KeyCommand::DeleteTo(CursorMove::LineStart),
+            )
+            .char("a", CTRL, KeyCommand::Move(CursorMove::LineStart))
+            .char("e", CTRL, KeyCommand::Move(CursorMove::LineEnd))
+            .char("k", CTRL, KeyCommand::KillTo(CursorMove::LineEnd))
+    }
+
+    /// Emacs-style readline bindings on top of the desktop bindings
+    pub fn emacs() -> Self {
+        Self::desktop()
+            .char("a", CTRL, KeyCommand::Move(CursorMove::LineStart))
+            .char("e", CTRL, KeyCommand::Move(CursorMove::LineEnd))
+            .char("b", CTRL, KeyCommand::Move(CursorMove::Left))
+            .char("f", CTRL, KeyCommand::Move(CursorMove::Right))
+            .char("p", CTRL, KeyCommand::Move(CursorMove::Up))
+            .char("n", CTRL, KeyCommand::Move(CursorMove::Down))
+            .char("b", ALT, KeyCommand::Move(CursorMove::BackwardWord))
+            .char("f", ALT, KeyCommand::Move(CursorMove::ForwardWord))
+            .char("d", CTRL, KeyCommand::DeleteForwardChar)
+            .char("h", CTRL, KeyCommand::DeleteBackwardChar)
+            .char("w", CTRL, KeyCommand::DeleteBackwardWord)
+            .char("d", ALT, KeyCommand::KillTo(CursorMove::ForwardWord))
+            .char("k", CTRL, KeyCommand::KillTo(CursorMove::LineEnd))
+            .char("u", CTRL, KeyCommand::KillTo(CursorMove::LineStart))
+            .char("y", CTRL, KeyCommand::Paste)
+            .char("/", CTRL, KeyCommand::Undo)
+            .char("_", CTRL, KeyCommand::Undo)
+    }
+
+    /// Bindings shared by all profiles, using `primary` for shortcuts and `word` to move by words
+    fn common(primary: ModifiersState, word: ModifiersState) -> Self {
+        Self::new()
+            .named_move(NamedKey::ArrowLeft, NONE, CursorMove::Left)
+            .named_move(NamedKey::ArrowRight, NONE, CursorMove::Right)
+            .named_move(NamedKey::ArrowUp, NONE, CursorMove::Up)
+            .named_move(NamedKey::ArrowDown, NONE, CursorMove::Down)
+            .named_move(NamedKey::ArrowLeft, word, CursorMove::BackwardWord)
+            .named_move(NamedKey::ArrowRight, word, CursorMove::ForwardWord)
+            .named_move(NamedKey::Home, NONE, CursorMove::LineStart)
+            .named_move(NamedKey::End, NONE, CursorMove::LineEnd)
+            .named_move(NamedKey::Home, CTRL, CursorMove::TextStart)
+            .named_move(NamedKey::End, CTRL, CursorMove::TextEnd)
+            .named(NamedKey::Backspace, NONE, KeyCommand::DeleteBackwardChar)
+            .named(NamedKey::Backspace, word, KeyCommand::DeleteBackwardWord)
+            .named(NamedKey::Delete, NONE, KeyCommand::DeleteForwardChar)
+            .named(NamedKey::Enter, NONE, KeyCommand::InsertLine)
+            .named(NamedKey::Escape, NONE, KeyCommand::ClearCarets)
+            .char("c", primary, KeyCommand::Copy)
+            .char("v", primary, KeyCommand::Paste)
+            .char("x", primary, KeyCommand::Cut)
+            .char("a", primary, KeyCommand::SelectAll)
+            .char("d", primary, KeyCommand::AddNextOccurrence)
+            .char("z", primary, KeyCommand::Undo)
+            .char("z", primary | SHIFT, KeyCommand::Redo)
+            .char("y", primary, KeyCommand::Redo)
+    }
+
+    fn find(&self, key: &Key, modifiers: ModifiersState) -> Option<KeyCommand> {
+        let key = match key {
+            Key::Character(c) => Key::Character(c.to_lowercase().into()),
+            key => key.clone(),
+        };
+
+        self.bindings
+            .iter()
+            .find(|v| v.key == key && v.modifiers == modifiers)
+            .map(|v| v.command)
+    }
+
+    /// Returns the command of a key, and whether shift was held to extend the selection
+    fn lookup(&self, input: &KeyboardInput) -> Option<(KeyCommand, bool)> {
+        if let Some(command) = self.find(&input.key, input.modifiers) {
+            return Some((command, false));
+        }
+
+        let mut unshifted = input.modifiers;
+        unshifted.remove(SHIFT);
+
+        if unshifted != input.modifiers {
+            return Some((self.find(&input.key, unshifted)?, true));
+        }
+
+        None
+    }
+}
+
+impl KeyHandler for Bindings {
+    fn handle(
+        &mut self,
+        input: &KeyboardInput,
+        options: &TextOptions,
+        send: &mut dyn FnMut(Action),
+    ) {
+        if let Some((command, select)) = self.lookup(input) {
+            command.send(select, options, send);
+        } else if let Some(text) = &input.text {
+            send(Action::Editor(EditorAction::Edit(EditAction::InsertText(
+                text.to_string(),
+            ))));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ViMode {
+    Insert,
+    Normal,
+    /// Movement extends the selection
+    Visual,
+}
+
+/// Modal vi-style key handler
+struct ViKeys {
+    mode: ViMode,
+    /// First key of a two key command, such as `dd`
+    pending: Option<char>,
+    insert: Bindings,
+}
+
+impl Default for ViKeys {
+    fn default() -> Self {
+        Self {
+            mode: ViMode::Insert,
+            pending: None,
+            insert: Bindings::desktop(),
+        }
+    }
+}
+
+fn vi_motion(c: &str) -> Option<CursorMove> {
+    match c {
+        "h" => Some(CursorMove::Left),
+        "l" => Some(CursorMove::Right),
+        "j" => Some(CursorMove::Down),
+        "k" => Some(CursorMove::Up),
+        "w" => Some(CursorMove::ForwardWord),
+        "b" => Some(CursorMove::BackwardWord),
+        "0" => Some(CursorMove::LineStart),
+        "$" => Some(CursorMove::LineEnd),
+        "G" => Some(CursorMove::TextEnd),
+        _ => None,
+    }
+}
+
+impl KeyHandler for ViKeys {
+    fn handle(
+        &mut self,
+        input: &KeyboardInput,
+        options: &TextOptions,
+        send: &mut dyn FnMut(Action),
+    ) {
+        if self.mode == ViMode::Insert {
+            if input.key == Key::Named(NamedKey::Escape) {
+                self.mode = ViMode::Normal;
+            } else {
+                self.insert.handle(input, options, send);
+            }
+
+            return;
+        }
+
+        let visual = self.mode == ViMode::Visual;
+        let pending = self.pending.take();
+
+        let c = match &input.key {
+            Key::Named(NamedKey::Escape) => {
+                if visual {
+                    send(Action::Editor(EditorAction::SelectionClear));
+                } else {
+                    send(Action::Editor(EditorAction::ClearCarets));
+                }
+
+                self.mode = ViMode::Normal;
+                return;
+            }
+            Key::Character(c) if input.modifiers.control_key() => {
+                if c.as_str() == "r" {
+                    KeyCommand::Redo.send(false, options, send);
+                }
+
+                return;
+            }
+            Key::Character(c) => c.as_str(),
+            // Arrow keys and the like move in all modes
+            _ => {
+                if let Some((command @ KeyCommand::Move(_), _)) = self.insert.lookup(input) {
+                    command.send(visual, options, send);
+                }
+
+                return;
+            }
+        };
+
+        if let Some(m) = vi_motion(c) {
+            match pending {
+                Some('d') => KeyCommand::KillTo(m).send(false, options, send),
+                _ => KeyCommand::Move(m).send(visual, options, send),
+            }
+
+            return;
+        }
+
+        let insert: &[KeyCommand] = match c {
+            "i" => &[],
+            "a" => &[KeyCommand::Move(CursorMove::Right)],
+            "A" => &[KeyCommand::Move(CursorMove::LineEnd)],
+            "I" => &[KeyCommand::Move(CursorMove::LineStart)],
+            "o" => &[
+                KeyCommand::Move(CursorMove::LineEnd),
+                KeyCommand::InsertLine,
+            ],
+            "O" => &[
+                KeyCommand::Move(CursorMove::LineStart),
+                KeyCommand::InsertLine,
+                KeyCommand::Move(CursorMove::Up),
+            ],
+            _ => &[],
+        };
+
+        match (pending, c) {
+            (Some('g'), "g") => KeyCommand::Move(CursorMove::TextStart).send(visual, options, send),
+            (Some('d'), "d") => KeyCommand::DeleteLine.send(false, options, send),
+            (_, "g") => self.pending = Some('g'),
+            (_, "d" | "x") if visual => {
+                KeyCommand::Cut.send(false, options, send);
+                self.mode = ViMode::Normal;
+            }
+            (_, "y") if visual => {
+                KeyCommand::Copy.send(false, options, send);
+                send(Action::Editor(EditorAction::SelectionClear));
+                self.mode = ViMode::Normal;
+            }
+            (_, "v") if visual => {
+                send(Action::Editor(EditorAction::SelectionClear));
+                self.mode = ViMode::Normal;
+            }
+            (_, "v") => {
+                send(Action::Editor(EditorAction::SelectionClear));
+                send(Action::Editor(EditorAction::SelectionStart));
+                self.mode = ViMode::Visual;
+            }
+            (_, "d") => self.pending = Some('d'),
+            (_, "x") => KeyCommand::DeleteForwardChar.send(false, options, send),
+            (_, "X") => KeyCommand::DeleteBackwardChar.send(false, options, send),
+            (_, "D") => KeyCommand::KillTo(CursorMove::LineEnd).send(false, options, send),
+            (_, "p") => KeyCommand::Paste.send(false, options, send),
+            (_, "u") => KeyCommand::Undo.send(false, options, send),
+            (_, "i" | "a" | "A" | "I" | "o" | "O") => {
+                for command in insert {
+                    command.send(false, options, send);
+                }
+
+                self.mode = ViMode::Insert;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use winit::event::ElementState;
+
+    use super::*;
+
+    fn press(key: Key, modifiers: ModifiersState) -> KeyboardInput {
+        let text = match &key {
+            Key::Character(c) if modifiers.is_empty() || modifiers == SHIFT => Some(c.clone()),
+            _ => None,
+        };
+
+        KeyboardInput {
+            modifiers,
+            state: ElementState::Pressed,
+            key,
+            text,
+        }
+    }
+
+    fn key(c: &str) -> KeyboardInput {
+        press(Key::Character(c.into()), NONE)
+    }
+
+    /// Returns a description of the actions sent for each key
+    fn actions(handler: &mut dyn KeyHandler, keys: &[KeyboardInput]) -> Vec<String> {
+        let options = TextOptions::default().with_allow_newlines(true);
+        let mut actions = Vec::new();
+        for key in keys {
+            handler.handle(key, &options, &mut |action| {
+                actions.push(match action {
+                    Action::Editor(EditorAction::CursorMove(m)) => format!("move {m:?}"),
+                    Action::Editor(EditorAction::SelectionStart) => "select".into(),
+                    Action::Editor(EditorAction::SelectionClear) => "clear".into(),
+                    Action::Editor(EditorAction::Edit(EditAction::InsertText(text))) => {
+                        format!("insert {text}")
+                    }
+                    Action::Editor(EditorAction::Edit(EditAction::DeleteLine)) => {
+                        "delete line".into()
+                    }
+                    Action::Editor(EditorAction::Undo) => "undo".into(),
+                    Action::Cut => "cut".into(),
+                    Action::Paste => "paste".into(),
+                    _ => "other".into(),
+                })
+            });
+        }
+
+        actions
+    }
+
+    #[test]
+    fn desktop() {
+        let mut handler = Bindings::desktop();
+
+        assert_eq!(
+            actions(
+                &mut handler,
+                &[
+                    press(Key::Named(NamedKey::ArrowLeft), SHIFT),
+                    press(Key::Named(NamedKey::Home), NONE),
+                    key("a"),
+                    press(Key::Character("Z".into()), CTRL),
+                ]
+            ),
+            [
+                "select",
+                "move Left",
+                "clear",
+                "move LineStart",
+                "insert a",
+                "undo"
+            ]
+        );
+    }
+
+    #[test]
+    fn emacs() {
+        let mut handler = Bindings::emacs();
+
+        assert_eq!(
+            actions(
+                &mut handler,
+                &[
+                    press(Key::Character("e".into()), CTRL),
+                    press(Key::Character("k".into()), CTRL),
+                    press(Key::Character("y".into()), CTRL),
+                ]
+            ),
+            [
+                "clear",
+                "move LineEnd",
+                "clear",
+                "select",
+                "move LineEnd",
+                "cut",
+                "paste"
+            ]
+        );
+    }
+
+    #[test]
+    fn vi_modes() {
+        let mut handler = ViKeys::default();
+        let escape = press(Key::Named(NamedKey::Escape), NONE);
+
+        assert_eq!(
+            actions(&mut handler, &[key("x"), escape.clone(), key("x")]),
+            ["insert x", "other"]
+        );
+        assert_eq!(handler.mode, ViMode::Normal);
+
+        assert_eq!(
+            actions(&mut handler, &[key("d"), key("d"), key("d"), key("w")]),
+            ["delete line", "clear", "select", "move ForwardWord", "cut"]
+        );
+
+        assert_eq!(
+            actions(&mut handler, &[key("v"), key("l"), key("d")]),
+            ["clear", "select", "select", "move Right", "cut"]
+        );
+        assert_eq!(handler.mode, ViMode::Normal);
+
+        assert_eq!(
+            actions(&mut handler, &[key("A"), key("y")]),
+            ["clear", "move LineEnd", "insert y"]
+        );
+        assert_eq!(handler.mode, ViMode::Insert);
+    }
+}
@@ -0,0 +1,1175 @@
+use std::{cell::RefCell, sync::Arc};
+
+use flax::{component, Entity, World};
+use futures::StreamExt;
+use futures_signals::signal::{Mutable, SignalExt};
+use glam::{vec2, Vec2, Vec3, Vec3Swizzles};
+use palette::Srgba;
+use parking_lot::Mutex;
+use winit::{
+    event::{ElementState, MouseButton},
+    keyboard::{Key, ModifiersState, NamedKey},
+};
+
+use crate::{
+    command::CommandRegistry,
+    components::{color, offset, rect, screen_transform},
+    hierarchy::{find_widget_intersect, OrderedDfsIterator},
+    input::{
+        interactive, keep_focus, on_cursor_hover, on_cursor_move, on_keyboard_input,
+        on_mouse_input, on_shortcut, request_focus_sender,
+    },
+    state::StateDuplex,
+    stored::WeakHandle,
+    style::{
+        default_corner_radius, element_disabled, element_primary, element_secondary,
+        element_tertiary, icon_check, icon_chevron, spacing_small, surface_hover,
+        surface_interactive, surface_secondary, Background, SizeExt,
+    },
+    text::{TextSegment, UnderlineStyle},
+    to_owned,
+    unit::Unit,
+    widget::{
+        col, label, row, Button, ButtonStyle, InteractiveWidget, IterWidgetCollection, Rectangle,
+        Stack, StreamWidget, Text,
+    },
+    Rect, Scope, ScopeRef, Widget,
+};
+
+use super::overlay::{overlay_state, CloseOnDropHandle, Overlay, OverlayHandle, OverlayState};
+
+pub type MenuCallback = Arc<dyn Send + Sync + Fn(&ScopeRef<'_>)>;
+
+component! {
+    /// Depth and index of a selectable menu entry
+    menu_entry: (usize, usize),
+    menu_panel: (),
+}
+
+#[derive(Clone)]
+enum MenuItemKind {
+    Action(Option<MenuCallback>),
+    Check {
+        state: Arc<dyn Send + Sync + StateDuplex<Item = bool>>,
+        current: Mutable<bool>,
+    },
+    Submenu(Menu),
+    Separator,
+}
+
+/// A single entry in a [`Menu`]
+#[derive(Clone)]
+pub struct MenuItem {
+    label: String,
+    mnemonic: Option<Mnemonic>,
+    icon: Option<String>,
+    shortcut: Option<String>,
+    enabled: bool,
+    kind: MenuItemKind,
+}
+
+impl MenuItem {
+    fn from_kind(label: String, kind: MenuItemKind) -> Self {
+        let (label, mnemonic) = parse_mnemonic(&label);
+        Self {
+            label,
+            mnemonic,
+            icon: None,
+            shortcut: None,
+            enabled: true,
+            kind,
+        }
+    }
+
+    /// An entry which invokes [`Self::on_select`] when chosen.
+    ///
+    /// A letter preceded by `&` in the label, such as `&Save`, chooses the entry when pressed while
+    /// the menu is open. Use `&&` for a literal ampersand.
+    pub fn new(label: impl Into<String>) -> Self {
+        Self::from_kind(label.into(), MenuItemKind::Action(None))
+    }
+
+    /// An entry which invokes a registered command, showing its name and shortcut.
+    ///
+    /// The entry is disabled if no such command is registered.
+    pub fn command(commands: &CommandRegistry, id: &str) -> Self {
+        let Some(command) = commands.get(id) else {
+            return Self::new(id).disabled();
+        };
+
+        let mut item = Self::new(command.name());
+        item.shortcut = command.shortcut().map(|v| v.to_string());
+        item.on_select(move |scope| command.invoke(scope))
+    }
+
+    /// An entry which toggles the state when chosen, and shows a check mark while set
+    pub fn checkable(
+        label: impl Into<String>,
+        state: impl 'static + Send + Sync + StateDuplex<Item = bool>,
+    ) -> Self {
+        Self::from_kind(
+            label.into(),
+            MenuItemKind::Check {
+                state: Arc::new(state),
+                current: Mutable::new(false),
+            },
+        )
+    }
+
+    /// An entry which opens a nested menu when hovered or chosen
+    pub fn submenu(label: impl Into<String>, menu: Menu) -> Self {
+        Self::from_kind(label.into(), MenuItemKind::Submenu(menu))
+    }
+
+    pub fn separator() -> Self {
+        Self::from_kind(String::new(), MenuItemKind::Separator)
+    }
+
+    pub fn on_select(mut self, on_select: impl 'static + Send + Sync + Fn(&ScopeRef<'_>)) -> Self {
+        self.kind = MenuItemKind::Action(Some(Arc::new(on_select)));
+        self
+    }
+
+    pub fn with_icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    /// Set the shortcut hint shown next to the label.
+    ///
+    /// This is only a hint, the shortcut itself is not handled by the menu.
+    pub fn with_shortcut(mut self, shortcut: impl Into<String>) -> Self {
+        self.shortcut = Some(shortcut.into());
+        self
+    }
+
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn disabled(self) -> Self {
+        self.with_enabled(false)
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn is_selectable(&self) -> bool {
+        self.enabled && !matches!(self.kind, MenuItemKind::Separator)
+    }
+}
+
+/// A list of menu entries.
+///
+/// Shown on right click by [`ContextMenu`], or opened directly using [`Menu::open`].
+#[derive(Clone, Default)]
+pub struct Menu {
+    items: Vec<MenuItem>,
+}
+
+impl Menu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_item(mut self, item: MenuItem) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    pub fn with_items(mut self, items: impl IntoIterator<Item = MenuItem>) -> Self {
+        self.items.extend(items);
+        self
+    }
+
+    pub fn items(&self) -> &[MenuItem] {
+        &self.items
+    }
+
+    /// Opens the menu as an overlay.
+    ///
+    /// The menu closes when an entry is chosen, when clicking outside of it, or when pressing
+    /// escape.
+    pub fn open(self, overlays: &OverlayState, placement: MenuPlacement) -> OverlayHandle {
+        overlays.open(MenuOverlay {
+            menu: self,
+            placement,
+            highlight_first: false,
+            hooks: MenuHooks::default(),
+        })
+    }
+}
+
+/// Screen space position of a menu.
+///
+/// If the menu does not fit on screen it is flipped to end at `flip` instead of starting at `pos`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MenuPlacement {
+    pub pos: Vec2,
+    pub flip: Vec2,
+}
+
+impl MenuPlacement {
+    /// Place the menu with its corner at `pos`, such as the mouse cursor
+    pub fn at(pos: Vec2) -> Self {
+        Self { pos, flip: pos }
+    }
+
+    /// Place the menu below `anchor`, flipping above it
+    pub fn below(anchor: Rect) -> Self {
+        Self {
+            pos: vec2(anchor.min.x, anchor.max.y),
+            flip: vec2(anchor.max.x, anchor.min.y),
+        }
+    }
+
+    /// Place the menu to the right of `anchor`, flipping to the left of it
+    pub fn beside(anchor: Rect) -> Self {
+        Self {
+            pos: vec2(anchor.max.x, anchor.min.y),
+            flip: vec2(anchor.min.x, anchor.max.y),
+        }
+    }
+
+    /// Returns the top left corner of a menu of `size` kept within `bounds`
+    pub fn resolve(&self, size: Vec2, bounds: Rect) -> Vec2 {
+        let mut pos = self.pos;
+
+        if pos.x + size.x > bounds.max.x {
+            pos.x = self.flip.x - size.x;
+        }
+
+        if pos.y + size.y > bounds.max.y {
+            pos.y = self.flip.y - size.y;
+        }
+
+        pos.min(bounds.max - size).max(bounds.min)
+    }
+}
+
+/// Opens a menu at the cursor when the widget is right clicked
+pub struct ContextMenu<W> {
+    widget: W,
+    menu: Menu,
+}
+
+impl<W> ContextMenu<W> {
+    pub fn new(widget: W, menu: Menu) -> Self {
+        Self { widget, menu }
+    }
+}
+
+impl<W: Widget> Widget for ContextMenu<W> {
+    fn mount(self, scope: &mut Scope<'_>) {
+        let overlays = scope.get_context_cloned(overlay_state());
+        let current_menu = Mutable::new(None);
+        let cursor_pos = Mutable::new(Vec2::ZERO);
+
+        InteractiveWidget::new(Stack::new(self.widget))
+            .on_generic_mouse_input({
+                to_owned![cursor_pos];
+                move |_, input| {
+                    cursor_pos.set(input.cursor.absolute_pos);
+                    Some(input)
+                }
+            })
+            .on_right_click(move |_| {
+                let token = self
+                    .menu
+                    .clone()
+                    .open(&overlays, MenuPlacement::at(cursor_pos.get()));
+
+                current_menu.set(Some(CloseOnDropHandle::new(token)));
+            })
+            .mount(scope);
+    }
+}
+
+struct MenuLevel {
+    items: Vec<MenuItem>,
+    panel: Entity,
+    /// Index of the entry in the parent level which opened this level
+    source: Option<usize>,
+    highlighted: Mutable<Option<usize>>,
+}
+
+type MenuLevels = WeakHandle<RefCell<Vec<MenuLevel>>>;
+
+enum MenuCommand {
+    /// Open the submenu of an entry, replacing any deeper levels
+    Open {
+        depth: usize,
+        index: usize,
+        highlight_first: bool,
+    },
+    /// Close all levels deeper than the given depth
+    Truncate(usize),
+}
+
+enum MenuKeyAction {
+    Activate(usize, usize),
+    Switch(isize),
+}
+
+/// Allows a [`MenuBar`] to follow the state of an open menu
+#[derive(Default, Clone)]
+struct MenuHooks {
+    /// The menu closed itself
+    on_close: Option<Arc<dyn Send + Sync + Fn()>>,
+    /// The cursor moved outside of all open menu panels
+    on_cursor_outside: Option<Arc<dyn Send + Sync + Fn(&ScopeRef<'_>, Vec2)>>,
+    /// Left or right was pressed in the top level menu
+    on_switch: Option<Arc<dyn Send + Sync + Fn(&ScopeRef<'_>, isize)>>,
+}
+
+#[derive(Clone)]
+struct MenuHandle {
+    token: OverlayHandle,
+    hooks: MenuHooks,
+}
+
+impl MenuHandle {
+    fn close(&self) {
+        self.token.close();
+        if let Some(on_close) = &self.hooks.on_close {
+            on_close();
+        }
+    }
+}
+
+/// Covers the screen to capture clicks outside the menu, and drives navigation of the menu and
+/// all open submenus.
+struct MenuOverlay {
+    menu: Menu,
+    placement: MenuPlacement,
+    highlight_first: bool,
+    hooks: MenuHooks,
+}
+
+impl Overlay for MenuOverlay {
+    fn create(self, scope: &mut Scope<'_>, token: OverlayHandle) {
+        let (tx, rx) = flume::unbounded();
+        let levels: MenuLevels = scope.store(RefCell::new(Vec::new()));
+        let handle = MenuHandle {
+            token,
+            hooks: self.hooks,
+        };
+
+        open_level(
+            scope,
+            levels,
+            self.menu.items,
+            self.placement,
+            None,
+            self.highlight_first,
+        );
+
+        scope.spawn_stream(rx.into_stream(), move |scope, command| match command {
+            MenuCommand::Open {
+                depth,
+                index,
+                highlight_first,
+            } => {
+                let (items, placement) = {
+                    let levels = scope.read(&levels).borrow();
+                    let Some(level) = levels.get(depth) else {
+                        return;
+                    };
+
+                    if let Some(open) = levels.get(depth + 1).filter(|v| v.source == Some(index)) {
+                        if highlight_first && open.highlighted.get().is_none() {
+                            open.highlighted.set(next_selectable(&open.items, None, 1));
+                        }
+                        return;
+                    }
+
+                    let Some(MenuItemKind::Submenu(menu)) = level.items.get(index).map(|v| &v.kind)
+                    else {
+                        return;
+                    };
+
+                    let anchor = entry_rect(scope.world(), level.panel, (depth, index));
+                    (
+                        menu.items.clone(),
+                        MenuPlacement::beside(anchor.unwrap_or_default()),
+                    )
+                };
+
+                close_levels(scope, levels, depth + 1);
+                open_level(
+                    scope,
+                    levels,
+                    items,
+                    placement,
+                    Some(index),
+                    highlight_first,
+                );
+            }
+            MenuCommand::Truncate(depth) => close_levels(scope, levels, depth),
+        });
+
+        if let Some(sender) = scope.get_atom(request_focus_sender()) {
+            sender.send(scope.id()).ok();
+        }
+
+        scope
+            .set(interactive(), ())
+            .set(keep_focus(), ())
+            .on_event(on_cursor_move(), {
+                to_owned!(tx, handle);
+                move |scope, input| {
+                    hover_entry(scope, levels, &tx, &handle, input.absolute_pos);
+                    None
+                }
+            })
+            .on_event(on_cursor_hover(), {
+                to_owned!(tx, handle);
+                move |scope, input| {
+                    hover_entry(scope, levels, &tx, &handle, input.absolute_pos);
+                    None
+                }
+            })
+            .on_event(on_mouse_input(), {
+                to_owned!(tx, handle);
+                move |scope, input| {
+                    if input.state == ElementState::Pressed {
+                        let pos = input.cursor.absolute_pos;
+                        let entry = find_widget_intersect(scope.id(), scope.frame(), pos, |v| {
+                            v.has(menu_entry())
+                        })
+                        .and_then(|(v, _)| v.get_copy(menu_entry()).ok());
+
+                        if let Some((depth, index)) = entry {
+                            if input.button == MouseButton::Left {
+                                activate_entry(scope, levels, &tx, &handle, depth, index);
+                            }
+                        } else if find_widget_intersect(scope.id(), scope.frame(), pos, |v| {
+                            v.has(menu_panel())
+                        })
+                        .is_none()
+                        {
+                            handle.close();
+                        }
+                    }
+
+                    // Clicking an entry moves focus to it, so reclaim it for keyboard navigation
+                    if let Some(sender) = scope.get_atom(request_focus_sender()) {
+                        sender.send(scope.id()).ok();
+                    }
+
+                    None
+                }
+            })
+            .on_event(on_keyboard_input(), move |scope, input| {
+                if input.state != ElementState::Pressed {
+                    return None;
+                }
+
+                // Leave shortcuts such as Ctrl+F to the command registry rather than matching
+                // them against mnemonics
+                if !input.modifiers.is_empty() && input.modifiers != ModifiersState::ALT {
+                    return Some(input);
+                }
+
+                let action = {
+                    let levels = scope.read(levels).borrow();
+                    let depth = levels.len().saturating_sub(1);
+                    let Some(level) = levels.last() else {
+                        return None;
+                    };
+
+                    let current = level.highlighted.get();
+                    let is_submenu =
+                        |index: usize| matches!(level.items[index].kind, MenuItemKind::Submenu(_));
+
+                    match &input.key {
+                        Key::Named(NamedKey::ArrowDown) => {
+                            level
+                                .highlighted
+                                .set(next_selectable(&level.items, current, 1));
+                            None
+                        }
+                        Key::Named(NamedKey::ArrowUp) => {
+                            level
+                                .highlighted
+                                .set(next_selectable(&level.items, current, -1));
+                            None
+                        }
+                        Key::Named(NamedKey::ArrowRight) => {
+                            match current.filter(|&v| is_submenu(v)) {
+                                Some(index) => {
+                                    tx.send(MenuCommand::Open {
+                                        depth,
+                                        index,
+                                        highlight_first: true,
+                                    })
+                                    .ok();
+                                    None
+                                }
+                                None if depth == 0 => Some(MenuKeyAction::Switch(1)),
+                                None => None,
+                            }
+                        }
+                        Key::Named(NamedKey::ArrowLeft | NamedKey::Escape) if depth > 0 => {
+                            tx.send(MenuCommand::Truncate(depth)).ok();
+                            None
+                        }
+                        Key::Named(NamedKey::ArrowLeft) => Some(MenuKeyAction::Switch(-1)),
+                        Key::Named(NamedKey::Escape) => {
+                            handle.close();
+                            None
+                        }
+                        Key::Named(NamedKey::Enter | NamedKey::Space) => {
+                            current.map(|index| MenuKeyAction::Activate(depth, index))
+                        }
+                        Key::Character(c) => {
+                            let c = c.chars().next().map(|v| v.to_ascii_lowercase());
+                            level
+                                .items
+                                .iter()
+                                .position(|v| {
+                                    v.is_selectable()
+                                        && v.mnemonic.is_some_and(|v| Some(v.key) == c)
+                                })
+                                .map(|index| MenuKeyAction::Activate(depth, index))
+                        }
+                        _ => None,
+                    }
+                };
+
+                match action {
+                    Some(MenuKeyAction::Activate(depth, index)) => {
+                        activate_entry(scope, levels, &tx, &handle, depth, index)
+                    }
+                    Some(MenuKeyAction::Switch(dir)) => {
+                        if let Some(on_switch) = &handle.hooks.on_switch {
+                            on_switch(scope, dir);
+                        }
+                    }
+                    None => {}
+                }
+
+                None
+            });
+
+        Stack::new(()).with_maximize(Vec2::ONE).mount(scope);
+    }
+}
+
+fn open_level(
+    scope: &mut Scope<'_>,
+    levels: MenuLevels,
+    items: Vec<MenuItem>,
+    placement: MenuPlacement,
+    source: Option<usize>,
+    highlight_first: bool,
+) {
+    let depth = scope.read(&levels).borrow().len();
+    let highlighted = Mutable::new(if highlight_first {
+        next_selectable(&items, None, 1)
+    } else {
+        None
+    });
+
+    let bounds = scope.id();
+    let panel = scope.attach(MenuPanel {
+        items: items.clone(),
+        depth,
+        placement,
+        bounds,
+        highlighted: highlighted.clone(),
+    });
+
+    scope.read(&levels).borrow_mut().push(MenuLevel {
+        items,
+        panel,
+        source,
+        highlighted,
+    });
+}
+
+fn close_levels(scope: &mut Scope<'_>, levels: MenuLevels, depth: usize) {
+    let closed = {
+        let mut levels = scope.read(&levels).borrow_mut();
+        let depth = depth.min(levels.len());
+        levels.split_off(depth)
+    };
+
+    for level in closed {
+        scope.detach(level.panel);
+    }
+}
+
+fn hover_entry(
+    scope: &ScopeRef<'_>,
+    levels: MenuLevels,
+    tx: &flume::Sender<MenuCommand>,
+    handle: &MenuHandle,
+    pos: Vec2,
+) {
+    let Some((depth, index)) =
+        find_widget_intersect(scope.id(), scope.frame(), pos, |v| v.has(menu_entry()))
+            .and_then(|(v, _)| v.get_copy(menu_entry()).ok())
+    else {
+        if let Some(on_cursor_outside) = &handle.hooks.on_cursor_outside {
+            if find_widget_intersect(scope.id(), scope.frame(), pos, |v| v.has(menu_panel()))
+                .is_none()
+            {
+                on_cursor_outside(scope, pos);
+            }
+        }
+
+        return;
+    };
+
+    let levels = scope.read(levels).borrow();
+    let Some(level) = levels.get(depth) else {
+        return;
+    };
+
+    level.highlighted.set_neq(Some(index));
+
+    if matches!(level.items[index].kind, MenuItemKind::Submenu(_)) {
+        tx.send(MenuCommand::Open {
+            depth,
+            index,
+            highlight_first: false,
+        })
+        .ok();
+    } else if levels.len() > depth + 1 {
+        tx.send(MenuCommand::Truncate(depth + 1)).ok();
+    }
+}
+
+fn activate_entry(
+    scope: &ScopeRef<'_>,
+    levels: MenuLevels,
+    tx: &flume::Sender<MenuCommand>,
+    handle: &MenuHandle,
+    depth: usize,
+    index: usize,
+) {
+    let Some(item) = scope
+        .read(levels)
+        .borrow()
+        .get(depth)
+        .and_then(|v| v.items.get(index).cloned())
+    else {
+        return;
+    };
+
+    if !item.is_selectable() {
+        return;
+    }
+
+    match item.kind {
+        MenuItemKind::Action(on_select) => {
+            if let Some(on_select) = on_select {
+                on_select(scope);
+            }
+            handle.close();
+        }
+        MenuItemKind::Check { state, current } => {
+            state.send(!current.get());
+            handle.close();
+        }
+        MenuItemKind::Submenu(_) => {
+            tx.send(MenuCommand::Open {
+                depth,
+                index,
+                highlight_first: true,
+            })
+            .ok();
+        }
+        MenuItemKind::Separator => {}
+    }
+}
+
+/// Returns the next selectable entry in the given direction, wrapping around
+fn next_selectable(items: &[MenuItem], current: Option<usize>, dir: isize) -> Option<usize> {
+    let len = items.len() as isize;
+    let start = current
+        .map(|v| v as isize)
+        .unwrap_or(if dir > 0 { -1 } else { len });
+
+    (1..=len)
+        .map(|i| (start + dir * i).rem_euclid(len.max(1)) as usize)
+        .find(|&i| items[i].is_selectable())
+}
+
+fn screen_rect(world: &World, id: Entity) -> Option<Rect> {
+    let entity = world.entity(id).ok()?;
+    let transform = entity.get_copy(screen_transform()).ok()?;
+    let rect = entity.get_copy(rect()).ok()?;
+
+    Some(rect.translate(transform.transform_point3(Vec3::ZERO).xy()))
+}
+
+fn entry_rect(world: &World, panel: Entity, entry: (usize, usize)) -> Option<Rect> {
+    let entity = OrderedDfsIterator::new(world, world.entity(panel).ok()?)
+        .find(|v| v.get_copy(menu_entry()).ok() == Some(entry))?;
+
+    screen_rect(world, entity.id())
+}
+
+struct MenuPanel {
+    items: Vec<MenuItem>,
+    depth: usize,
+    placement: MenuPlacement,
+    bounds: Entity,
+    highlighted: Mutable<Option<usize>>,
+}
+
+impl Widget for MenuPanel {
+    fn mount(self, scope: &mut Scope<'_>) {
+        let size = Mutable::new(None);
+
+        scope.monitor(rect(), {
+            to_owned!(size);
+            move |rect| size.set_neq(rect.map(|v| v.size()))
+        });
+
+        scope
+            .set(menu_panel(), ())
+            .set(offset(), Unit::px(self.placement.pos));
+
+        scope.spawn_stream(size.signal().to_stream(), move |scope, size| {
+            let Some(size) = size else {
+                return;
+            };
+
+            let Some(bounds) = screen_rect(scope.world(), self.bounds) else {
+                return;
+            };
+
+            let pos = self.placement.resolve(size, bounds) - bounds.min;
+            scope.update_dedup(offset(), Unit::px(pos)).unwrap();
+        });
+
+        let entries = self
+            .items
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| MenuEntry {
+                item,
+                entry: (self.depth, index),
+                highlighted: self.highlighted.clone(),
+            });
+
+        Stack::new(col(IterWidgetCollection::new(entries)).with_stretch(true))
+            .with_background(Background::new(surface_interactive()))
+            .with_padding(spacing_small())
+            .with_corner_radius(default_corner_radius())
+            .with_min_size(Unit::px2(160.0, 0.0))
+            .mount(scope);
+    }
+}
+
+struct MenuEntry {
+    item: MenuItem,
+    entry: (usize, usize),
+    highlighted: Mutable<Option<usize>>,
+}
+
+impl Widget for MenuEntry {
+    fn mount(self, scope: &mut Scope<'_>) {
+        if let MenuItemKind::Separator = self.item.kind {
+            Rectangle::new(element_tertiary())
+                .with_min_size(Unit::px2(0.0, 1.0))
+                .with_maximize(Vec2::X)
+                .with_margin(spacing_small())
+                .mount(scope);
+            return;
+        }
+
+        let stylesheet = scope.stylesheet();
+        let hover = stylesheet.get_copy(surface_hover()).unwrap_or_default();
+        let chevron = stylesheet.get_clone(icon_chevron()).unwrap_or_default();
+        let check_icon = stylesheet.get_clone(icon_check()).unwrap_or_default();
+        let normal = Srgba::new(0.0, 0.0, 0.0, 0.0);
+
+        let text_color = if self.item.enabled {
+            element_primary()
+        } else {
+            element_disabled()
+        };
+
+        if self.item.is_selectable() {
+            scope.set(menu_entry(), self.entry);
+        }
+
+        let index = self.entry.1;
+        scope.spawn_stream(
+            self.highlighted
+                .signal()
+                .map(move |v| v == Some(index))
+                .dedupe()
+                .to_stream(),
+            move |scope, active| {
+                scope
+                    .update_dedup(color(), if active { hover } else { normal })
+                    .unwrap();
+            },
+        );
+
+        let is_submenu = matches!(self.item.kind, MenuItemKind::Submenu(_));
+        let icon = self.item.icon.unwrap_or_default();
+        let kind = self.item.kind;
+
+        let icon_slot = move |scope: &mut Scope<'_>| match kind {
+            MenuItemKind::Check { state, current } => {
+                StreamWidget::new(state.stream().map(move |checked| {
+                    current.set(checked);
+                    label(if checked {
+                        check_icon.clone()
+                    } else {
+                        icon.clone()
+                    })
+                    .with_color(text_color)
+                }))
+                .mount(scope);
+            }
+            _ => label(icon).with_color(text_color).mount(scope),
+        };
+
+        row((
+            Stack::new(icon_slot).with_min_size(Unit::px2(20.0, 0.0)),
+            mnemonic_label(self.item.label, self.item.mnemonic)
+                .with_margin(spacing_small())
+                .with_color(text_color)
+                .with_maximize(Vec2::X),
+            label(self.item.shortcut.unwrap_or_default()).with_color(element_secondary()),
+            label(if is_submenu { chevron } else { String::new() }).with_color(text_color),
+        ))
+        .with_background(Background::new(normal))
+        .with_corner_radius(default_corner_radius())
+        .mount(scope);
+    }
+}
+
+/// Character marked by `&` in a label, which activates the entry when pressed
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Mnemonic {
+    /// Lowercase key which activates the entry
+    key: char,
+    /// Byte offset of the character in the displayed text
+    offset: usize,
+}
+
+/// Splits a label into the displayed text and the mnemonic marked by `&`
+fn parse_mnemonic(label: &str) -> (String, Option<Mnemonic>) {
+    let mut text = String::with_capacity(label.len());
+    let mut mnemonic = None;
+    let mut chars = label.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '&' {
+            text.push(c);
+            continue;
+        }
+
+        match chars.peek() {
+            Some('&') => {
+                chars.next();
+                text.push('&');
+            }
+            Some(&next) if next.is_alphanumeric() && mnemonic.is_none() => {
+                mnemonic = Some(Mnemonic {
+                    key: next.to_ascii_lowercase(),
+                    offset: text.len(),
+                });
+            }
+            _ => text.push('&'),
+        }
+    }
+
+    (text, mnemonic)
+}
+
+/// Displays a label with its mnemonic underlined
+fn mnemonic_label(text: String, mnemonic: Option<Mnemonic>) -> Text {
+    let Some(Mnemonic { offset, .. }) = mnemonic else {
+        return Text::new(text);
+    };
+
+    let end = offset + text[offset..].chars().next().map_or(0, char::len_utf8);
+
+    Text::formatted([
+        TextSegment::new(&text[..offset]),
+        TextSegment::new(&text[offset..end]).with_underline(UnderlineStyle::Solid),
+        TextSegment::new(&text[end..]),
+    ])
+}
+
+/// Horizontal bar of top level menus, such as File, Edit and View.
+///
+/// Hovering another title while a menu is open switches to it. A title may contain a mnemonic,
+/// such as `&File`, which opens the menu when pressed together with Alt.
+#[derive(Default)]
+pub struct MenuBar {
+    menus: Vec<(String, Menu)>,
+    commands: Option<CommandRegistry>,
+}
+
+impl MenuBar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_menu(mut self, title: impl Into<String>, menu: Menu) -> Self {
+        self.menus.push((title.into(), menu));
+        self
+    }
+
+    /// Invoke the shortcuts of the registered commands regardless of which widget is focused
+    pub fn with_commands(mut self, commands: CommandRegistry) -> Self {
+        self.commands = Some(commands);
+        self
+    }
+}
+
+struct MenuBarState {
+    menus: Vec<(Option<char>, Menu)>,
+    titles: Mutex<Vec<Entity>>,
+    open: Mutex<Option<(usize, CloseOnDropHandle)>>,
+    overlays: OverlayState,
+}
+
+impl MenuBarState {
+    fn open(self: &Arc<Self>, world: &World, index: usize, highlight_first: bool) {
+        let Some((_, menu)) = self.menus.get(index) else {
+            return;
+        };
+
+        let anchor = self
+            .titles
+            .lock()
+            .get(index)
+            .and_then(|&v| screen_rect(world, v))
+            .unwrap_or_default();
+
+        let state = Arc::downgrade(self);
+        let hooks = MenuHooks {
+            on_close: Some(Arc::new({
+                to_owned!(state);
+                move || {
+                    if let Some(state) = state.upgrade() {
+                        let mut open = state.open.lock();
+                        if open.as_ref().is_some_and(|v| v.0 == index) {
+                            *open = None;
+                        }
+                    }
+                }
+            })),
+            on_cursor_outside: Some(Arc::new({
+                to_owned!(state);
+                move |scope: &ScopeRef<'_>, pos: Vec2| {
+                    if let Some(state) = state.upgrade() {
+                        state.hover(scope.frame().world(), pos);
+                    }
+                }
+            })),
+            on_switch: Some(Arc::new(move |scope: &ScopeRef<'_>, dir: isize| {
+                if let Some(state) = state.upgrade() {
+                    let index = (index as isize + dir).rem_euclid(state.menus.len() as isize);
+                    state.open(scope.frame().world(), index as usize, true);
+                }
+            })),
+        };
+
+        let token = self.overlays.open(MenuOverlay {
+            menu: menu.clone(),
+            placement: MenuPlacement::below(anchor),
+            highlight_first,
+            hooks,
+        });
+
+        *self.open.lock() = Some((index, CloseOnDropHandle::new(token)));
+    }
+
+    /// Switches to the menu under the cursor while another menu is open
+    fn hover(self: &Arc<Self>, world: &World, pos: Vec2) {
+        let Some(current) = self.open.lock().as_ref().map(|v| v.0) else {
+            return;
+        };
+
+        let hovered = self
+            .titles
+            .lock()
+            .iter()
+            .position(|&v| screen_rect(world, v).is_some_and(|v| v.contains_point(pos)));
+
+        if let Some(index) = hovered.filter(|&v| v != current) {
+            self.open(world, index, false);
+        }
+    }
+}
+
+impl Widget for MenuBar {
+    fn mount(self, scope: &mut Scope<'_>) {
+        let (titles, menus): (Vec<_>, Vec<_>) = self
+            .menus
+            .into_iter()
+            .map(|(title, menu)| {
+                let (title, mnemonic) = parse_mnemonic(&title);
+                ((title, mnemonic), (mnemonic.map(|v| v.key), menu))
+            })
+            .unzip();
+
+        let state = Arc::new(MenuBarState {
+            menus,
+            titles: Mutex::new(Vec::new()),
+            open: Mutex::new(None),
+            overlays: scope.get_context_cloned(overlay_state()),
+        });
+
+        let titles = titles
+            .into_iter()
+            .enumerate()
+            .map(|(index, (title, mnemonic))| {
+                to_owned!(state);
+                scope.attach(
+                    Button::new(mnemonic_label(title, mnemonic))
+                        .with_style(ButtonStyle::hidden())
+                        .on_click(move |scope| state.open(scope.frame().world(), index, false)),
+                )
+            })
+            .collect();
+
+        *state.titles.lock() = titles;
+
+        let commands = self.commands;
+        scope.on_event(on_shortcut(), move |scope, input| {
+            if input.state != ElementState::Pressed {
+                return Some(input);
+            }
+
+            if input.modifiers.alt_key() {
+                if let Key::Character(c) = &input.key {
+                    let c = c.chars().next().map(|v| v.to_ascii_lowercase());
+                    if let Some(index) = state.menus.iter().position(|v| v.0.is_some() && v.0 == c)
+                    {
+                        state.open(scope.frame().world(), index, true);
+                        return None;
+                    }
+                }
+            }
+
+            if commands.as_ref().is_some_and(|v| v.dispatch(scope, &input)) {
+                return None;
+            }
+
+            Some(input)
+        });
+
+        row(())
+            .with_background(surface_secondary())
+            .with_maximize(Vec2::X)
+            .mount(scope);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn placement() {
+        let bounds = Rect::from_size(vec2(100.0, 100.0));
+        let size = vec2(20.0, 20.0);
+
+        assert_eq!(
+            MenuPlacement::at(vec2(10.0, 10.0)).resolve(size, bounds),
+            vec2(10.0, 10.0)
+        );
+
+        // Flips to end at the cursor when there is no room
+        assert_eq!(
+            MenuPlacement::at(vec2(90.0, 90.0)).resolve(size, bounds),
+            vec2(70.0, 70.0)
+        );
+
+        let anchor = Rect::new(vec2(10.0, 80.0), vec2(30.0, 90.0));
+        assert_eq!(
+            MenuPlacement::below(anchor).resolve(size, bounds),
+            vec2(10.0, 60.0)
+        );
+
+        let anchor = Rect::new(vec2(80.0, 10.0), vec2(95.0, 20.0));
+        assert_eq!(
+            MenuPlacement::beside(anchor).resolve(size, bounds),
+            vec2(60.0, 10.0)
+        );
+
+        // A menu larger than the screen starts at its edge
+        assert_eq!(
+            MenuPlacement::at(vec2(5.0, 5.0)).resolve(vec2(200.0, 50.0), bounds),
+            vec2(0.0, 5.0)
+        );
+    }
+
+    #[test]
+    fn selectable_items() {
+        let items = [
+            MenuItem::new("Cut"),
+            MenuItem::separator(),
+            MenuItem::new("Copy").disabled(),
+            MenuItem::new("Paste"),
+        ];
+
+        assert_eq!(next_selectable(&items, None, 1), Some(0));
+        assert_eq!(next_selectable(&items, Some(0), 1), Some(3));
+        assert_eq!(next_selectable(&items, Some(3), 1), Some(0));
+        assert_eq!(next_selectable(&items, None, -1), Some(3));
+        assert_eq!(next_selectable(&items, Some(0), -1), Some(3));
+        assert_eq!(next_selectable(&items, Some(3), -1), Some(0));
+
+        assert_eq!(next_selectable(&items[1..3], None, 1), None);
+        assert_eq!(next_selectable(&[], None, 1), None);
+    }
+
+    #[test]
+    fn mnemonics() {
+        let mnemonic = |key, offset| Some(Mnemonic { key, offset });
+
+        assert_eq!(parse_mnemonic("&File"), ("File".into(), mnemonic('f', 0)));
+        assert_eq!(
+            parse_mnemonic("Save &As"),
+            ("Save As".into(), mnemonic('a', 5))
+        );
+        assert_eq!(
+            parse_mnemonic("Fish && &Chips"),
+            ("Fish & Chips".into(), mnemonic('c', 7))
+        );
+        assert_eq!(
+            parse_mnemonic("&Open &Recent"),
+            ("Open Recent".into(), mnemonic('o', 0))
+        );
+
+        // A missing or trailing `&`, or one before a space, is shown as is
+        assert_eq!(parse_mnemonic("Close"), ("Close".into(), None));
+        assert_eq!(parse_mnemonic("Trailing&"), ("Trailing&".into(), None));
+        assert_eq!(
+            parse_mnemonic("Copy & Paste"),
+            ("Copy & Paste".into(), None)
+        );
+        assert_eq!(parse_mnemonic("&&"), ("&".into(), None));
+
+        let segments = mnemonic_label("Save As".into(), mnemonic('a', 5))
+            .segments()
+            .iter()
+            .map(|v| (v.text.clone(), v.underline))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            segments,
+            [
+                ("Save ".to_string(), None),
+                ("A".to_string(), Some(UnderlineStyle::Solid)),
+                ("s".to_string(), None),
+            ]
+        );
+    }
+}
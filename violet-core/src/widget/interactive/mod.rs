@@ -4,10 +4,16 @@ pub mod collapsible;
 pub mod colorpicker;
 pub mod drag;
 pub mod dropdown;
+pub mod find_bar;
 pub mod input;
+pub mod input_mask;
+pub mod keymap;
+pub mod menu;
 pub mod overlay;
 pub mod select_list;
+pub(crate) mod selectable;
 pub mod slider;
+pub mod text_area;
 pub mod tooltip;
 
 pub use base::*;
@@ -16,8 +22,13 @@ pub use collapsible::*;
 pub use colorpicker::*;
 pub use drag::*;
 pub use dropdown::*;
+pub use find_bar::*;
 pub use input::*;
+pub use input_mask::*;
+pub use keymap::*;
+pub use menu::*;
 pub use overlay::*;
 pub use select_list::*;
 pub use slider::*;
+pub use text_area::*;
 pub use tooltip::*;
@@ -0,0 +1,397 @@
+use std::sync::Arc;
+
+use futures_signals::signal::{Mutable, SignalExt};
+use glam::Vec2;
+use itertools::Itertools;
+use unicode_segmentation::UnicodeSegmentation;
+use web_time::{Duration, Instant};
+use winit::{
+    event::{ElementState, MouseButton},
+    keyboard::Key,
+};
+
+use crate::{
+    components::layout_glyphs,
+    input::{
+        cursor_icon, interactive, keep_focus, on_cursor_move, on_focus, on_keyboard_input,
+        on_mouse_input, CursorIcon,
+    },
+    io,
+    style::{surface_hover_accent, ResolvableStyle, SizeExt, WidgetSizeProps},
+    text::{CursorLocation, LayoutGlyphs, TextSegment},
+    to_owned,
+    unit::Unit,
+    widget::{
+        basic::find_link, Float, LinkCallback, Positioned, Rectangle, Stack, StreamWidget, Text,
+    },
+    Scope, Widget,
+};
+
+use super::input::selection_rects;
+
+/// Clicks within this duration of each other select a word, and then a line
+const MULTI_CLICK_TIMEOUT: Duration = Duration::from_millis(400);
+
+#[derive(Default)]
+struct SelectionState {
+    /// Where the selection started, and where it currently ends
+    selection: Option<(CursorLocation, CursorLocation)>,
+    dragging: bool,
+    glyphs: LayoutGlyphs,
+}
+
+impl SelectionState {
+    fn bounds(&self) -> Option<(CursorLocation, CursorLocation)> {
+        let (anchor, head) = self.selection?;
+        Some((anchor.min(head), anchor.max(head)))
+    }
+
+    /// Returns the link released over at `pos`, unless text was selected by dragging
+    fn clicked_link<'a>(&self, segments: &'a [TextSegment], pos: Vec2) -> Option<&'a str> {
+        if self.selection.is_some_and(|(anchor, head)| anchor != head) {
+            return None;
+        }
+
+        find_link(&self.glyphs, segments, pos)
+    }
+}
+
+/// Read-only text which can be selected with the mouse and copied
+pub(crate) struct SelectableText {
+    text: Text,
+    size: WidgetSizeProps,
+    on_link_click: Option<LinkCallback>,
+}
+
+impl SelectableText {
+    pub(crate) fn new(
+        text: Text,
+        size: WidgetSizeProps,
+        on_link_click: Option<LinkCallback>,
+    ) -> Self {
+        Self {
+            text,
+            size,
+            on_link_click,
+        }
+    }
+}
+
+impl Widget for SelectableText {
+    fn mount(self, scope: &mut Scope<'_>) {
+        let selection_color = surface_hover_accent().resolve(scope.stylesheet());
+
+        let clipboard = scope
+            .get_atom(io::clipboard())
+            .expect("Missing clipboard")
+            .clone();
+
+        let clipboard = scope.frame().store().get(&clipboard).clone();
+
+        let (copy_tx, copy_rx) = flume::unbounded::<String>();
+        scope.spawn(async move {
+            while let Ok(text) = copy_rx.recv_async().await {
+                clipboard.set_text(text).await;
+            }
+        });
+
+        let rows = text_rows(&self.text);
+        let segments: Arc<[TextSegment]> = self.text.segments().into();
+        let state = Mutable::new(SelectionState::default());
+
+        let text = {
+            to_owned![state];
+            move |scope: &mut Scope<'_>| {
+                self.text.mount(scope);
+
+                scope.monitor(layout_glyphs(), {
+                    to_owned![state];
+                    move |glyphs| {
+                        if let Some(glyphs) = glyphs {
+                            state.lock_mut().glyphs = glyphs.clone();
+                        }
+                    }
+                });
+
+                let mut last_click: Option<Instant> = None;
+                let mut clicks = 0;
+
+                scope
+                    .set(interactive(), ())
+                    .set(keep_focus(), ())
+                    .set(cursor_icon(), CursorIcon::Text)
+                    .on_event(on_focus(), {
+                        to_owned![state];
+                        move |_, focus| {
+                            if !focus {
+                                state.lock_mut().selection = None;
+                            }
+
+                            None
+                        }
+                    })
+                    // Replaces the link handler of the text, so links are clicked here instead
+                    .on_event(on_mouse_input(), {
+                        to_owned![state, rows];
+                        move |scope, input| {
+                            if input.button != MouseButton::Left {
+                                return Some(input);
+                            }
+
+                            if input.state == ElementState::Released {
+                                let mut state = state.lock_mut();
+                                state.dragging = false;
+
+                                let pos = input.cursor.local_pos;
+                                if let Some(on_link_click) = &self.on_link_click {
+                                    if let Some(link) = state.clicked_link(&segments, pos) {
+                                        state.selection = None;
+                                        drop(state);
+                                        on_link_click(scope, link);
+                                    }
+                                }
+
+                                return None;
+                            }
+
+                            let now = Instant::now();
+                            if last_click
+                                .is_some_and(|v| now.duration_since(v) < MULTI_CLICK_TIMEOUT)
+                            {
+                                clicks += 1;
+                            } else {
+                                clicks = 1;
+                            }
+
+                            last_click = Some(now);
+
+                            let mut state = state.lock_mut();
+                            let Some(hit) = state.glyphs.hit(input.cursor.local_pos) else {
+                                state.selection = None;
+                                return None;
+                            };
+
+                            let line = rows.get(hit.row).map(|v| &**v).unwrap_or_default();
+                            state.selection = match clicks {
+                                1 => {
+                                    state.dragging = true;
+                                    Some((hit, hit))
+                                }
+                                2 => {
+                                    let (start, end) = word_at(line, hit.col);
+                                    Some((
+                                        CursorLocation::new(hit.row, start),
+                                        CursorLocation::new(hit.row, end),
+                                    ))
+                                }
+                                _ => Some((
+                                    CursorLocation::new(hit.row, 0),
+                                    CursorLocation::new(hit.row, line.len()),
+                                )),
+                            };
+
+                            None
+                        }
+                    })
+                    .on_event(on_cursor_move(), {
+                        to_owned![state];
+                        move |_, input| {
+                            let mut state = state.lock_mut();
+                            if !state.dragging {
+                                return Some(input);
+                            }
+
+                            if let Some(hit) = state.glyphs.hit(input.local_pos) {
+                                if let Some((_, head)) = &mut state.selection {
+                                    *head = hit;
+                                }
+                            }
+
+                            None
+                        }
+                    })
+                    .on_event(on_keyboard_input(), {
+                        to_owned![state];
+                        move |_, input| {
+                            // Shortcuts use Cmd on macOS, and Ctrl elsewhere
+                            let primary = if cfg!(target_os = "macos") {
+                                input.modifiers.super_key()
+                            } else {
+                                input.modifiers.control_key()
+                            };
+
+                            if input.state != ElementState::Pressed || !primary {
+                                return Some(input);
+                            }
+
+                            match &input.key {
+                                Key::Character(c) if c.eq_ignore_ascii_case("c") => {
+                                    if let Some((start, end)) = state.lock_ref().bounds() {
+                                        copy_tx.send(selected_text(&rows, start, end)).ok();
+                                    }
+                                }
+                                Key::Character(c) if c.eq_ignore_ascii_case("a") => {
+                                    let last = rows.len().saturating_sub(1);
+                                    let end = rows.last().map(|v| v.len()).unwrap_or_default();
+                                    state.lock_mut().selection = Some((
+                                        CursorLocation::new(0, 0),
+                                        CursorLocation::new(last, end),
+                                    ));
+                                }
+                                _ => return Some(input),
+                            }
+
+                            None
+                        }
+                    });
+            }
+        };
+
+        Stack::new((
+            text,
+            Float::new(StreamWidget(
+                state
+                    .signal_ref(move |state| {
+                        let rects = match state.bounds() {
+                            Some((start, end)) => {
+                                selection_rects(&state.glyphs, start, end).collect_vec()
+                            }
+                            None => Vec::new(),
+                        };
+
+                        Stack::new(
+                            rects
+                                .into_iter()
+                                .map(|rect| {
+                                    Positioned::new(
+                                        Rectangle::new(selection_color)
+                                            .with_exact_size(Unit::px(rect.size())),
+                                    )
+                                    .with_offset(Unit::px(rect.pos()))
+                                })
+                                .collect_vec(),
+                        )
+                    })
+                    .to_stream(),
+            )),
+        ))
+        .with_size_props(self.size)
+        .mount(scope);
+    }
+}
+
+/// Returns the text of each row, as the segments are laid out
+fn text_rows(text: &Text) -> Arc<[String]> {
+    text.segments()
+        .iter()
+        .map(|v| &*v.text)
+        .collect::<String>()
+        .split('\n')
+        .map(|v| v.to_string())
+        .collect()
+}
+
+/// Returns the byte range of the word at `col`
+fn word_at(line: &str, col: usize) -> (usize, usize) {
+    line.unicode_word_indices()
+        .find(|&(i, word)| col >= i && col <= i + word.len())
+        .map(|(i, word)| (i, i + word.len()))
+        .unwrap_or((col, col))
+}
+
+fn selected_text(rows: &[String], start: CursorLocation, end: CursorLocation) -> String {
+    rows.iter()
+        .enumerate()
+        .take(end.row + 1)
+        .skip(start.row)
+        .map(|(row, line)| {
+            let from = if row == start.row { start.col } else { 0 };
+            let to = if row == end.row { end.col } else { line.len() };
+            line.get(from..to.min(line.len())).unwrap_or_default()
+        })
+        .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn select_text() {
+        let rows = ["error: no such file", "id: 3f2a-91bc"].map(String::from);
+
+        assert_eq!(word_at(&rows[0], 11), (10, 14));
+        assert_eq!(word_at(&rows[0], 0), (0, 5));
+
+        assert_eq!(
+            selected_text(&rows, CursorLocation::new(0, 7), CursorLocation::new(0, 12)),
+            "no su"
+        );
+        assert_eq!(
+            selected_text(&rows, CursorLocation::new(0, 10), CursorLocation::new(1, 2)),
+            "such file\nid"
+        );
+        assert_eq!(
+            selected_text(
+                &rows,
+                CursorLocation::new(1, 0),
+                CursorLocation::new(1, 100)
+            ),
+            "id: 3f2a-91bc"
+        );
+    }
+
+    #[test]
+    fn click_link() {
+        use glam::vec2;
+
+        use crate::{
+            text::{LayoutGlyph, LayoutLineGlyphs},
+            Rect,
+        };
+
+        let segments = [
+            TextSegment::new("see "),
+            TextSegment::new("docs").with_link("https://example.com"),
+        ];
+
+        let glyphs = (0..8)
+            .map(|i| LayoutGlyph {
+                index: i,
+                start: i,
+                end: i + 1,
+                bounds: Rect::new(
+                    vec2(i as f32 * 10.0, 0.0),
+                    vec2(i as f32 * 10.0 + 10.0, 10.0),
+                ),
+                rtl: false,
+            })
+            .collect();
+
+        let mut state = SelectionState {
+            glyphs: LayoutGlyphs::new(
+                vec![vec![LayoutLineGlyphs {
+                    row: 0,
+                    bounds: Rect::new(Vec2::ZERO, vec2(80.0, 10.0)),
+                    start: 0,
+                    end: 8,
+                    glyphs,
+                }]],
+                10.0,
+            ),
+            ..Default::default()
+        };
+
+        let hit = CursorLocation::new(0, 5);
+        state.selection = Some((hit, hit));
+        assert_eq!(
+            state.clicked_link(&segments, vec2(55.0, 5.0)),
+            Some("https://example.com")
+        );
+        assert_eq!(state.clicked_link(&segments, vec2(15.0, 5.0)), None);
+
+        // Selecting text across the link does not follow it
+        state.selection = Some((CursorLocation::new(0, 1), hit));
+        assert_eq!(state.clicked_link(&segments, vec2(55.0, 5.0)), None);
+    }
+}
@@ -0,0 +1,1497 @@
+use std::{future::Future, ops::Range, sync::Arc};
+
+use futures::{
+    future::{Fuse, FusedFuture},
+    stream, FutureExt, StreamExt,
+};
+use futures_signals::signal::{Mutable, SignalExt};
+use glam::{vec2, BVec2, Mat4, Vec2, Vec3, Vec3Swizzles};
+use itertools::Itertools;
+use palette::{Srgba, WithAlpha};
+use parking_lot::Mutex;
+use unicode_segmentation::UnicodeSegmentation;
+use web_time::Duration;
+use winit::{
+    event::{ElementState, Ime},
+    keyboard::{Key, NamedKey},
+};
+
+use super::base::{InteractiveWidget, TooltipOptions};
+use super::find_bar::{SearchCommand, SearchStatus, TextSearch};
+use super::input::{
+    apply_action, calculate_position, column_selection, insert_segment_text, line_segments,
+    selection_rects, Action, Preedit, RowChange, TextContent, TextInputStyle, TextOptions,
+};
+use super::keymap::{keymap, Keymap};
+use super::overlay::{overlay_state, CloseOnDropHandle};
+use super::tooltip::TooltipOverlay;
+use crate::{
+    components::rect,
+    editor::{
+        Caret, CursorMove, Diagnostic, EditorAction, HighlightCache, HighlightState, Highlighter,
+        Severity, TextBuffer, TextChange, TextEditorCore,
+    },
+    input::{
+        cursor_icon, ime_cursor_area_sender, interactive, keep_focus, on_cursor_hover,
+        on_cursor_move, on_focus, on_ime, on_keyboard_input, on_mouse_input, on_scroll, CursorIcon,
+        HoverState,
+    },
+    io::{self, Clipboard},
+    state::{StateDuplex, StateExt, StateSink, StateStream},
+    style::*,
+    text::{CursorLocation, LayoutGlyphs, TextSegment, UnderlineStyle, Wrap},
+    time::{sleep, Sleep},
+    to_owned,
+    unit::Unit,
+    utils::throttle,
+    widget::{pill, row, Float, Positioned, Rectangle, Stack, StreamWidget, Text, TextStyle},
+    Edges, FutureEffect, Rect, Scope, ScopeRef, Widget,
+};
+
+#[derive(Clone, Debug)]
+pub struct TextAreaStyle {
+    pub input: TextInputStyle,
+    pub line_number_color: ValueOrRef<Srgba>,
+    pub current_line_color: ValueOrRef<Srgba>,
+    /// Background of the matches of a search
+    pub match_color: ValueOrRef<Srgba>,
+}
+
+impl Default for TextAreaStyle {
+    fn default() -> Self {
+        let input = TextInputStyle::default();
+        TextInputStyle {
+            size: input.size.with_min_size(Unit::px2(128.0, 128.0)),
+            text_style: TextStyle {
+                wrap: Wrap::Word,
+                ..input.text_style
+            },
+            ..input
+        }
+        .into()
+    }
+}
+
+impl From<TextInputStyle> for TextAreaStyle {
+    fn from(input: TextInputStyle) -> Self {
+        Self {
+            input,
+            line_number_color: element_secondary().into(),
+            current_line_color: surface_hover().into(),
+            match_color: surface_warning().into(),
+        }
+    }
+}
+
+/// Creates the tooltip shown when hovering a diagnostic
+pub type DiagnosticTooltip = Arc<dyn Send + Sync + Fn(&Diagnostic) -> TooltipOptions>;
+
+/// Multi-line text editor with scrolling and an optional line-number gutter.
+///
+/// Only the rows visible in the viewport are laid out, so long documents do not use more memory
+/// for widgets and glyphs than a short one. The area is sized by its size props rather than by its
+/// content.
+pub struct TextArea {
+    style: TextAreaStyle,
+    content: Arc<dyn Send + Sync + StateDuplex<Item = String>>,
+    options: TextOptions,
+    highlighter: Option<Arc<dyn Highlighter>>,
+    line_numbers: bool,
+    highlight_current_line: bool,
+    request_focus: bool,
+    keymap: Option<Keymap>,
+    search: Option<TextSearch>,
+    diagnostics: Option<Arc<dyn Send + Sync + StateStream<Item = Vec<Diagnostic>>>>,
+    diagnostic_tooltip: DiagnosticTooltip,
+}
+
+impl TextArea {
+    pub fn new(content: impl 'static + Send + Sync + StateDuplex<Item = String>) -> Self {
+        Self {
+            content: Arc::new(content),
+            style: Default::default(),
+            options: TextOptions::default().with_allow_newlines(true),
+            highlighter: None,
+            line_numbers: false,
+            highlight_current_line: true,
+            request_focus: false,
+            keymap: None,
+            search: None,
+            diagnostics: None,
+            diagnostic_tooltip: Arc::new(|v| TooltipOptions::label(&v.message)),
+        }
+    }
+
+    /// Line breaks are always allowed in a text area
+    pub fn with_options(mut self, options: TextOptions) -> Self {
+        self.options = options.with_allow_newlines(true);
+        self
+    }
+
+    /// Style the text using a syntax highlighter
+    pub fn with_highlighter(mut self, highlighter: impl 'static + Highlighter) -> Self {
+        self.highlighter = Some(Arc::new(highlighter));
+        self
+    }
+
+    /// Use different key bindings than the global [`keymap`]
+    pub fn with_keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = Some(keymap);
+        self
+    }
+
+    /// Highlight and navigate the matches of a search, such as from a [`FindBar`](super::FindBar)
+    pub fn with_search(mut self, search: TextSearch) -> Self {
+        self.search = Some(search);
+        self
+    }
+
+    /// Underline the text of diagnostics, with an icon in a gutter for the rows they start on.
+    ///
+    /// Diagnostics follow edits to the text until new diagnostics are received.
+    pub fn with_diagnostics(
+        mut self,
+        diagnostics: impl 'static + Send + Sync + StateStream<Item = Vec<Diagnostic>>,
+    ) -> Self {
+        self.diagnostics = Some(Arc::new(diagnostics));
+        self
+    }
+
+    /// Customize the tooltip shown when hovering a diagnostic or its gutter icon
+    pub fn with_diagnostic_tooltip(
+        mut self,
+        tooltip: impl 'static + Send + Sync + Fn(&Diagnostic) -> TooltipOptions,
+    ) -> Self {
+        self.diagnostic_tooltip = Arc::new(tooltip);
+        self
+    }
+
+    /// Show line numbers in a gutter to the left of the text
+    pub fn with_line_numbers(mut self, line_numbers: bool) -> Self {
+        self.line_numbers = line_numbers;
+        self
+    }
+
+    /// Highlight the row containing the cursor
+    pub fn with_current_line_highlight(mut self, highlight: bool) -> Self {
+        self.highlight_current_line = highlight;
+        self
+    }
+
+    pub fn request_focus(mut self) -> Self {
+        self.request_focus = true;
+        self
+    }
+}
+
+impl StyleExt for TextArea {
+    type Style = TextAreaStyle;
+
+    fn with_style(mut self, style: Self::Style) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+impl SizeExt for TextArea {
+    fn size_mut(&mut self) -> &mut WidgetSizeProps {
+        &mut self.style.input.size
+    }
+}
+
+impl Widget for TextArea {
+    fn mount(self, scope: &mut Scope<'_>) {
+        let stylesheet = scope.stylesheet();
+
+        let input_style = self.style.input;
+        let cursor_color = input_style.cursor_color.resolve(stylesheet);
+        let selection_color = input_style
+            .selection_color
+            .resolve(stylesheet)
+            .with_alpha(0.2);
+        let line_number_color = self.style.line_number_color.resolve(stylesheet);
+        let current_line_color = self
+            .style
+            .current_line_color
+            .resolve(stylesheet)
+            .with_alpha(0.2);
+        let match_color = self.style.match_color.resolve(stylesheet).with_alpha(0.3);
+        let font_size = input_style.text_style.font_size.resolve(stylesheet);
+
+        let error_icon = stylesheet.get_clone(icon_error()).unwrap();
+        let warning_icon = stylesheet.get_clone(icon_warning()).unwrap();
+        let info_icon = stylesheet.get_clone(icon_info()).unwrap();
+
+        let (tx, actions_rx) = flume::unbounded();
+        let (dirty_tx, dirty_rx) = flume::unbounded();
+
+        let highlights = self
+            .highlighter
+            .map(|v| Arc::new(Mutex::new(HighlightCache::new(v))));
+
+        // Displayed rows are synchronized with the text after each action, so changes only need
+        // to update the highlighting state
+        let mut editor = TextEditorCore::new({
+            to_owned![highlights];
+            move |text: &TextBuffer, change: TextChange| {
+                if let Some(highlights) = &highlights {
+                    highlights.lock().apply_change(text, &change);
+                }
+            }
+        });
+        editor.set_history_limit(self.options.history_limit);
+        editor.set_cursor_at_end();
+
+        let focused = Mutable::new(false);
+        let layout_glyphs = Mutable::new(Default::default());
+        let text_bounds: Mutable<Option<Mat4>> = Mutable::new(None);
+        let viewport: Mutable<Option<Rect>> = Mutable::new(None);
+        let top = Mutable::new(0);
+        let visuals = Mutable::new(AreaVisuals::default());
+
+        let clipboard = scope
+            .get_atom(io::clipboard())
+            .expect("Missing clipboard")
+            .clone();
+
+        let clipboard = scope.frame().store().get(&clipboard).clone();
+
+        let ime_cursor_area = scope.get_atom(ime_cursor_area_sender()).map(|v| v.clone());
+
+        let mut keys = self
+            .keymap
+            .clone()
+            .or_else(|| scope.get_atom(keymap()).map(|v| v.clone()))
+            .unwrap_or_default()
+            .handler();
+
+        scope.spawn(process_area_commands(
+            AreaState {
+                focused: focused.clone(),
+                layout_glyphs: layout_glyphs.clone(),
+                text_bounds: text_bounds.clone(),
+                viewport: viewport.clone(),
+                top: top.clone(),
+                visuals: visuals.clone(),
+            },
+            actions_rx,
+            editor,
+            clipboard,
+            self.content.clone(),
+            dirty_tx,
+            highlights,
+            ime_cursor_area,
+            self.options.clone(),
+            self.search.unwrap_or_default(),
+            self.diagnostics.clone(),
+            font_size,
+        ));
+
+        if self.request_focus {
+            if let Some(sender) = scope.get_atom(crate::input::request_focus_sender()) {
+                sender.send(scope.entity().id()).ok();
+            }
+        }
+
+        // Positions within the displayed rows are relative to the first displayed row
+        let to_text = {
+            to_owned![top];
+            move |loc: CursorLocation| CursorLocation::new(loc.row + top.get(), loc.col)
+        };
+
+        let text_pos = {
+            to_owned![text_bounds];
+            move |absolute_pos: Vec2| {
+                text_bounds
+                    .get()
+                    .map(|v| absolute_pos - v.transform_point3(Vec3::ZERO).xy())
+            }
+        };
+
+        let dragging: Mutable<Option<Drag>> = Mutable::new(None);
+        let options = self.options;
+
+        scope.monitor(rect(), {
+            to_owned![viewport];
+            move |v| viewport.set(v.copied())
+        });
+
+        scope
+            .set(interactive(), ())
+            .set(keep_focus(), ())
+            .set(cursor_icon(), CursorIcon::Text)
+            .on_event(on_focus(), {
+                to_owned![tx];
+                move |_, focus| {
+                    focused.set(focus);
+
+                    if !focus {
+                        tx.send(AreaAction::Input(Action::Editor(
+                            EditorAction::SelectionClear,
+                        )))
+                        .ok();
+                        tx.send(AreaAction::Input(Action::Ime(Ime::Disabled))).ok();
+                    }
+
+                    None
+                }
+            })
+            .on_event(on_mouse_input(), {
+                to_owned![layout_glyphs, tx, dragging, to_text, text_pos];
+                move |_, input| {
+                    let send = |action| {
+                        tx.send(AreaAction::Input(Action::Editor(action))).ok();
+                    };
+
+                    if input.state == ElementState::Pressed {
+                        let pos = text_pos(input.cursor.absolute_pos)?;
+                        let alt = input.modifiers.alt_key();
+
+                        if input.modifiers.shift_key() {
+                            send(EditorAction::SelectionStart);
+                        } else if !alt {
+                            send(EditorAction::SelectionClear);
+                        }
+
+                        if let Some(hit) = layout_glyphs.lock_ref().hit(pos) {
+                            dragging.set(Some(Drag {
+                                start: pos,
+                                column: alt,
+                            }));
+
+                            if alt {
+                                send(EditorAction::AddCaret(to_text(hit)));
+                            } else {
+                                send(EditorAction::CursorMove(CursorMove::SetPosition(to_text(
+                                    hit,
+                                ))));
+                            }
+                        }
+                    } else {
+                        dragging.set(None)
+                    }
+
+                    None
+                }
+            })
+            .on_event(on_cursor_hover(), {
+                to_owned![visuals, text_pos];
+                let tooltip = self.diagnostic_tooltip.clone();
+                let hover = Arc::new(Mutex::new(DiagnosticHover::default()));
+
+                move |scope, event| {
+                    let hovered = match event.state {
+                        HoverState::Exited => None,
+                        HoverState::Entered | HoverState::Moved => text_pos(event.absolute_pos)
+                            .and_then(|pos| {
+                                visuals
+                                    .lock_ref()
+                                    .diagnostics
+                                    .iter()
+                                    .find(|(rects, _)| rects.iter().any(|v| v.contains_point(pos)))
+                                    .map(|(_, v)| v.clone())
+                            }),
+                    };
+
+                    let mut state = hover.lock();
+                    if state.diagnostic == hovered {
+                        return Some(event);
+                    }
+
+                    // Dropping the handle closes the tooltip of the previously hovered diagnostic
+                    state.tooltip = None;
+                    state.diagnostic = hovered.clone();
+
+                    if let Some(diagnostic) = hovered {
+                        let options = tooltip(&diagnostic);
+                        let position = event.absolute_pos + options.offset;
+
+                        to_owned![hover];
+                        scope.spawn_effect(FutureEffect::new(
+                            sleep(options.delay),
+                            move |scope: &mut Scope, _| {
+                                let mut state = hover.lock();
+                                if state.diagnostic.as_ref() != Some(&diagnostic) {
+                                    return;
+                                }
+
+                                let overlays = scope.get_context_cloned(overlay_state());
+                                let overlay = TooltipOverlay::new(
+                                    position,
+                                    pill((options.create_tooltip)(&ScopeRef::from_scope(scope))),
+                                );
+
+                                state.tooltip =
+                                    Some(CloseOnDropHandle::new(overlays.open(overlay)));
+                            },
+                        ));
+                    }
+
+                    Some(event)
+                }
+            })
+            .on_event(on_cursor_move(), {
+                to_owned![layout_glyphs, tx, dragging];
+                move |_, input| {
+                    let drag = dragging.get()?;
+                    let pos = text_pos(input.absolute_pos)?;
+
+                    if pos.distance(drag.start) < 5.0 {
+                        return None;
+                    }
+
+                    let glyphs = layout_glyphs.lock_ref();
+
+                    let action = if drag.column {
+                        let carets = column_selection(&glyphs, drag.start, pos)
+                            .into_iter()
+                            .map(|caret| Caret {
+                                cursor: to_text(caret.cursor),
+                                selection: caret.selection.map(&to_text),
+                            })
+                            .collect_vec();
+
+                        if carets.is_empty() {
+                            return None;
+                        }
+
+                        EditorAction::SetCarets(carets)
+                    } else {
+                        let hit = glyphs.hit(pos)?;
+                        tx.send(AreaAction::Input(Action::Editor(
+                            EditorAction::SelectionStart,
+                        )))
+                        .ok();
+
+                        EditorAction::CursorMove(CursorMove::SetPosition(to_text(hit)))
+                    };
+
+                    tx.send(AreaAction::Input(Action::Editor(action))).ok();
+                    None
+                }
+            })
+            .on_event(on_scroll(), {
+                to_owned![tx];
+                move |_, scroll| {
+                    tx.send(AreaAction::Scroll(scroll.delta.y)).ok();
+                    None
+                }
+            })
+            .on_event(on_keyboard_input(), {
+                to_owned![tx];
+                move |_, input| {
+                    if input.state != ElementState::Pressed {
+                        return None;
+                    }
+
+                    if let Key::Named(key @ (NamedKey::PageUp | NamedKey::PageDown)) = input.key {
+                        let selection = if input.modifiers.shift_key() {
+                            EditorAction::SelectionStart
+                        } else {
+                            EditorAction::SelectionClear
+                        };
+
+                        tx.send(AreaAction::Input(Action::Editor(selection))).ok();
+                        tx.send(AreaAction::Page(if key == NamedKey::PageUp {
+                            -1
+                        } else {
+                            1
+                        }))
+                        .ok();
+                    } else {
+                        keys.handle(&input, &options, &mut |v| {
+                            tx.send(AreaAction::Input(v)).ok();
+                        });
+                    }
+
+                    None
+                }
+            })
+            .on_event(on_ime(), {
+                to_owned![tx];
+                move |_, ime| {
+                    tx.send(AreaAction::Input(Action::Ime(ime))).ok();
+                    None
+                }
+            });
+
+        let gutter = self.line_numbers.then(|| {
+            StreamWidget(
+                visuals
+                    .signal_ref(move |v| {
+                        let digits = v
+                            .line_numbers
+                            .last()
+                            .map_or(1, |(number, _)| number.to_string().len());
+
+                        let labels = v
+                            .line_numbers
+                            .iter()
+                            .map(|&(number, y)| {
+                                Positioned::new(
+                                    Text::new(number.to_string())
+                                        .with_font_size(font_size)
+                                        .with_color(line_number_color)
+                                        .with_margin(Edges::ZERO),
+                                )
+                                .with_offset(Unit::px2(0.0, y))
+                            })
+                            .collect_vec();
+
+                        // Digits are roughly 0.6em wide, with some space between the gutter and
+                        // the text
+                        let width = digits as f32 * font_size * 0.6 + 8.0;
+                        Stack::new(Float::new(labels)).with_min_size(Unit::px2(width, 0.0))
+                    })
+                    .to_stream(),
+            )
+        });
+
+        let markers = self.diagnostics.is_some().then(|| {
+            let tooltip = self.diagnostic_tooltip.clone();
+
+            StreamWidget(
+                visuals
+                    .signal_ref(move |v| {
+                        let icons = v
+                            .markers
+                            .iter()
+                            .map(|(y, diagnostic)| {
+                                let icon = match diagnostic.severity {
+                                    Severity::Error => &error_icon,
+                                    Severity::Warning => &warning_icon,
+                                    Severity::Info => &info_icon,
+                                };
+
+                                Positioned::new(
+                                    InteractiveWidget::new(
+                                        Text::new(icon)
+                                            .with_font_size(font_size)
+                                            .with_color(severity_color(diagnostic.severity))
+                                            .with_margin(Edges::ZERO),
+                                    )
+                                    .with_tooltip(tooltip(diagnostic)),
+                                )
+                                .with_offset(Unit::px2(0.0, *y))
+                            })
+                            .collect_vec();
+
+                        Stack::new(Float::new(icons)).with_min_size(Unit::px2(font_size + 4.0, 0.0))
+                    })
+                    .to_stream(),
+            )
+        });
+
+        let highlight = self.highlight_current_line.then(|| {
+            Float::new(StreamWidget(
+                visuals
+                    .signal_ref(move |v| {
+                        v.current_line.map(|line| {
+                            Positioned::new(
+                                Rectangle::new(current_line_color)
+                                    .with_exact_size(Unit::px(line.size())),
+                            )
+                            .with_offset(Unit::px(line.pos()))
+                        })
+                    })
+                    .to_stream(),
+            ))
+        });
+
+        let overlay = Float::new(StreamWidget(
+            visuals
+                .signal_ref(move |v| {
+                    let matches = v.matches.iter().map(move |line| {
+                        Positioned::new(
+                            Rectangle::new(match_color).with_exact_size(Unit::px(line.size())),
+                        )
+                        .with_offset(Unit::px(line.pos()))
+                    });
+
+                    let selections = v.selections.iter().map(move |line| {
+                        Positioned::new(
+                            Rectangle::new(selection_color).with_exact_size(Unit::px(line.size())),
+                        )
+                        .with_offset(Unit::px(line.pos()))
+                    });
+
+                    let carets = v.carets.iter().chain(&v.preedit).map(move |caret| {
+                        Positioned::new(
+                            Rectangle::new(cursor_color).with_exact_size(Unit::px(caret.size())),
+                        )
+                        .with_offset(Unit::px(caret.pos()))
+                    });
+
+                    Stack::new(matches.chain(selections).chain(carets).collect_vec())
+                })
+                .to_stream(),
+        ));
+
+        Stack::new(row((
+            markers,
+            gutter,
+            Stack::new((
+                highlight,
+                TextContent {
+                    rx: dirty_rx,
+                    text_bounds,
+                    layout_glyphs,
+                    style: input_style.clone(),
+                },
+                overlay,
+            )),
+        )))
+        .with_background(input_style.background)
+        .with_clip(BVec2::TRUE)
+        .with_size_props(input_style.size)
+        .mount(scope)
+    }
+}
+
+/// Pixels scrolled per unit of scroll delta, matching [`crate::widget::ScrollArea`]
+const SCROLL_SENSITIVITY: f32 = -2.0;
+
+enum AreaAction {
+    Input(Action),
+    /// Move the carets and the view by a number of pages
+    Page(isize),
+    /// Scroll the view without moving the carets
+    Scroll(f32),
+}
+
+/// Mouse drag within the displayed rows
+#[derive(Clone, Copy)]
+struct Drag {
+    start: Vec2,
+    /// Alt-dragging selects a rectangular column of text
+    column: bool,
+}
+
+/// Decorations of the displayed rows, relative to the first displayed row
+#[derive(Default)]
+struct AreaVisuals {
+    /// Matches of the search query
+    matches: Vec<Rect>,
+    selections: Vec<Rect>,
+    carets: Vec<Rect>,
+    /// Underline of the text currently being composed by the input method
+    preedit: Option<Rect>,
+    current_line: Option<Rect>,
+    /// Line number and vertical offset of each displayed row
+    line_numbers: Vec<(usize, f32)>,
+    /// Displayed diagnostics along with the area of their text, used for hovering
+    diagnostics: Vec<(Vec<Rect>, Diagnostic)>,
+    /// Vertical offset of each row where a diagnostic starts, with its most severe diagnostic
+    markers: Vec<(f32, Diagnostic)>,
+}
+
+/// Diagnostic under the mouse cursor and its tooltip
+#[derive(Default)]
+struct DiagnosticHover {
+    diagnostic: Option<Diagnostic>,
+    tooltip: Option<CloseOnDropHandle>,
+}
+
+/// State shared between the widget and the editing task
+struct AreaState {
+    focused: Mutable<bool>,
+    layout_glyphs: Mutable<LayoutGlyphs>,
+    text_bounds: Mutable<Option<Mat4>>,
+    viewport: Mutable<Option<Rect>>,
+    /// Index of the first displayed row
+    top: Mutable<usize>,
+    visuals: Mutable<AreaVisuals>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_area_commands(
+    state: AreaState,
+    rx: flume::Receiver<AreaAction>,
+    mut editor: TextEditorCore,
+    clipboard: Arc<Clipboard>,
+    source_content: Arc<dyn Send + Sync + StateDuplex<Item = String>>,
+    dirty_tx: flume::Sender<RowChange>,
+    highlights: Option<Arc<Mutex<HighlightCache>>>,
+    ime_cursor_area: Option<flume::Sender<Rect>>,
+    options: TextOptions,
+    search: TextSearch,
+    diagnostics: Option<Arc<dyn Send + Sync + StateStream<Item = Vec<Diagnostic>>>>,
+    font_size: f32,
+) -> impl Future<Output = ()> {
+    let mut layout_glyphs = state.layout_glyphs.signal_cloned().to_stream().fuse();
+    let mut query_signal = search.query.signal_cloned().to_stream().fuse();
+    let mut new_diagnostics = match &diagnostics {
+        Some(diagnostics) => diagnostics.stream(),
+        None => stream::pending().boxed(),
+    }
+    .fuse();
+    let mut focused_signal = state.focused.stream().fuse();
+    let mut viewport = state.viewport.stream().fuse();
+
+    async move {
+        let mut rx = rx.into_stream().fuse();
+        let mut search_commands = search.commands_rx.clone().into_stream().fuse();
+
+        // Echoes of the published text are dropped, as they may arrive after newer edits
+        let source_content = source_content.prevent_feedback();
+        let mut new_text = throttle(source_content.stream(), || {
+            sleep(Duration::from_millis(100))
+        })
+        .fuse();
+
+        // Publishing copies the whole text, so edits are published at most every 100ms
+        let mut publish: Fuse<Sleep> = Fuse::terminated();
+        let publish_delay = || sleep(Duration::from_millis(100)).fuse();
+
+        let mut glyphs = LayoutGlyphs::default();
+        let mut focused = false;
+        let mut preedit: Option<Preedit> = None;
+        let mut last_ime_area = None;
+        let mut viewport_rect = Rect::ZERO;
+
+        let mut top = 0;
+        // Scrolled distance which does not yet amount to a whole row
+        let mut scroll_offset = 0.0;
+        let mut follow_cursor = false;
+        // Text of the displayed rows
+        let mut shown: Vec<ShownRow> = Vec::new();
+
+        let mut query = search.query.get_cloned();
+        let mut matches = Vec::new();
+        // Matches are only searched for again when the text or query changes
+        let mut matches_stale = true;
+
+        loop {
+            let line_height = if glyphs.line_height > 0.0 {
+                glyphs.line_height
+            } else {
+                font_size
+            };
+
+            let page_rows = page_rows(viewport_rect.size().y, line_height);
+
+            futures::select! {
+                focus = focused_signal.select_next_some() => {
+                    focused = focus;
+
+                    // Pending edits are published right away when leaving the area
+                    if !focus && !publish.is_terminated() {
+                        publish = Fuse::terminated();
+                        source_content.send(editor.text().to_string());
+                    }
+                }
+                new_text = new_text.select_next_some() => {
+                    editor.set_text(new_text.split('\n'));
+                    matches_stale = true;
+                }
+                () = publish => {
+                    source_content.send(editor.text().to_string());
+                }
+                diagnostics = new_diagnostics.select_next_some() => {
+                    editor.set_diagnostics(diagnostics);
+                }
+                new_query = query_signal.select_next_some() => {
+                    query = new_query;
+                    matches_stale = true;
+
+                    // Select the first match at or after the start of the current selection
+                    if !query.is_empty() {
+                        follow_cursor = true;
+                        let (start, _) = editor.carets().next().unwrap().bounds();
+                        editor.set_cursor(start.row, start.col);
+                        editor.select_next_match(&query);
+                    }
+                }
+                command = search_commands.select_next_some() => {
+                    follow_cursor = true;
+
+                    let changed = match command {
+                        SearchCommand::Next => {
+                            editor.select_next_match(&query);
+                            false
+                        }
+                        SearchCommand::Previous => {
+                            editor.select_previous_match(&query);
+                            false
+                        }
+                        SearchCommand::Replace(text) => editor.replace_match(&query, &text),
+                        SearchCommand::ReplaceAll(text) => editor.replace_all(&query, &text) > 0,
+                    };
+
+                    // Moving between matches leaves the text, and therefore the matches, unchanged
+                    if changed {
+                        if publish.is_terminated() {
+                            publish = publish_delay();
+                        }
+                        matches_stale = true;
+                    }
+                }
+                rect = viewport.select_next_some() => {
+                    viewport_rect = rect.unwrap_or_default();
+                }
+                action = rx.select_next_some() => match action {
+                    AreaAction::Input(Action::Editor(EditorAction::CursorMove(
+                        m @ (CursorMove::Left | CursorMove::Right),
+                    ))) => {
+                        follow_cursor = true;
+
+                        // Move on screen rather than in text order within right-to-left text
+                        let move_visual =
+                            |loc| visual_window_move(&editor, &glyphs, top, shown.len(), loc, m);
+
+                        let carets = editor
+                            .carets()
+                            .map(|caret| Caret {
+                                cursor: move_visual(caret.cursor),
+                                ..caret
+                            })
+                            .collect_vec();
+
+                        editor.set_carets(carets);
+                    }
+                    AreaAction::Input(Action::Editor(EditorAction::SelectionMove(
+                        m @ (CursorMove::Left | CursorMove::Right),
+                    ))) => {
+                        follow_cursor = true;
+
+                        let move_visual =
+                            |loc| visual_window_move(&editor, &glyphs, top, shown.len(), loc, m);
+
+                        let carets = editor
+                            .carets()
+                            .map(|caret| {
+                                let head = caret.selection.unwrap_or(caret.cursor);
+                                Caret {
+                                    selection: Some(move_visual(head)),
+                                    ..caret
+                                }
+                            })
+                            .collect_vec();
+
+                        editor.set_carets(carets);
+                    }
+                    AreaAction::Input(Action::Editor(EditorAction::CursorMove(
+                        m @ (CursorMove::Up | CursorMove::Down),
+                    ))) => {
+                        follow_cursor = true;
+
+                        let down = matches!(m, CursorMove::Down);
+                        let carets = editor
+                            .carets()
+                            .map(|caret| {
+                                let cursor = to_window(top, shown.len(), caret.cursor)
+                                    .and_then(|loc| visual_move(&glyphs, loc, down))
+                                    .map(|loc| CursorLocation::new(loc.row + top, loc.col))
+                                    .unwrap_or_else(|| {
+                                        move_rows(&editor, caret.cursor, if down { 1 } else { -1 })
+                                    });
+
+                                Caret { cursor, ..caret }
+                            })
+                            .collect_vec();
+
+                        editor.set_carets(carets);
+                    }
+                    AreaAction::Input(action) => {
+                        follow_cursor = true;
+
+                        if apply_action(&mut editor, &clipboard, &options, &mut preedit, action).await {
+                            if publish.is_terminated() {
+                                publish = publish_delay();
+                            }
+                            matches_stale = true;
+                        }
+                    }
+                    AreaAction::Page(pages) => {
+                        follow_cursor = true;
+
+                        let rows = pages * page_rows as isize;
+                        let carets = editor
+                            .carets()
+                            .map(|caret| Caret {
+                                cursor: move_rows(&editor, caret.cursor, rows),
+                                ..caret
+                            })
+                            .collect_vec();
+
+                        editor.set_carets(carets);
+                        top = offset_row(&editor, top, rows);
+                    }
+                    AreaAction::Scroll(delta) => {
+                        follow_cursor = false;
+
+                        scroll_offset += delta * SCROLL_SENSITIVITY;
+                        let rows = (scroll_offset / line_height).trunc();
+                        scroll_offset -= rows * line_height;
+                        top = offset_row(&editor, top, rows as isize);
+                    }
+                },
+                new_glyphs = layout_glyphs.select_next_some() => {
+                    glyphs = new_glyphs;
+                }
+            }
+
+            let cursor = editor.cursor();
+            if follow_cursor && viewport_rect.size().y > 0.0 {
+                top = scroll_to_row(top, cursor.row, page_rows, viewport_rect.size().y, |row| {
+                    glyphs.rows.get(row).map_or(1, |v| v.len().max(1)) as f32 * line_height
+                });
+            }
+
+            top = top.min(editor.text().len_lines() - 1);
+            state.top.set(top);
+
+            sync_rows(
+                &editor,
+                highlights.as_deref(),
+                preedit.as_ref(),
+                top..top + page_rows + 1,
+                &mut shown,
+                &dirty_tx,
+            );
+
+            let rows = shown.len();
+            let window = |loc| to_window(top, rows, loc);
+
+            let cursor_location = match &preedit {
+                Some(preedit) => preedit.cursor.map(|(start, _)| CursorLocation {
+                    row: preedit.location.row,
+                    col: preedit.location.col + start,
+                }),
+                None => Some(cursor),
+            };
+
+            let cursor_pos = cursor_location
+                .and_then(window)
+                .map(|v| calculate_position(&glyphs, v));
+
+            if let (true, Some(ime_cursor_area), Some(cursor_pos)) =
+                (focused, &ime_cursor_area, cursor_pos)
+            {
+                let origin = state
+                    .text_bounds
+                    .get()
+                    .map(|v| v.transform_point3(Vec3::ZERO).xy())
+                    .unwrap_or_default();
+
+                let area = Rect::from_size_pos(vec2(2.0, line_height), origin + cursor_pos);
+
+                if last_ime_area != Some(area) {
+                    ime_cursor_area.send(area).ok();
+                    last_ime_area = Some(area);
+                }
+            }
+
+            let visuals = &mut *state.visuals.lock_mut();
+
+            visuals.preedit = preedit.as_ref().and_then(|preedit| {
+                let location = window(preedit.location)?;
+                let start = calculate_position(&glyphs, location);
+                let end = calculate_position(
+                    &glyphs,
+                    CursorLocation::new(location.row, location.col + preedit.text.len()),
+                );
+
+                let underline_height = 1.5;
+                Some(Rect::from_size_pos(
+                    vec2((end.x - start.x).max(0.0), underline_height),
+                    start + vec2(0.0, line_height - underline_height),
+                ))
+            });
+
+            if matches_stale {
+                matches.clear();
+                if !query.is_empty() {
+                    matches.extend(editor.find_matches(&query));
+                }
+
+                matches_stale = false;
+            }
+
+            search.status.set_neq((!query.is_empty()).then(|| {
+                SearchStatus {
+                    current: matches
+                        .iter()
+                        .position(|&v| Some(v) == editor.selection_bounds()),
+                    count: matches.len(),
+                }
+            }));
+
+            visuals.matches.clear();
+            for &(start, end) in &matches {
+                if end.row < top || start.row >= top + rows {
+                    continue;
+                }
+
+                let start = window(start).unwrap_or_default();
+                let end = window(end).unwrap_or(CursorLocation::new(rows - 1, usize::MAX));
+                visuals.matches.extend(selection_rects(&glyphs, start, end));
+            }
+
+            visuals.diagnostics.clear();
+            for diagnostic in editor.diagnostics() {
+                if diagnostic.end.row < top || diagnostic.start.row >= top + rows {
+                    continue;
+                }
+
+                let start = window(diagnostic.start).unwrap_or_default();
+                let end =
+                    window(diagnostic.end).unwrap_or(CursorLocation::new(rows - 1, usize::MAX));
+
+                let rects = selection_rects(&glyphs, start, end).collect_vec();
+                visuals.diagnostics.push((rects, diagnostic));
+            }
+
+            visuals.selections.clear();
+            visuals.carets.clear();
+
+            for (i, caret) in editor.carets().enumerate() {
+                if let Some((start, end)) = caret.selection_bounds() {
+                    if end.row < top || start.row >= top + rows {
+                        continue;
+                    }
+
+                    // Selections extending outside the displayed rows are cut off at the edges
+                    let start = window(start).unwrap_or_default();
+                    let end = window(end).unwrap_or(CursorLocation::new(rows - 1, usize::MAX));
+                    visuals
+                        .selections
+                        .extend(selection_rects(&glyphs, start, end));
+                } else if focused {
+                    // The primary caret is displayed within the text being composed
+                    let pos = match i {
+                        0 => cursor_pos,
+                        _ => window(caret.cursor).map(|v| calculate_position(&glyphs, v)),
+                    };
+
+                    if let Some(pos) = pos {
+                        visuals
+                            .carets
+                            .push(Rect::from_size_pos(vec2(2.0, line_height), pos));
+                    }
+                }
+            }
+
+            visuals.line_numbers.clear();
+            visuals.markers.clear();
+            visuals.current_line = None;
+
+            let mut y = 0.0;
+            for i in 0..rows {
+                let height = glyphs.rows.get(i).map_or(1, |v| v.len().max(1)) as f32 * line_height;
+
+                visuals.line_numbers.push((top + i + 1, y));
+
+                let marker = visuals
+                    .diagnostics
+                    .iter()
+                    .map(|(_, v)| v)
+                    .filter(|v| v.start.row == top + i)
+                    .max_by_key(|v| v.severity);
+
+                if let Some(marker) = marker {
+                    visuals.markers.push((y, marker.clone()));
+                }
+                if top + i == cursor.row {
+                    visuals.current_line = Some(Rect::from_size_pos(
+                        vec2(viewport_rect.size().x, height),
+                        vec2(0.0, y),
+                    ));
+                }
+
+                y += height;
+            }
+        }
+    }
+}
+
+/// Text, highlighting state and underlined diagnostics of a displayed row
+type ShownRow = (String, HighlightState, Vec<(Range<usize>, Severity)>);
+
+/// Updates the displayed rows to show the given rows of the text.
+///
+/// Only rows whose text, highlighting state or diagnostics differ from what is displayed are
+/// sent.
+fn sync_rows(
+    editor: &TextEditorCore,
+    highlights: Option<&Mutex<HighlightCache>>,
+    preedit: Option<&Preedit>,
+    rows: Range<usize>,
+    shown: &mut Vec<ShownRow>,
+    dirty_tx: &flume::Sender<RowChange>,
+) {
+    let rows = rows.start..rows.end.min(editor.text().len_lines());
+
+    let diagnostics = editor
+        .diagnostics()
+        .filter(|v| v.start.row < rows.end && v.end.row >= rows.start)
+        .collect_vec();
+
+    for (i, row) in rows.clone().enumerate() {
+        let preedit = preedit.filter(|v| v.location.row == row);
+
+        let mut text = editor.text().line(row).into_owned();
+        if let Some(preedit) = preedit {
+            text.insert_str(preedit.location.col.min(text.len()), &preedit.text);
+        }
+
+        let underlines = diagnostics
+            .iter()
+            .filter_map(|v| {
+                let columns = diagnostic_columns(v, row, editor.text().line_len(row))?;
+                Some((columns, v.severity))
+            })
+            .collect_vec();
+
+        let state = highlights.map(|v| v.lock().state(row)).unwrap_or_default();
+        let displayed = (text, state, underlines);
+
+        if shown.get(i) == Some(&displayed) {
+            continue;
+        }
+
+        let mut segments =
+            underline_segments(line_segments(highlights, editor.text(), row), &displayed.2);
+
+        if let Some(preedit) = preedit {
+            insert_segment_text(&mut segments, preedit.location.col, &preedit.text);
+        }
+
+        dirty_tx.send(RowChange::Set(i, segments)).ok();
+
+        if i < shown.len() {
+            shown[i] = displayed;
+        } else {
+            shown.push(displayed);
+        }
+    }
+
+    while shown.len() > rows.len() {
+        shown.pop();
+        dirty_tx.send(RowChange::Remove(shown.len())).ok();
+    }
+}
+
+/// Returns the columns of a row covered by a diagnostic
+fn diagnostic_columns(diagnostic: &Diagnostic, row: usize, len: usize) -> Option<Range<usize>> {
+    if row < diagnostic.start.row || row > diagnostic.end.row {
+        return None;
+    }
+
+    let start = if row == diagnostic.start.row {
+        diagnostic.start.col
+    } else {
+        0
+    };
+
+    let end = if row == diagnostic.end.row {
+        diagnostic.end.col
+    } else {
+        len
+    };
+
+    (start < end).then_some(start..end)
+}
+
+/// Splits the segments of a row to underline the columns of diagnostics with a wavy line.
+///
+/// Where diagnostics overlap, the color of the most severe one is used.
+fn underline_segments(
+    segments: Vec<TextSegment>,
+    underlines: &[(Range<usize>, Severity)],
+) -> Vec<TextSegment> {
+    if underlines.is_empty() {
+        return segments;
+    }
+
+    let mut result = Vec::new();
+    let mut offset = 0;
+
+    for segment in segments {
+        let end = offset + segment.text.len();
+
+        let cuts = underlines
+            .iter()
+            .flat_map(|(v, _)| [v.start, v.end])
+            .filter(|&v| v > offset && v < end && segment.text.is_char_boundary(v - offset))
+            .sorted()
+            .dedup()
+            .chain([end]);
+
+        let mut start = offset;
+        for cut in cuts {
+            let mut part = segment.clone();
+            part.text = segment.text[start - offset..cut - offset].to_string();
+
+            let severity = underlines
+                .iter()
+                .filter(|(v, _)| v.start <= start && cut <= v.end)
+                .map(|&(_, severity)| severity)
+                .max();
+
+            if let Some(severity) = severity {
+                part = part
+                    .with_underline(UnderlineStyle::Wavy)
+                    .with_decoration_color(severity_color(severity));
+            }
+
+            result.push(part);
+            start = cut;
+        }
+
+        offset = end;
+    }
+
+    result
+}
+
+fn severity_color(severity: Severity) -> ValueOrRef<Srgba> {
+    match severity {
+        Severity::Error => surface_danger().into(),
+        Severity::Warning => surface_warning().into(),
+        Severity::Info => surface_accent().into(),
+    }
+}
+
+/// Number of whole rows which fit in the viewport, and at least one
+fn page_rows(viewport_height: f32, line_height: f32) -> usize {
+    ((viewport_height / line_height) as usize).max(1)
+}
+
+/// Returns the first displayed row which brings `row` into view, scrolling as little as possible.
+///
+/// `row_height` is the laid out height of a row relative to `top`, as rows may wrap.
+fn scroll_to_row(
+    top: usize,
+    row: usize,
+    page_rows: usize,
+    viewport_height: f32,
+    row_height: impl Fn(usize) -> f32,
+) -> usize {
+    if row < top {
+        return row;
+    } else if row >= top + page_rows {
+        return row + 1 - page_rows;
+    }
+
+    // Wrapped rows above may still push the row out of view
+    let rel = row - top;
+    let mut overflow = (0..=rel).map(&row_height).sum::<f32>() - viewport_height;
+
+    let mut skipped = 0;
+    while overflow > 0.0 && skipped < rel {
+        overflow -= row_height(skipped);
+        skipped += 1;
+    }
+
+    top + skipped
+}
+
+/// Converts a location in the text to a location within the displayed rows
+fn to_window(top: usize, rows: usize, loc: CursorLocation) -> Option<CursorLocation> {
+    let row = loc.row.checked_sub(top).filter(|&row| row < rows)?;
+    Some(CursorLocation::new(row, loc.col))
+}
+
+/// Moves a location left or right on screen within the displayed rows, and in text order outside
+/// of them
+fn visual_window_move(
+    editor: &TextEditorCore,
+    glyphs: &LayoutGlyphs,
+    top: usize,
+    rows: usize,
+    loc: CursorLocation,
+    m: CursorMove,
+) -> CursorLocation {
+    to_window(top, rows, loc)
+        .and_then(|v| glyphs.visual_move(v, matches!(m, CursorMove::Right)))
+        .map(|v| CursorLocation::new(v.row + top, v.col))
+        .unwrap_or_else(|| editor.get_new_cursor(m, loc))
+}
+
+fn offset_row(editor: &TextEditorCore, row: usize, rows: isize) -> usize {
+    row.saturating_add_signed(rows)
+        .min(editor.text().len_lines() - 1)
+}
+
+/// Moves a location by a number of rows, keeping the column where the target line allows.
+///
+/// The column is snapped to the start of the grapheme it falls within on the target line.
+fn move_rows(editor: &TextEditorCore, loc: CursorLocation, rows: isize) -> CursorLocation {
+    let row = offset_row(editor, loc.row, rows);
+    let line = editor.text().line(row);
+
+    let col = line
+        .grapheme_indices(true)
+        .map(|(i, _)| i)
+        .chain([line.len()])
+        .take_while(|&i| i <= loc.col)
+        .last()
+        .unwrap_or_default();
+
+    CursorLocation::new(row, col)
+}
+
+/// Moves a location to the visual line above or below, following wrapped lines.
+///
+/// Returns `None` if the line is not laid out.
+fn visual_move(glyphs: &LayoutGlyphs, loc: CursorLocation, down: bool) -> Option<CursorLocation> {
+    // A location at a wrap boundary belongs to the following line
+    let line = glyphs
+        .find_lines(loc.row)
+        .position(|v| loc.col < v.end)
+        .or_else(|| glyphs.find_lines(loc.row).count().checked_sub(1))?;
+
+    let (ln, _) = glyphs.find_lines_indices(loc.row).nth(line)?;
+    let target = if down { ln + 1 } else { ln.checked_sub(1)? };
+
+    glyphs.lines().nth(target)?;
+
+    let x = calculate_position(glyphs, loc).x;
+    glyphs.hit(vec2(x, (target as f32 + 0.5) * glyphs.line_height))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn editor(lines: impl IntoIterator<Item = String>) -> TextEditorCore {
+        let mut editor = TextEditorCore::new(|_, _| {});
+        let lines = lines.into_iter().collect_vec();
+        editor.set_text(lines.iter().map(|v| v.as_str()));
+        editor
+    }
+
+    /// Returns the text of each set row, or `None` for removed rows
+    fn changes(rx: &flume::Receiver<RowChange>) -> Vec<(usize, Option<String>)> {
+        rx.try_iter()
+            .map(|change| match change {
+                RowChange::Set(i, segments) => {
+                    (i, Some(segments.iter().map(|v| v.text.as_str()).collect()))
+                }
+                RowChange::Remove(i) => (i, None),
+                _ => panic!("Unexpected row change"),
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn window_rows() {
+        let editor = editor((0..10_000).map(|i| format!("Line {i}")));
+        let (tx, rx) = flume::unbounded();
+        let mut shown = Vec::new();
+
+        // Only the rows of the window are sent, regardless of the length of the text
+        sync_rows(&editor, None, None, 20..24, &mut shown, &tx);
+        assert_eq!(
+            changes(&rx),
+            [
+                (0, Some("Line 20".into())),
+                (1, Some("Line 21".into())),
+                (2, Some("Line 22".into())),
+                (3, Some("Line 23".into())),
+            ]
+        );
+        assert_eq!(shown.len(), 4);
+
+        // Unchanged rows are not sent again
+        sync_rows(&editor, None, None, 20..24, &mut shown, &tx);
+        assert_eq!(changes(&rx), []);
+
+        // Scrolling replaces the displayed rows in place, and shrinking removes rows from the end
+        sync_rows(&editor, None, None, 21..23, &mut shown, &tx);
+        assert_eq!(
+            changes(&rx),
+            [
+                (0, Some("Line 21".into())),
+                (1, Some("Line 22".into())),
+                (3, None),
+                (2, None),
+            ]
+        );
+        assert_eq!(shown.len(), 2);
+
+        // The window is cut off at the end of the text
+        sync_rows(&editor, None, None, 9_999..10_003, &mut shown, &tx);
+        assert_eq!(changes(&rx), [(0, Some("Line 9999".into())), (1, None)]);
+        assert_eq!(shown.len(), 1);
+    }
+
+    #[test]
+    fn window_preedit() {
+        let editor = editor(["abc".to_string(), "def".to_string()]);
+        let (tx, rx) = flume::unbounded();
+        let mut shown = Vec::new();
+
+        let preedit = Preedit {
+            location: CursorLocation::new(1, 1),
+            text: "xy".into(),
+            cursor: None,
+        };
+
+        sync_rows(&editor, None, Some(&preedit), 0..2, &mut shown, &tx);
+        assert_eq!(
+            changes(&rx),
+            [(0, Some("abc".into())), (1, Some("dxyef".into()))]
+        );
+
+        // Finishing composition restores the row
+        sync_rows(&editor, None, None, 0..2, &mut shown, &tx);
+        assert_eq!(changes(&rx), [(1, Some("def".into()))]);
+    }
+
+    #[test]
+    fn page_math() {
+        assert_eq!(page_rows(100.0, 20.0), 5);
+        assert_eq!(page_rows(119.0, 20.0), 5);
+        assert_eq!(page_rows(10.0, 20.0), 1);
+        assert_eq!(page_rows(0.0, 20.0), 1);
+
+        let single = |_| 20.0;
+
+        // Rows within the page do not scroll
+        assert_eq!(scroll_to_row(10, 10, 5, 100.0, single), 10);
+        assert_eq!(scroll_to_row(10, 14, 5, 100.0, single), 10);
+
+        // Rows outside of the page scroll until they are at the edge
+        assert_eq!(scroll_to_row(10, 4, 5, 100.0, single), 4);
+        assert_eq!(scroll_to_row(10, 15, 5, 100.0, single), 11);
+        assert_eq!(scroll_to_row(10, 40, 5, 100.0, single), 36);
+
+        // The first two rows wrap onto three lines each, pushing the following rows out of view
+        let wrapped = |row| if row < 2 { 60.0 } else { 20.0 };
+        assert_eq!(scroll_to_row(10, 10, 5, 100.0, wrapped), 10);
+        assert_eq!(scroll_to_row(10, 11, 5, 100.0, wrapped), 11);
+        assert_eq!(scroll_to_row(10, 12, 5, 100.0, wrapped), 11);
+        assert_eq!(scroll_to_row(10, 13, 5, 100.0, wrapped), 11);
+
+        // A row taller than the viewport is kept at the top
+        assert_eq!(scroll_to_row(10, 12, 5, 100.0, |_| 200.0), 12);
+    }
+
+    #[test]
+    fn move_by_rows() {
+        let editor = editor(
+            ["hello world", "e\u{301}x", "", "last line"]
+                .into_iter()
+                .map(String::from),
+        );
+
+        let at = |row, col| CursorLocation::new(row, col);
+
+        assert_eq!(move_rows(&editor, at(0, 4), 3), at(3, 4));
+        assert_eq!(move_rows(&editor, at(3, 4), -3), at(0, 4));
+
+        // Columns within a grapheme snap to its start, and beyond the line to its end
+        assert_eq!(move_rows(&editor, at(0, 2), 1), at(1, 0));
+        assert_eq!(move_rows(&editor, at(0, 3), 1), at(1, 3));
+        assert_eq!(move_rows(&editor, at(0, 8), 1), at(1, 4));
+        assert_eq!(move_rows(&editor, at(0, 8), 2), at(2, 0));
+
+        // Moving past either end of the text stops at the first and last rows
+        assert_eq!(move_rows(&editor, at(1, 1), -10), at(0, 1));
+        assert_eq!(move_rows(&editor, at(1, 1), 10), at(3, 1));
+        assert_eq!(offset_row(&editor, 2, -5), 0);
+        assert_eq!(offset_row(&editor, 2, 5), 3);
+    }
+}
@@ -0,0 +1,575 @@
+use std::sync::Arc;
+
+use cosmic_text::{Style, Weight};
+use futures::StreamExt;
+use glam::Vec2;
+use palette::Srgba;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+
+use crate::{
+    state::StateStream,
+    style::{
+        default_corner_radius, element_accent, element_primary, element_secondary,
+        element_tertiary, spacing_medium, spacing_small, surface_secondary, text_large,
+        text_medium, text_small, Background, SizeExt, StyleExt, ValueOrRef, WidgetSizeProps,
+    },
+    text::{FontFamily, TextOverflow, TextSegment, UnderlineStyle, Wrap},
+    unit::Unit,
+    widget::{col, label, row, LinkCallback, Rectangle, Stack, StreamWidget, Text, WidgetExt},
+    Scope, ScopeRef, Widget,
+};
+
+#[derive(Debug, Clone)]
+pub struct MarkdownStyle {
+    pub link_color: ValueOrRef<Srgba>,
+    pub code_color: ValueOrRef<Srgba>,
+    pub code_background: ValueOrRef<Srgba>,
+    pub quote_color: ValueOrRef<Srgba>,
+    pub rule_color: ValueOrRef<Srgba>,
+}
+
+impl Default for MarkdownStyle {
+    fn default() -> Self {
+        Self {
+            link_color: element_accent().into(),
+            code_color: element_secondary().into(),
+            code_background: surface_secondary().into(),
+            quote_color: element_tertiary().into(),
+            rule_color: element_tertiary().into(),
+        }
+    }
+}
+
+/// A block level element of a markdown document
+#[derive(Debug, Clone)]
+pub enum MarkdownBlock {
+    Heading(usize, Vec<TextSegment>),
+    Paragraph(Vec<TextSegment>),
+    CodeBlock {
+        language: Option<String>,
+        code: String,
+    },
+    /// Numbered lists start at the given number
+    List {
+        start: Option<u64>,
+        items: Vec<Vec<MarkdownBlock>>,
+    },
+    Quote(Vec<MarkdownBlock>),
+    Table {
+        head: Vec<Vec<TextSegment>>,
+        rows: Vec<Vec<Vec<TextSegment>>>,
+    },
+    Rule,
+}
+
+enum Container {
+    Document(Vec<MarkdownBlock>),
+    Quote(Vec<MarkdownBlock>),
+    List {
+        start: Option<u64>,
+        items: Vec<Vec<MarkdownBlock>>,
+    },
+    Item(Vec<MarkdownBlock>),
+}
+
+struct DocumentBuilder<'a> {
+    style: &'a MarkdownStyle,
+    containers: Vec<Container>,
+    /// Inline content of the current paragraph, heading or table cell
+    segments: Vec<TextSegment>,
+    /// Styles of the currently open inline spans
+    spans: Vec<TextSegment>,
+    code: Option<(Option<String>, String)>,
+    table: Vec<Vec<Vec<TextSegment>>>,
+}
+
+impl<'a> DocumentBuilder<'a> {
+    fn new(style: &'a MarkdownStyle) -> Self {
+        Self {
+            style,
+            containers: vec![Container::Document(Vec::new())],
+            segments: Vec::new(),
+            spans: Vec::new(),
+            code: None,
+            table: Vec::new(),
+        }
+    }
+
+    fn span(&self) -> TextSegment {
+        self.spans
+            .last()
+            .cloned()
+            .unwrap_or_else(|| TextSegment::new(""))
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if let Some((_, code)) = &mut self.code {
+            code.push_str(text);
+        } else {
+            let segment = TextSegment {
+                text: text.to_string(),
+                ..self.span()
+            };
+
+            self.segments.push(segment);
+        }
+    }
+
+    fn push_block(&mut self, block: MarkdownBlock) {
+        match self.containers.last_mut() {
+            Some(
+                Container::Document(blocks) | Container::Quote(blocks) | Container::Item(blocks),
+            ) => blocks.push(block),
+            Some(Container::List { items, .. }) => items.push(vec![block]),
+            None => {}
+        }
+    }
+
+    /// Tight list items contain text without an enclosing paragraph
+    fn flush_paragraph(&mut self) {
+        if !self.segments.is_empty() {
+            let segments = std::mem::take(&mut self.segments);
+            self.push_block(MarkdownBlock::Paragraph(segments));
+        }
+    }
+
+    fn process(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.push_text(&text),
+            Event::Code(code) => {
+                let segment = TextSegment {
+                    text: code.to_string(),
+                    ..self.span()
+                }
+                .with_family(FontFamily::Monospace)
+                .with_color(self.style.code_color);
+
+                self.segments.push(segment);
+            }
+            Event::SoftBreak => self.push_text(" "),
+            Event::HardBreak => self.push_text("\n"),
+            Event::Rule => {
+                self.flush_paragraph();
+                self.push_block(MarkdownBlock::Rule)
+            }
+            Event::TaskListMarker(checked) => self.push_text(if checked { "☑ " } else { "☐ " }),
+            Event::FootnoteReference(name) => self.push_text(&format!("[{name}]")),
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::BlockQuote(_) => {
+                self.flush_paragraph();
+                self.containers.push(Container::Quote(Vec::new()))
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush_paragraph();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().map(|v| v.to_string())
+                    }
+                    CodeBlockKind::Indented => None,
+                };
+
+                self.code = Some((language, String::new()));
+            }
+            Tag::List(start) => {
+                self.flush_paragraph();
+                self.containers.push(Container::List {
+                    start,
+                    items: Vec::new(),
+                })
+            }
+            Tag::Item => self.containers.push(Container::Item(Vec::new())),
+            Tag::Table(_) => self.table.clear(),
+            Tag::TableHead | Tag::TableRow => self.table.push(Vec::new()),
+            Tag::Emphasis => {
+                let span = self.span().with_style(Style::Italic);
+                self.spans.push(span);
+            }
+            Tag::Strong => {
+                let span = self.span().with_weight(Weight::BOLD);
+                self.spans.push(span);
+            }
+            Tag::Link { dest_url, .. } => {
+                let span = self
+                    .span()
+                    .with_color(self.style.link_color)
+                    .with_underline(UnderlineStyle::Solid)
+                    .with_link(dest_url.to_string());
+                self.spans.push(span);
+            }
+            Tag::Strikethrough => {
+                let span = self.span().with_strikethrough();
+                self.spans.push(span);
+            }
+            // Shows the alt text of the image
+            Tag::Image { .. } => {
+                let span = self.span();
+                self.spans.push(span);
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.flush_paragraph(),
+            TagEnd::Heading(level) => {
+                let segments = std::mem::take(&mut self.segments);
+                self.push_block(MarkdownBlock::Heading(level as usize, segments));
+            }
+            TagEnd::CodeBlock => {
+                if let Some((language, mut code)) = self.code.take() {
+                    code.truncate(code.trim_end_matches('\n').len());
+                    self.push_block(MarkdownBlock::CodeBlock { language, code });
+                }
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush_paragraph();
+                if let Some(Container::Quote(blocks)) = self.containers.pop() {
+                    self.push_block(MarkdownBlock::Quote(blocks));
+                }
+            }
+            TagEnd::Item => {
+                self.flush_paragraph();
+                if let Some(Container::Item(blocks)) = self.containers.pop() {
+                    if let Some(Container::List { items, .. }) = self.containers.last_mut() {
+                        items.push(blocks);
+                    }
+                }
+            }
+            TagEnd::List(_) => {
+                if let Some(Container::List { start, items }) = self.containers.pop() {
+                    self.push_block(MarkdownBlock::List { start, items });
+                }
+            }
+            TagEnd::TableCell => {
+                let segments = std::mem::take(&mut self.segments);
+                if let Some(row) = self.table.last_mut() {
+                    row.push(segments);
+                }
+            }
+            TagEnd::Table => {
+                let mut rows = std::mem::take(&mut self.table);
+                let head = if rows.is_empty() {
+                    Vec::new()
+                } else {
+                    rows.remove(0)
+                };
+
+                self.push_block(MarkdownBlock::Table { head, rows });
+            }
+            TagEnd::Emphasis
+            | TagEnd::Strong
+            | TagEnd::Strikethrough
+            | TagEnd::Link
+            | TagEnd::Image => {
+                self.spans.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> Vec<MarkdownBlock> {
+        self.flush_paragraph();
+        match self.containers.swap_remove(0) {
+            Container::Document(blocks) => blocks,
+            _ => unreachable!("document is always the root container"),
+        }
+    }
+}
+
+/// Parses a markdown document into blocks of styled text
+pub fn parse_markdown(markdown: &str, style: &MarkdownStyle) -> Vec<MarkdownBlock> {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+
+    let mut builder = DocumentBuilder::new(style);
+    for event in Parser::new_ext(markdown, options) {
+        builder.process(event);
+    }
+
+    builder.finish()
+}
+
+/// Renders a markdown document.
+///
+/// The document is re-rendered whenever the source changes.
+pub struct Markdown<S> {
+    source: S,
+    style: MarkdownStyle,
+    size: WidgetSizeProps,
+    on_link: Option<LinkCallback>,
+}
+
+impl<S> Markdown<S>
+where
+    S: StateStream<Item = String>,
+{
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            style: Default::default(),
+            size: Default::default(),
+            on_link: None,
+        }
+    }
+
+    /// Invoked when a link in the document is clicked
+    pub fn on_link(
+        mut self,
+        on_link: impl 'static + Send + Sync + Fn(&ScopeRef<'_>, &str),
+    ) -> Self {
+        self.on_link = Some(Arc::new(on_link));
+        self
+    }
+}
+
+impl<S> StyleExt for Markdown<S> {
+    type Style = MarkdownStyle;
+
+    fn with_style(mut self, style: Self::Style) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+impl<S> SizeExt for Markdown<S> {
+    fn size_mut(&mut self) -> &mut WidgetSizeProps {
+        &mut self.size
+    }
+}
+
+impl<S> Widget for Markdown<S>
+where
+    S: StateStream<Item = String>,
+{
+    fn mount(self, scope: &mut Scope<'_>) {
+        self.size.mount(scope);
+
+        let style = self.style;
+        let on_link = self.on_link;
+
+        StreamWidget::new(self.source.stream().map(move |source| {
+            let blocks = parse_markdown(&source, &style);
+            col(blocks
+                .into_iter()
+                .map(|block| render_block(block, &style, &on_link))
+                .collect::<Vec<_>>())
+            .with_stretch(true)
+        }))
+        .mount(scope);
+    }
+}
+
+fn render_block(
+    block: MarkdownBlock,
+    style: &MarkdownStyle,
+    on_link: &Option<LinkCallback>,
+) -> Box<dyn Widget> {
+    match block {
+        MarkdownBlock::Heading(level, segments) => {
+            // Matches the `title` and `subtitle` widgets
+            let (weight, font_size, color) = match level {
+                1 => (Weight::BOLD, text_large(), element_primary()),
+                2 => (Weight::MEDIUM, text_medium(), element_secondary()),
+                _ => (Weight::BOLD, text_small(), element_primary()),
+            };
+
+            let segments = segments
+                .into_iter()
+                .map(|v| {
+                    let weight = Weight(v.weight.0.max(weight.0));
+                    v.with_weight(weight)
+                })
+                .collect();
+
+            paragraph(segments, on_link)
+                .with_font_size(font_size)
+                .with_color(color)
+                .with_margin(spacing_medium())
+                .boxed()
+        }
+        MarkdownBlock::Paragraph(segments) => paragraph(segments, on_link).boxed(),
+        MarkdownBlock::CodeBlock { code, .. } => Stack::new(
+            Text::formatted([TextSegment::new(code).with_family(FontFamily::Monospace)])
+                .with_color(style.code_color),
+        )
+        .with_background(Background::new(style.code_background))
+        .with_padding(spacing_small())
+        .with_margin(spacing_small())
+        .with_corner_radius(default_corner_radius())
+        .boxed(),
+        MarkdownBlock::List { start, items } => col(items
+            .into_iter()
+            .enumerate()
+            .map(|(i, blocks)| {
+                let marker = match start {
+                    Some(start) => format!("{}.", start + i as u64),
+                    None => "•".to_string(),
+                };
+
+                row((
+                    label(marker),
+                    col(blocks
+                        .into_iter()
+                        .map(|block| render_block(block, style, on_link))
+                        .collect::<Vec<_>>()),
+                ))
+            })
+            .collect::<Vec<_>>())
+        .boxed(),
+        MarkdownBlock::Quote(blocks) => row((
+            Rectangle::new(style.quote_color)
+                .with_min_size(Unit::px2(2.0, 0.0))
+                .with_margin(spacing_small()),
+            col(blocks
+                .into_iter()
+                .map(|block| render_block(block, style, on_link))
+                .collect::<Vec<_>>()),
+        ))
+        .with_stretch(true)
+        .boxed(),
+        MarkdownBlock::Table { head, rows } => {
+            let columns = rows
+                .iter()
+                .map(|v| v.len())
+                .chain([head.len()])
+                .max()
+                .unwrap_or_default();
+
+            // Each column is laid out separately so that the cells line up
+            let mut cells = vec![Vec::new(); columns];
+            for (i, cell) in head.into_iter().enumerate() {
+                let cell = cell
+                    .into_iter()
+                    .map(|v| v.with_weight(Weight::BOLD))
+                    .collect();
+                cells[i].push(cell);
+            }
+
+            for row in rows {
+                for (i, cell) in row.into_iter().enumerate() {
+                    cells[i].push(cell);
+                }
+            }
+
+            row(cells
+                .into_iter()
+                .map(|column| {
+                    col(column
+                        .into_iter()
+                        .map(|cell| {
+                            paragraph(cell, on_link)
+                                .with_wrap(Wrap::None)
+                                .with_overflow(TextOverflow::EllipsisEnd)
+                        })
+                        .collect::<Vec<_>>())
+                })
+                .collect::<Vec<_>>())
+            .with_background(Background::new(style.code_background))
+            .boxed()
+        }
+        MarkdownBlock::Rule => Rectangle::new(style.rule_color)
+            .with_min_size(Unit::px2(0.0, 1.0))
+            .with_maximize(Vec2::X)
+            .with_margin(spacing_small())
+            .boxed(),
+    }
+}
+
+fn paragraph(segments: Vec<TextSegment>, on_link: &Option<LinkCallback>) -> Text {
+    let text = Text::formatted(segments).with_wrap(Wrap::Word);
+
+    match on_link.clone() {
+        Some(on_link) => text.on_link_click(move |scope, link| on_link(scope, link)),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn texts(segments: &[TextSegment]) -> Vec<&str> {
+        segments.iter().map(|v| &*v.text).collect()
+    }
+
+    #[test]
+    fn parse_document() {
+        let source = r#"# Release notes
+
+Some *emphasis*, **strong** and `code` with a [link](https://example.com).
+
+- first
+- second
+  1. nested
+
+> quoted
+
+```rust
+fn main() {}
+```
+
+| a | b |
+|---|---|
+| 1 | 2 |
+
+---
+"#;
+
+        let blocks = parse_markdown(source, &MarkdownStyle::default());
+        assert_eq!(blocks.len(), 7, "{blocks:#?}");
+
+        let MarkdownBlock::Heading(1, heading) = &blocks[0] else {
+            panic!("expected heading, found {:?}", blocks[0]);
+        };
+        assert_eq!(texts(heading), ["Release notes"]);
+
+        let MarkdownBlock::Paragraph(paragraph) = &blocks[1] else {
+            panic!("expected paragraph, found {:?}", blocks[1]);
+        };
+        assert_eq!(
+            texts(paragraph),
+            ["Some ", "emphasis", ", ", "strong", " and ", "code", " with a ", "link", "."]
+        );
+        assert_eq!(paragraph[1].style, Style::Italic);
+        assert_eq!(paragraph[3].weight, Weight::BOLD);
+        assert!(matches!(paragraph[5].family, FontFamily::Monospace));
+        assert_eq!(paragraph[7].link.as_deref(), Some("https://example.com"));
+
+        let MarkdownBlock::List { start: None, items } = &blocks[2] else {
+            panic!("expected list, found {:?}", blocks[2]);
+        };
+        assert_eq!(items.len(), 2);
+        assert!(matches!(
+            &items[1][..],
+            [
+                MarkdownBlock::Paragraph(_),
+                MarkdownBlock::List { start: Some(1), .. }
+            ]
+        ));
+
+        assert!(matches!(&blocks[3], MarkdownBlock::Quote(v) if v.len() == 1));
+
+        let MarkdownBlock::CodeBlock { language, code } = &blocks[4] else {
+            panic!("expected code block, found {:?}", blocks[4]);
+        };
+        assert_eq!(language.as_deref(), Some("rust"));
+        assert_eq!(code, "fn main() {}");
+
+        let MarkdownBlock::Table { head, rows } = &blocks[5] else {
+            panic!("expected table, found {:?}", blocks[5]);
+        };
+        assert_eq!(head.len(), 2);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(texts(&rows[0][1]), ["2"]);
+
+        assert!(matches!(blocks[6], MarkdownBlock::Rule));
+    }
+}
@@ -6,6 +6,7 @@ mod future;
 pub mod image;
 /// Interactive widgets
 pub mod interactive;
+mod markdown;
 mod scroll;
 
 pub use animate::*;
@@ -16,6 +17,7 @@ pub use future::*;
 use futures_signals::signal::Mutable;
 pub use image::*;
 pub use interactive::{button::*, collapsible::*, drag::*, input::*, slider::*};
+pub use markdown::*;
 pub use scroll::ScrollArea;
 
 /// A widget is a description of a part of the Ui with the capability to mount itself into the world.
@@ -5,7 +5,7 @@ use glam::{vec2, BVec2, Mat4, Vec2, Vec2Swizzles};
 use super::{Float, Movable, Rectangle, Stack};
 use crate::{
     components::{item_align, max_size, min_size, offset, rect, transform, LayoutAlignment},
-    input::{interactive, on_scroll},
+    input::{interactive, on_scroll, CursorIcon},
     state::{StateStream, StateWrite},
     style::{
         default_corner_radius, scrollbar_size, surface_interactive_accent, Background, SizeExt,
@@ -206,6 +206,11 @@ impl Widget for Scrollbar {
                     .with_max_size(Unit::px2(40.0, 40.0))
                     .with_corner_radius(default_corner_radius()),
             )
+            .with_cursor_icon(if self.axis == Vec2::Y {
+                CursorIcon::NsResize
+            } else {
+                CursorIcon::EwResize
+            })
             .on_move(move |_, v| {
                 let size = size.get();
                 let outer_size = outer_size.get();
@@ -2,19 +2,22 @@ use glam::{BVec2, Vec2};
 use itertools::Itertools;
 use violet::{
     core::{
+        editor::{Diagnostic, TomlHighlighter},
         layout::Align,
         style::{
             base_colors::*, default_corner_radius, spacing_small, surface_danger, surface_primary,
             surface_warning, text_medium, SizeExt,
         },
+        text::CursorLocation,
         unit::Unit,
         widget::{
             bold, card, col,
             interactive::{
-                colorpicker::RgbColorPicker, dropdown::Dropdown, select_list::SelectList,
+                colorpicker::RgbColorPicker, dropdown::Dropdown, select_list::SelectList, FindBar,
+                InputMask, TextSearch,
             },
             label, pill, row, subtitle, title, Button, Checkbox, Collapsible, LabeledSlider, Radio,
-            Rectangle, ScrollArea, SignalWidget, Text, TextInput,
+            Rectangle, ScrollArea, SignalWidget, Text, TextArea, TextInput, TextOptions,
         },
         Edges, StateExt, Widget,
     },
@@ -25,6 +28,18 @@ use violet::{
 
 use crate::drag;
 
+const SAMPLE_TOML: &str = r#"[package]
+name = "violet"
+version = "0.1.0" # not yet published
+authors = ["Violet Contributors"]
+
+[dependencies]
+glam = { version = "0.25", features = ["bytemuck"] }
+description = """
+A multi-line
+string"""
+"#;
+
 fn dialog(name: impl Into<String>, content: impl 'static + Widget) -> impl Widget {
     card(Collapsible::new(title(name.into()), content))
 }
@@ -197,6 +212,7 @@ fn icons() -> impl Widget {
 
 fn inputs() -> impl Widget {
     let selection = Mutable::new(0);
+    let search = TextSearch::new();
 
     dialog(
         "Input",
@@ -204,6 +220,21 @@ fn inputs() -> impl Widget {
             LabeledSlider::new(Mutable::new(50), 0, 100).editable(true),
             LabeledSlider::new(Mutable::new(10.0), 0.1, 100.0).editable(true).logarithmic().precision(2),
             TextInput::new(Mutable::new("Text Input".to_string())),
+            TextInput::new(Mutable::new(String::new())).with_placeholder("Password").with_password(),
+            TextInput::new(Mutable::new(String::new()))
+                .with_placeholder("Phone number")
+                .with_options(TextOptions::default().with_mask(InputMask::new("+1 (###) ###-####"))),
+            FindBar::new(search.clone()).with_replace(true),
+            TextArea::new(Mutable::new(SAMPLE_TOML.to_string()))
+                .with_highlighter(TomlHighlighter::new())
+                .with_line_numbers(true)
+                .with_search(search)
+                .with_diagnostics(Mutable::new(vec![Diagnostic::info(
+                    CursorLocation::new(2, 10),
+                    CursorLocation::new(2, 17),
+                    "This version has not been published yet",
+                )]))
+                .with_exact_size(Unit::px2(320.0, 160.0)),
             row((
                 row((0..10)
                     .map(|i| Radio::new_value(selection.clone(), i))
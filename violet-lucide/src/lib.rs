@@ -21,7 +21,8 @@ use icons::LUCIDE_CHEVRON_RIGHT;
 use violet_core::{style::IconSet, text::Source};
 
 use crate::icons::{
-    LUCIDE_CHECK, LUCIDE_CHEVRON_DOWN, LUCIDE_CIRCLE_X, LUCIDE_LIGHTBULB, LUCIDE_TRIANGLE_ALERT,
+    LUCIDE_CHECK, LUCIDE_CHEVRON_DOWN, LUCIDE_CIRCLE_X, LUCIDE_EYE, LUCIDE_EYE_OFF,
+    LUCIDE_LIGHTBULB, LUCIDE_TRIANGLE_ALERT,
 };
 
 /// All icon glyphs.
@@ -43,5 +44,7 @@ pub fn icon_set() -> IconSet {
         ellipsis: LUCIDE_CHEVRON_DOWN.into(),
         spinner: ".".into(),
         search: LUCIDE_CHEVRON_RIGHT.into(),
+        show: LUCIDE_EYE.into(),
+        hide: LUCIDE_EYE_OFF.into(),
     }
 }
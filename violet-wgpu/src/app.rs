@@ -1,16 +1,17 @@
 use std::{mem, sync::Arc};
 
 use cosmic_text::fontdb::Source;
-use flax::{components::name, Entity, EntityBuilder, Schedule, World};
+use flax::{components::name, entity_ids, Entity, EntityBuilder, Query, Schedule, World};
 use glam::{vec2, Vec2};
+use itertools::Itertools;
 use parking_lot::Mutex;
 use tracing::info;
 use violet_core::{
     animation::update_animations,
-    assets::AssetCache,
+    assets::{Asset, AssetCache},
     components::{self, app_instance, rect},
     executor::Executor,
-    input::{request_focus_sender, InputState},
+    input::{ime_cursor_area_sender, request_focus_sender, CursorIcon, InputState},
     io::{self, Clipboard},
     layout::cache::LayoutUpdateEvent,
     style::{stylesheet, StylesheetOptions},
@@ -18,6 +19,7 @@ use violet_core::{
         compute_transform_system, hydrate_text, invalidate_cached_layout_system, layout_system,
         templating_system, transform_system,
     },
+    text::font::{load_font_sender, FontData},
     tweens,
     widget::interactive::overlay::OverlayStack,
     Frame, FutureEffect, Rect, Scope, Widget,
@@ -25,7 +27,7 @@ use violet_core::{
 use web_time::Instant;
 use winit::{
     application::ApplicationHandler,
-    dpi::{LogicalSize, PhysicalSize},
+    dpi::{LogicalPosition, LogicalSize, PhysicalSize},
     event::WindowEvent,
     event_loop::{ControlFlow, EventLoop},
     window::Window,
@@ -143,6 +145,8 @@ impl AppBuilder {
             renderer_config,
             title,
             resize_window,
+            ime_allowed: false,
+            cursor_icon: CursorIcon::Default,
         };
 
         #[cfg(not(target_arch = "wasm32"))]
@@ -172,6 +176,7 @@ pub struct AppInstance {
     window_size: PhysicalSize<u32>,
     pub input_state: InputState,
     text_system: Arc<Mutex<TextSystem>>,
+    load_font_rx: flume::Receiver<Asset<FontData>>,
     layout_changes_rx: flume::Receiver<(Entity, LayoutUpdateEvent)>,
     pub needs_update: bool,
 }
@@ -199,6 +204,12 @@ impl AppInstance {
         let (request_focus_tx, request_focus_rx) = flume::unbounded();
         frame.set_atom(request_focus_sender(), request_focus_tx);
 
+        let (ime_cursor_area_tx, ime_cursor_area_rx) = flume::unbounded();
+        frame.set_atom(ime_cursor_area_sender(), ime_cursor_area_tx);
+
+        let (load_font_tx, load_font_rx) = flume::unbounded();
+        frame.set_atom(load_font_sender(), load_font_tx);
+
         // Mount the root widget
         let root = frame.new_root(Canvas { stylesheet, root });
 
@@ -218,7 +229,7 @@ impl AppInstance {
             .with_system(layout_system(root, resize_canvas))
             .with_system(transform_system);
 
-        let input_state = InputState::new(root, Vec2::ZERO, request_focus_rx);
+        let input_state = InputState::new(root, Vec2::ZERO, request_focus_rx, ime_cursor_area_rx);
 
         let start_time = Instant::now();
 
@@ -233,6 +244,7 @@ impl AppInstance {
             window_size: Default::default(),
             input_state,
             text_system,
+            load_font_rx,
             layout_changes_rx,
             needs_update: false,
         }
@@ -278,6 +290,8 @@ impl AppInstance {
 
         self.executor.tick(&mut self.frame);
 
+        self.load_fonts();
+
         let elapsed = self.current_time - self.start_time;
         update_animations(&mut self.frame, elapsed, delta_time);
 
@@ -295,6 +309,34 @@ impl AppInstance {
         }
     }
 
+    /// Adds fonts loaded at runtime to the text system and lays out all text again
+    fn load_fonts(&mut self) {
+        let fonts = self.load_font_rx.try_iter().collect_vec();
+        if fonts.is_empty() {
+            return;
+        }
+
+        {
+            let mut text_system = self.text_system.lock();
+            for font in fonts {
+                text_system.load_font(Source::Binary(font.data().clone()));
+            }
+        }
+
+        // Modifying the text reshapes it and invalidates the cached layout
+        let ids = Query::new(entity_ids())
+            .with(components::text())
+            .borrow(&self.frame.world)
+            .iter()
+            .collect_vec();
+
+        for id in ids {
+            if let Ok(mut entity) = self.frame.world.entity_mut(id) {
+                entity.get_mut(components::text()).ok();
+            }
+        }
+    }
+
     pub fn is_minimized(&self) -> bool {
         self.window_size.width == 0 || self.window_size.height == 0
     }
@@ -350,6 +392,8 @@ struct WindowEventHandler {
     renderer_config: MainRendererConfig,
     title: String,
     resize_window: bool,
+    ime_allowed: bool,
+    cursor_icon: CursorIcon,
 }
 
 impl WindowEventHandler {
@@ -365,6 +409,41 @@ impl WindowEventHandler {
 
         Ok(())
     }
+
+    /// Forward input method requests of the focused widget to the window
+    fn update_ime(&mut self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+
+        let input_state = &mut self.instance.input_state;
+        let allowed = input_state.ime_allowed();
+        if allowed != self.ime_allowed {
+            window.set_ime_allowed(allowed);
+            self.ime_allowed = allowed;
+        }
+
+        if let Some(area) = input_state.update_ime_cursor_area() {
+            let size = area.size();
+            window.set_ime_cursor_area(
+                LogicalPosition::new(area.min.x, area.min.y),
+                LogicalSize::new(size.x, size.y),
+            );
+        }
+    }
+
+    /// Apply the cursor icon of the hovered widget to the window
+    fn update_cursor_icon(&mut self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+
+        let icon = self.instance.input_state.cursor_icon();
+        if icon != self.cursor_icon {
+            window.set_cursor(icon);
+            self.cursor_icon = icon;
+        }
+    }
 }
 
 impl ApplicationHandler for WindowEventHandler {
@@ -497,6 +576,26 @@ impl ApplicationHandler for WindowEventHandler {
                     event.text,
                 );
             }
+            WindowEvent::Ime(ime) => {
+                puffin::profile_scope!("Ime");
+                instance.input_state.on_ime(&mut instance.frame, ime);
+            }
+            WindowEvent::HoveredFile(path) => {
+                puffin::profile_scope!("HoveredFile");
+                instance
+                    .input_state
+                    .on_file_hovered(&mut instance.frame, path);
+            }
+            WindowEvent::HoveredFileCancelled => {
+                puffin::profile_scope!("HoveredFileCancelled");
+                instance
+                    .input_state
+                    .on_file_hover_cancelled(&mut instance.frame);
+            }
+            WindowEvent::DroppedFile(path) => {
+                puffin::profile_scope!("DroppedFile");
+                instance.input_state.on_file_dropped(path);
+            }
             WindowEvent::CursorMoved { position, .. } => {
                 puffin::profile_scope!("CursorMoved");
                 let position = position.to_logical::<f32>(instance.scale_factor);
@@ -543,6 +642,9 @@ impl ApplicationHandler for WindowEventHandler {
                 tracing::trace!(?event, ?window_id, "Window event")
             }
         }
+
+        self.update_ime();
+        self.update_cursor_icon();
     }
 
     fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
@@ -580,6 +682,10 @@ impl ApplicationHandler for WindowEventHandler {
                 .unwrap();
         }
 
+        self.instance
+            .input_state
+            .flush_dropped_files(&mut self.instance.frame);
+
         self.instance.update();
 
         if self.resize_window {
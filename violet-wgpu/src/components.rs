@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use cosmic_text::CacheKey;
 use flax::component;
 use violet_core::assets::Asset;
 use wgpu::TextureView;
@@ -23,6 +24,8 @@ component! {
 
     /// The mesh for a rendered shape
     pub(crate) text_mesh: Arc<MeshHandle>,
+    /// The glyphs drawn by the text mesh, which are kept in the font atlas
+    pub(crate) text_glyphs: Vec<CacheKey>,
 
     // pub model_matrix: glam::Mat4,
 
@@ -1,11 +1,9 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::HashMap;
 
-use cosmic_text::{CacheKey, Placement, SwashImage};
-use glam::{uvec2, vec3, UVec2};
-use guillotiere::{size2, AtlasAllocator};
-use image::{ImageBuffer, Luma};
-use violet_core::assets::AssetCache;
-use wgpu::{util::DeviceExt, Extent3d, TextureDescriptor, TextureDimension, TextureUsages};
+use cosmic_text::{CacheKey, Placement, SwashContent, SwashImage};
+use glam::{uvec2, UVec2};
+use guillotiere::{size2, AllocId, AtlasAllocator};
+use wgpu::{Extent3d, TextureDescriptor, TextureDimension, TextureUsages};
 
 use crate::{graphics::texture::Texture, text::TextSystem, Gpu};
 
@@ -14,196 +12,388 @@ use crate::{graphics::texture::Texture, text::TextSystem, Gpu};
 pub struct GlyphLocation {
     pub min: UVec2,
     pub max: UVec2,
+    /// The glyph carries its own color, such as emoji, and should not be tinted by the text color
+    pub is_color: bool,
 }
 
+/// The atlas texture format.
+///
+/// Mask glyphs are stored as white with their coverage in the alpha channel, while color glyphs
+/// are stored as is. This allows both kinds to be drawn by multiplying with the vertex color.
+const ATLAS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+const INITIAL_SIZE: i32 = 256;
+const GLYPH_PADDING: i32 = 2;
+
+struct CachedGlyph {
+    placement: Placement,
+    location: GlyphLocation,
+    /// `None` for glyphs which could not be rasterized and occupy no space
+    alloc: Option<AllocId>,
+    /// The last frame the glyph was drawn or used by an existing mesh
+    last_used: u64,
+}
+
+/// Caches rasterized glyphs in a texture atlas.
+///
+/// Glyphs are rasterized and uploaded individually as they are first requested. When the atlas is
+/// full the least recently used glyphs which are not used by any mesh this frame are evicted, and
+/// if that is not enough the texture is grown.
+///
+/// As existing meshes keep referring to their glyphs without requesting them again, they must be
+/// marked as used each frame through [`Self::mark_used`] to not be evicted.
 pub(crate) struct FontAtlas {
     /// The backing GPU texture of the rasterized fonts
-    pub texture: Texture,
-    pub glyphs: BTreeMap<CacheKey, (Placement, GlyphLocation)>,
+    texture: Texture,
+    allocator: AtlasAllocator,
+    glyphs: HashMap<CacheKey, CachedGlyph>,
+    /// A fully covered region, used to draw decorations such as underlines
+    solid: GlyphLocation,
+    frame: u64,
 }
 
 impl FontAtlas {
-    pub(crate) fn empty(gpu: &Gpu) -> Self {
-        let texture = gpu.device.create_texture(&TextureDescriptor {
-            label: Some("FontAtlas"),
-            size: Extent3d {
-                width: 1,
-                height: 1,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: TextureDimension::D2,
-            format: wgpu::TextureFormat::R8Unorm,
-            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
+    pub(crate) fn new(gpu: &Gpu) -> Self {
+        let mut allocator = AtlasAllocator::new(size2(INITIAL_SIZE, INITIAL_SIZE));
+
+        let solid = allocator
+            .allocate(size2(4, 4))
+            .expect("Atlas has space for the solid region");
+
+        let solid = GlyphLocation {
+            min: uvec2(
+                solid.rectangle.min.x as u32 + 1,
+                solid.rectangle.min.y as u32 + 1,
+            ),
+            max: uvec2(
+                solid.rectangle.max.x as u32 - 1,
+                solid.rectangle.max.y as u32 - 1,
+            ),
+            is_color: false,
+        };
+
+        let texture = create_texture(gpu, uvec2(INITIAL_SIZE as u32, INITIAL_SIZE as u32));
+
+        let size = solid.max - solid.min;
+        write_region(
+            gpu,
+            &texture,
+            solid.min,
+            size,
+            &vec![255; (size.x * size.y * 4) as usize],
+        );
 
         Self {
-            texture: Texture::from_texture(texture),
-            glyphs: Default::default(),
+            texture,
+            allocator,
+            glyphs: HashMap::new(),
+            solid,
+            frame: 0,
         }
     }
 
-    pub(crate) fn new(
-        _assets: &AssetCache,
-        gpu: &Gpu,
-        text_system: &mut TextSystem,
-        glyphs: impl IntoIterator<Item = CacheKey>,
-    ) -> anyhow::Result<Self> {
-        let mut atlas = AtlasAllocator::new(size2(128, 128));
+    pub(crate) fn texture(&self) -> &Texture {
+        &self.texture
+    }
 
-        let glyphs = glyphs.into_iter().collect::<BTreeSet<_>>();
+    pub(crate) fn solid(&self) -> GlyphLocation {
+        self.solid
+    }
 
-        // let images = glyphs
-        //     .iter()
-        //     .map(|&glyph| {
-        //         let image = swash_cache.get_image_uncached(font_system, glyph).unwrap();
+    pub(crate) fn size(&self) -> Extent3d {
+        self.texture.size()
+    }
 
-        //         (glyph, image)
-        //     })
-        //     .collect_vec();
+    /// Advances the frame used to determine which glyphs are in use
+    pub(crate) fn begin_frame(&mut self) {
+        self.frame += 1;
+    }
 
-        let mut images = Vec::new();
-        let glyphs = glyphs
-            .iter()
-            .map(|&glyph| {
-                let Some(image) = text_system
-                    .swash_cache
-                    .get_image_uncached(&mut text_system.font_system, glyph)
-                else {
-                    // tracing::error!(?glyph, "failed to load glyph");
-                    return (
-                        glyph,
-                        (
-                            Placement {
-                                left: 0,
-                                top: 0,
-                                width: 10,
-                                height: 10,
-                            },
-                            GlyphLocation {
-                                min: UVec2::ZERO,
-                                max: UVec2::ONE,
-                            },
-                        ),
-                    );
-                };
-                // let index = font.lookup_glyph_index(c);
-
-                let metrics = image.placement;
-                let padding = 2;
-
-                let requested_size = size2(
-                    metrics.width as i32 + padding * 2,
-                    metrics.height as i32 + padding * 2,
-                );
-                let v = loop {
-                    if let Some(v) = atlas.allocate(requested_size) {
-                        break v;
-                    }
+    /// Marks the glyphs of an existing mesh as used this frame, keeping them in the atlas
+    pub(crate) fn mark_used<'a>(&mut self, glyphs: impl IntoIterator<Item = &'a CacheKey>) {
+        for glyph in glyphs {
+            if let Some(entry) = self.glyphs.get_mut(glyph) {
+                entry.last_used = self.frame;
+            }
+        }
+    }
 
-                    atlas.grow(atlas.size() * 2)
-                };
+    /// Returns a glyph in the atlas and marks it as used this frame
+    pub(crate) fn get(&mut self, glyph: CacheKey) -> Option<(Placement, GlyphLocation)> {
+        let entry = self.glyphs.get_mut(&glyph)?;
+        entry.last_used = self.frame;
+        Some((entry.placement, entry.location))
+    }
 
-                let min = uvec2(
-                    (v.rectangle.min.x + padding) as u32,
-                    (v.rectangle.min.y + padding) as u32,
-                );
+    /// Rasterizes and uploads the given glyphs.
+    ///
+    /// Returns `true` if the texture was replaced due to growing, which requires a new bind group.
+    pub(crate) fn insert(
+        &mut self,
+        gpu: &Gpu,
+        text_system: &mut TextSystem,
+        glyphs: impl IntoIterator<Item = CacheKey>,
+    ) -> anyhow::Result<bool> {
+        puffin::profile_function!();
+        let mut grown = false;
+
+        for glyph in glyphs {
+            if self.glyphs.contains_key(&glyph) {
+                continue;
+            }
 
-                let max = uvec2(
-                    (v.rectangle.max.x - padding) as u32,
-                    (v.rectangle.max.y - padding) as u32,
+            let Some(image) = text_system
+                .swash_cache
+                .get_image_uncached(&mut text_system.font_system, glyph)
+            else {
+                self.glyphs.insert(
+                    glyph,
+                    CachedGlyph {
+                        placement: Placement {
+                            left: 0,
+                            top: 0,
+                            width: 10,
+                            height: 10,
+                        },
+                        location: GlyphLocation {
+                            min: UVec2::ZERO,
+                            max: UVec2::ONE,
+                            is_color: false,
+                        },
+                        alloc: None,
+                        last_used: self.frame,
+                    },
                 );
+                continue;
+            };
 
-                let loc = GlyphLocation { min, max };
-                images.push((glyph, image, loc));
-                (glyph, (metrics, loc))
-            })
-            .collect::<BTreeMap<_, _>>();
+            let placement = image.placement;
+            let requested_size = size2(
+                placement.width as i32 + GLYPH_PADDING * 2,
+                placement.height as i32 + GLYPH_PADDING * 2,
+            );
 
-        let size = atlas.size();
-        let size = uvec2(size.width as _, size.height as _);
-        let mut image = ImageBuffer::from_pixel(size.x, size.y, Luma([0]));
+            let allocation = loop {
+                if let Some(v) = self.allocator.allocate(requested_size) {
+                    break v;
+                }
+
+                if !self.evict_unused() {
+                    self.grow(gpu)?;
+                    grown = true;
+                }
+            };
 
-        images.iter().for_each(|(_, src_image, loc)| {
-            if src_image.placement.width > 0 {
+            let rect = allocation.rectangle;
+            let padded_size = uvec2(rect.width() as u32, rect.height() as u32);
+
+            // Upload the padding as well to clear any previously evicted glyph
+            let mut data = vec![0; (padded_size.x * padded_size.y * 4) as usize];
+            if placement.width > 0 && placement.height > 0 {
                 blit_to_image(
-                    src_image,
-                    &mut image,
-                    loc.min.x as i32,
-                    loc.min.y as i32,
-                    size.x,
+                    &image,
+                    &mut data,
+                    GLYPH_PADDING,
+                    GLYPH_PADDING,
+                    padded_size.x,
                 );
             }
-        });
-
-        let texture = gpu.device.create_texture_with_data(
-            &gpu.queue,
-            &TextureDescriptor {
-                label: Some("FontAtlas"),
-                size: Extent3d {
-                    width: size.x,
-                    height: size.y,
-                    depth_or_array_layers: 1,
+
+            let origin = uvec2(rect.min.x as u32, rect.min.y as u32);
+            write_region(gpu, &self.texture, origin, padded_size, &data);
+
+            let padding = UVec2::splat(GLYPH_PADDING as u32);
+            self.glyphs.insert(
+                glyph,
+                CachedGlyph {
+                    placement,
+                    location: GlyphLocation {
+                        min: origin + padding,
+                        max: origin + padded_size - padding,
+                        is_color: image.content == SwashContent::Color,
+                    },
+                    alloc: Some(allocation.id),
+                    last_used: self.frame,
                 },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: TextureDimension::D2,
-                format: wgpu::TextureFormat::R8Unorm,
-                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
-                view_formats: &[],
-            },
-            wgpu::util::TextureDataOrder::LayerMajor,
-            &image,
-        );
+            );
+        }
 
-        Ok(Self {
-            texture: Texture::from_texture(texture),
-            glyphs,
-        })
+        Ok(grown)
     }
 
-    pub(crate) fn size(&self) -> Extent3d {
-        self.texture.size()
+    /// Evicts the least recently used glyphs which are not used by any mesh this frame.
+    ///
+    /// Returns `false` if there was nothing to evict.
+    fn evict_unused(&mut self) -> bool {
+        let mut unused = self
+            .glyphs
+            .iter()
+            .filter(|(_, v)| v.last_used < self.frame)
+            .map(|(&k, v)| (v.last_used, k))
+            .collect::<Vec<_>>();
+
+        if unused.is_empty() {
+            return false;
+        }
+
+        // Evict the older half at once to avoid repeatedly scanning when filling up the atlas
+        unused.sort_unstable_by_key(|&(last_used, _)| last_used);
+        let count = unused.len().div_ceil(2);
+
+        tracing::debug!(count, "Evicting glyphs from the atlas");
+        for &(_, key) in &unused[..count] {
+            if let Some(alloc) = self.glyphs.remove(&key).and_then(|v| v.alloc) {
+                self.allocator.deallocate(alloc);
+            }
+        }
+
+        true
+    }
+
+    /// Doubles the size of the atlas, retaining the existing glyphs
+    fn grow(&mut self, gpu: &Gpu) -> anyhow::Result<()> {
+        let new_size = self.allocator.size() * 2;
+        let max_size = gpu.device.limits().max_texture_dimension_2d as i32;
+        if new_size.width > max_size || new_size.height > max_size {
+            anyhow::bail!("Font atlas can not grow beyond {max_size}x{max_size}");
+        }
+
+        tracing::debug!(?new_size, "Growing font atlas");
+        self.allocator.grow(new_size);
+
+        let texture = create_texture(gpu, uvec2(new_size.width as u32, new_size.height as u32));
+
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("FontAtlas::grow"),
+            });
+
+        encoder.copy_texture_to_texture(
+            self.texture.as_image_copy(),
+            texture.as_image_copy(),
+            self.texture.size(),
+        );
+
+        gpu.queue.submit([encoder.finish()]);
+
+        // Meshes which were already generated keep drawing from the previous texture through
+        // their bind group, so it is fine to drop our reference to it
+        self.texture = texture;
+        Ok(())
     }
 }
 
+fn create_texture(gpu: &Gpu, size: UVec2) -> Texture {
+    let texture = gpu.device.create_texture(&TextureDescriptor {
+        label: Some("FontAtlas"),
+        size: Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: ATLAS_FORMAT,
+        usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+
+    Texture::from_texture(texture)
+}
+
+fn write_region(gpu: &Gpu, texture: &Texture, origin: UVec2, size: UVec2, data: &[u8]) {
+    gpu.queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d {
+                x: origin.x,
+                y: origin.y,
+                z: 0,
+            },
+            aspect: wgpu::TextureAspect::All,
+        },
+        data,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(size.x * 4),
+            rows_per_image: Some(size.y),
+        },
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+/// Copies a rasterized glyph into an rgba atlas image at `x, y`
 pub fn blit_to_image(src: &SwashImage, dst: &mut [u8], x: i32, y: i32, dst_width: u32) {
-    match src.content {
-        cosmic_text::SwashContent::Mask => {
-            for (row_index, row) in src
-                .data
-                .chunks_exact(src.placement.width as usize)
-                .enumerate()
-            {
-                let dst_index = x as usize + (y as usize + row_index) * dst_width as usize;
-
-                dst[dst_index..(dst_index + src.placement.width as usize)].copy_from_slice(row);
+    let width = src.placement.width as usize;
+    let channels = match src.content {
+        SwashContent::Mask => 1,
+        SwashContent::SubpixelMask | SwashContent::Color => 4,
+    };
+
+    for (row_index, row) in src.data.chunks_exact(width * channels).enumerate() {
+        let dst_index = (x as usize + (y as usize + row_index) * dst_width as usize) * 4;
+        let dst_row = &mut dst[dst_index..dst_index + width * 4];
+
+        match src.content {
+            SwashContent::Mask => {
+                for (dst, &coverage) in dst_row.chunks_exact_mut(4).zip(row) {
+                    dst.copy_from_slice(&[255, 255, 255, coverage]);
+                }
             }
-        }
-        cosmic_text::SwashContent::SubpixelMask => todo!(),
-        cosmic_text::SwashContent::Color => {
-            for (row_index, row) in src
-                .data
-                .chunks_exact(src.placement.width as usize * 4)
-                .enumerate()
-            {
-                let dst_index = x as usize + (y as usize + row_index) * dst_width as usize;
-
-                for (pixel_index, pixel) in row.chunks_exact(4).enumerate() {
-                    let dst_index = dst_index + pixel_index;
-
-                    let l = vec3(
-                        pixel[0] as f32 / 255.0,
-                        pixel[1] as f32 / 255.0,
-                        pixel[2] as f32 / 255.0,
-                    )
-                    .dot(vec3(0.2126, 0.7152, 0.0722));
-
-                    dst[dst_index] = ((l * pixel[3] as f32 / 255.0) * 255.0) as u8;
+            SwashContent::SubpixelMask => {
+                // Subpixel rendering is not supported, so collapse it to a single coverage value
+                for (dst, pixel) in dst_row.chunks_exact_mut(4).zip(row.chunks_exact(4)) {
+                    let coverage = pixel[..3].iter().map(|&v| v as u32).sum::<u32>() / 3;
+                    dst.copy_from_slice(&[255, 255, 255, coverage as u8]);
                 }
             }
+            SwashContent::Color => dst_row.copy_from_slice(row),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn swash_image(content: SwashContent, width: u32, height: u32, data: Vec<u8>) -> SwashImage {
+        let mut image = SwashImage::new();
+        image.content = content;
+        image.placement = Placement {
+            left: 0,
+            top: 0,
+            width,
+            height,
+        };
+        image.data = data;
+        image
+    }
+
+    #[test]
+    fn blit_mask() {
+        let src = swash_image(SwashContent::Mask, 2, 1, vec![10, 20]);
+        let mut dst = vec![0; 3 * 2 * 4];
+
+        blit_to_image(&src, &mut dst, 1, 1, 3);
+
+        assert_eq!(&dst[..16], &[0; 16]);
+        assert_eq!(&dst[16..], &[255, 255, 255, 10, 255, 255, 255, 20]);
+    }
+
+    #[test]
+    fn blit_color() {
+        let src = swash_image(SwashContent::Color, 1, 2, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        let mut dst = vec![0; 2 * 2 * 4];
+
+        blit_to_image(&src, &mut dst, 1, 0, 2);
+
+        assert_eq!(dst, [0, 0, 0, 0, 1, 2, 3, 4, 0, 0, 0, 0, 5, 6, 7, 8]);
+    }
+}
@@ -1,24 +1,23 @@
-use std::sync::Arc;
+use std::{f32::consts::TAU, sync::Arc};
 
-use cosmic_text::{Buffer, CacheKey, Metrics, Placement};
+use cosmic_text::{Buffer, CacheKey, LayoutRun, Placement};
 use flax::{
     entity_ids,
     fetch::{Modified, TransformFetch},
     filter::{self, All, Cmp, With},
-    CommandBuffer, Component, ComponentMut, EntityIds, Fetch, FetchExt, Opt, OptOr, Query,
+    CommandBuffer, Component, ComponentMut, Entity, EntityIds, Fetch, FetchExt, Opt, OptOr, Query,
 };
 use glam::{vec2, vec3, Mat4, Quat, Vec2, Vec3, Vec4};
 use itertools::Itertools;
 use palette::Srgba;
 use parking_lot::Mutex;
 use violet_core::{
-    assets::AssetCache,
     components::{
         color, computed_visible, draw_shape, font_size, layout_bounds, rect, screen_transform, text,
     },
     shape::shape_text,
     stored::{self, Handle},
-    text::TextSegment,
+    text::{TextSegment, UnderlineStyle},
     Frame, Rect,
 };
 use wgpu::{BindGroup, BindGroupLayout, Sampler, SamplerDescriptor, ShaderStages, TextureFormat};
@@ -26,14 +25,14 @@ use wgpu::{BindGroup, BindGroupLayout, Sampler, SamplerDescriptor, ShaderStages,
 use super::{DrawCommand, ObjectData, RendererContext, RendererStore};
 use crate::{
     components,
-    components::{draw_cmd, object_data, text_buffer_state, text_mesh},
+    components::{draw_cmd, object_data, text_buffer_state, text_glyphs, text_mesh},
     font::{FontAtlas, GlyphLocation},
     graphics::{
         shader::ShaderDesc, BindGroupBuilder, BindGroupLayoutBuilder, Shader, Vertex, VertexDesc,
     },
     mesh_buffer::{MeshBuffer, MeshHandle},
     renderer::srgba_to_vec4,
-    text::{TextBufferState, TextSystem},
+    text::{Decoration, TextBufferState, TextSystem},
     Gpu,
 };
 
@@ -73,60 +72,68 @@ impl FontRasterizer {
         text_layout: BindGroupLayout,
         store: &mut RendererStore,
     ) -> Self {
-        let atlas = FontAtlas::empty(gpu);
-
-        let bind_group = BindGroupBuilder::new("TextRenderer::bind_group")
-            .bind_sampler(&sampler)
-            .bind_texture(&atlas.texture.view(&Default::default()))
-            .build(gpu, &text_layout);
+        let atlas = FontAtlas::new(gpu);
+        let bind_group = Self::create_bind_group(gpu, &sampler, &text_layout, &atlas, store);
 
         Self {
-            rasterized: RasterizedFont {
-                atlas,
-                bind_group: store.bind_groups.insert(bind_group),
-            },
+            rasterized: RasterizedFont { atlas, bind_group },
             sampler,
             text_layout,
         }
     }
 
+    fn create_bind_group(
+        gpu: &Gpu,
+        sampler: &Sampler,
+        text_layout: &BindGroupLayout,
+        atlas: &FontAtlas,
+        store: &mut RendererStore,
+    ) -> Handle<BindGroup> {
+        store.bind_groups.insert(
+            BindGroupBuilder::new("TextRenderer::bind_group")
+                .bind_sampler(sampler)
+                .bind_texture(&atlas.texture().view(&Default::default()))
+                .build(gpu, text_layout),
+        )
+    }
+
     pub fn add_glyphs(
         &mut self,
-        assets: &AssetCache,
         gpu: &Gpu,
         text_system: &mut TextSystem,
         new_glyphs: &[CacheKey],
         store: &mut RendererStore,
     ) -> anyhow::Result<()> {
         puffin::profile_function!();
-        let glyphs = self
+        let grown = self
             .rasterized
             .atlas
-            .glyphs
-            .keys()
-            .chain(new_glyphs)
-            .copied();
-
-        let atlas = FontAtlas::new(assets, gpu, text_system, glyphs)?;
-
-        let bind_group = store.bind_groups.insert(
-            BindGroupBuilder::new("TextRenderer::bind_group")
-                .bind_sampler(&self.sampler)
-                .bind_texture(&atlas.texture.view(&Default::default()))
-                .build(gpu, &self.text_layout),
-        );
-
-        self.rasterized = RasterizedFont { atlas, bind_group };
+            .insert(gpu, text_system, new_glyphs.iter().copied())?;
+
+        if grown {
+            self.rasterized.bind_group = Self::create_bind_group(
+                gpu,
+                &self.sampler,
+                &self.text_layout,
+                &self.rasterized.atlas,
+                store,
+            );
+        }
 
         Ok(())
     }
 
-    pub fn clear(&mut self) {
-        self.rasterized.atlas.glyphs.clear();
+    /// Discards all rasterized glyphs, such as when the scale factor changes
+    pub fn clear(&mut self, gpu: &Gpu, store: &mut RendererStore) {
+        let atlas = FontAtlas::new(gpu);
+        let bind_group =
+            Self::create_bind_group(gpu, &self.sampler, &self.text_layout, &atlas, store);
+
+        self.rasterized = RasterizedFont { atlas, bind_group };
     }
 
-    pub fn get_glyph(&self, glyph: CacheKey) -> Option<&(Placement, GlyphLocation)> {
-        self.rasterized.atlas.glyphs.get(&glyph)
+    pub fn get_glyph(&mut self, glyph: CacheKey) -> Option<(Placement, GlyphLocation)> {
+        self.rasterized.atlas.get(glyph)
     }
 }
 
@@ -183,19 +190,20 @@ impl MeshGenerator {
         &mut self,
         gpu: &Gpu,
         mesh_buffer: &mut MeshBuffer,
-        assets: &AssetCache,
         text_system: &mut TextSystem,
         buffer: &mut Buffer,
+        decorations: &[Decoration],
         mesh: &mut Arc<MeshHandle>,
         store: &mut RendererStore,
         scale_factor: f64,
-    ) -> u32 {
+    ) -> (u32, Vec<CacheKey>) {
         puffin::profile_function!();
-        // let rasterized = self
-        //     .rasterizer
-        //     .get(ctx, assets, font.clone(), font_size, text);
 
         let mut vertices = Vec::new();
+        let mut glyphs = Vec::new();
+        // Drawn behind and in front of the glyphs respectively
+        let mut highlights = Vec::new();
+        let mut lines = Vec::new();
 
         // let color = cosmic_text::Color::rgb(0xFF, 0xFF, 0xFF);
 
@@ -203,6 +211,38 @@ impl MeshGenerator {
         let mut missing = Vec::new();
         loop {
             for run in buffer.layout_runs() {
+                for (metadata, glyphs) in &run.glyphs.iter().chunk_by(|v| v.metadata) {
+                    let Some(decoration) = decorations.get(metadata).filter(|v| !v.is_empty())
+                    else {
+                        continue;
+                    };
+
+                    let solid = self.rasterizer.rasterized.atlas.solid();
+                    let atlas_size = self.rasterizer.rasterized.atlas.size();
+                    let atlas_size = vec2(atlas_size.width as f32, atlas_size.height as f32);
+                    let uv = (solid.min.as_vec2() + solid.max.as_vec2()) / 2.0 / atlas_size;
+
+                    let (left, right, font_size) =
+                        glyphs.fold((f32::MAX, f32::MIN, 0.0f32), |(l, r, fs), glyph| {
+                            (
+                                l.min(glyph.x),
+                                r.max(glyph.x + glyph.w),
+                                fs.max(glyph.font_size),
+                            )
+                        });
+
+                    decoration_mesh(
+                        &mut highlights,
+                        &mut lines,
+                        decoration,
+                        &run,
+                        vec2(left, right),
+                        font_size,
+                        uv,
+                        sf,
+                    );
+                }
+
                 for glyph in run.glyphs.iter() {
                     let physical_glyph = glyph.physical((0., 0.), 1.0);
                     let Some((placement, loc)) =
@@ -212,6 +252,8 @@ impl MeshGenerator {
                         continue;
                     };
 
+                    glyphs.push(physical_glyph.cache_key);
+
                     let atlas_size = self.rasterizer.rasterized.atlas.size();
                     let atlas_size = vec2(atlas_size.width as f32, atlas_size.height as f32);
 
@@ -220,13 +262,18 @@ impl MeshGenerator {
 
                     let x = placement.left as f32 + physical_glyph.x as f32;
                     let y = run.line_y - placement.top as f32 + physical_glyph.y as f32;
-                    let color = glyph
+                    let mut color = glyph
                         .color_opt
                         .map(|v| {
                             srgba_to_vec4(Srgba::new(v.r(), v.g(), v.b(), v.a()).into_format())
                         })
                         .unwrap_or(Vec4::ONE);
 
+                    // Color glyphs keep their own colors and only inherit the opacity
+                    if loc.is_color {
+                        color = Vec4::ONE.with_w(color.w);
+                    }
+
                     vertices.extend_from_slice(&[
                         // Bottom left
                         Vertex::new(
@@ -254,11 +301,15 @@ impl MeshGenerator {
             }
             tracing::debug!(?missing, "Adding missing glyphs");
             vertices.clear();
+            glyphs.clear();
+            highlights.clear();
+            lines.clear();
             self.rasterizer
-                .add_glyphs(assets, gpu, text_system, &missing, store)
+                .add_glyphs(gpu, text_system, &missing, store)
                 .unwrap();
             missing.clear();
         }
+        let vertices = [highlights, vertices, lines].concat();
         let glyph_count = vertices.len() / 4;
 
         let indices = (0..)
@@ -275,10 +326,97 @@ impl MeshGenerator {
             *mesh = Arc::new(mesh_buffer.insert(gpu, &vertices, &indices));
         }
 
-        indices.len() as u32
+        (
+            indices.len() as u32,
+            glyphs.into_iter().unique().collect_vec(),
+        )
+    }
+}
+
+/// Generates quads for the decorations of the glyphs between `span.x` and `span.y` in a run
+#[allow(clippy::too_many_arguments)]
+fn decoration_mesh(
+    highlights: &mut Vec<Vertex>,
+    lines: &mut Vec<Vertex>,
+    decoration: &Decoration,
+    run: &LayoutRun,
+    span: Vec2,
+    font_size: f32,
+    uv: Vec2,
+    sf: f32,
+) {
+    let (left, right) = (span.x, span.y);
+    let thickness = (font_size / 16.0).round().max(1.0);
+    let color = srgba_to_vec4(decoration.color);
+
+    if let Some(highlight) = decoration.highlight {
+        push_quad(
+            highlights,
+            vec2(left, run.line_top),
+            vec2(right, run.line_top + run.line_height),
+            srgba_to_vec4(highlight),
+            uv,
+            sf,
+        );
+    }
+
+    if let Some(underline) = decoration.underline {
+        let y = run.line_y + (font_size * 0.1).max(thickness);
+
+        match underline {
+            UnderlineStyle::Solid => push_quad(
+                lines,
+                vec2(left, y),
+                vec2(right, y + thickness),
+                color,
+                uv,
+                sf,
+            ),
+            UnderlineStyle::Dotted => {
+                let mut x = left;
+                while x < right {
+                    let end = (x + thickness).min(right);
+                    push_quad(lines, vec2(x, y), vec2(end, y + thickness), color, uv, sf);
+                    x += thickness * 3.0;
+                }
+            }
+            UnderlineStyle::Wavy => {
+                let wavelength = thickness * 6.0;
+                let amplitude = thickness;
+
+                let mut x = left;
+                while x < right {
+                    let end = (x + thickness).min(right);
+                    let y = y + amplitude * (TAU * (x - left) / wavelength).sin();
+                    push_quad(lines, vec2(x, y), vec2(end, y + thickness), color, uv, sf);
+                    x += thickness * 0.5;
+                }
+            }
+        }
+    }
+
+    if decoration.strikethrough {
+        let y = run.line_y - font_size * 0.3;
+        push_quad(
+            lines,
+            vec2(left, y),
+            vec2(right, y + thickness),
+            color,
+            uv,
+            sf,
+        );
     }
 }
 
+fn push_quad(vertices: &mut Vec<Vertex>, min: Vec2, max: Vec2, color: Vec4, uv: Vec2, sf: f32) {
+    vertices.extend_from_slice(&[
+        Vertex::new(vec3(min.x, max.y, 0.0) / sf, color, uv),
+        Vertex::new(vec3(max.x, max.y, 0.0) / sf, color, uv),
+        Vertex::new(vec3(max.x, min.y, 0.0) / sf, color, uv),
+        Vertex::new(vec3(min.x, min.y, 0.0) / sf, color, uv),
+    ]);
+}
+
 #[derive(Fetch)]
 #[fetch(transforms = [Modified])]
 /// Query text entities in the world and allocate them a slot in the mesh and atlas
@@ -323,7 +461,10 @@ pub(crate) struct TextRenderer {
 
     object_query: Query<ObjectQuery, (All, With)>,
     mesh_query: Query<<TextMeshQuery as TransformFetch<Modified>>::Output, All>,
+    glyph_query: Query<Component<Vec<CacheKey>>>,
     scale_factor: f64,
+    /// The rasterized glyphs are discarded on the next update, such as due to scale changes
+    clear_atlas: bool,
 }
 
 impl TextRenderer {
@@ -346,8 +487,10 @@ impl TextRenderer {
             object_query: Query::new(ObjectQuery::new()).with(text()),
             mesh_generator,
             mesh_query: Query::new(TextMeshQuery::new().modified()),
+            glyph_query: Query::new(text_glyphs()),
             text_system,
             scale_factor: 1.0,
+            clear_atlas: false,
         }
     }
 
@@ -362,64 +505,44 @@ impl TextRenderer {
 
         let text_system = &mut *self.text_system.lock();
 
+        if self.clear_atlas {
+            self.mesh_generator.rasterizer.clear(&ctx.gpu, store);
+            self.clear_atlas = false;
+        }
+
+        self.mesh_generator
+            .rasterizer
+            .rasterized
+            .atlas
+            .begin_frame();
+
+        // Meshes which are not regenerated this frame keep referring to their glyphs
+        let atlas = &mut self.mesh_generator.rasterizer.rasterized.atlas;
+        for glyphs in self.glyph_query.borrow(&frame.world).iter() {
+            atlas.mark_used(glyphs);
+        }
+
         (self.mesh_query.borrow(&frame.world))
             .iter()
             .collect_vec()
             .into_iter()
             .rev()
             .for_each(|item| {
-                let _span = tracing::debug_span!("update_mesh").entered();
-
-                // Update intrinsic sizes
-                {
-                    let mut buffer = item.state.buffer.borrow_with(&mut text_system.font_system);
-
-                    let sf = self.scale_factor as f32;
-                    buffer.set_metrics_and_size(
-                        Metrics {
-                            font_size: item.font_size * sf,
-                            line_height: item.font_size * sf,
-                        },
-                        Some((item.layout_bounds.x + 5.0) * sf),
-                        Some((item.layout_bounds.y + 5.0) * sf),
-                    );
-                    // buffer.set_size(item.layout_bounds.x + 5.0, item.layout_bounds.y + 5.0);
-
-                    buffer.shape_until_scroll(true);
-                }
-
-                let mut new_mesh = None;
-
-                let text_mesh = match item.text_mesh {
-                    Some(v) => v,
-                    None => new_mesh.insert(Arc::new(ctx.mesh_buffer.allocate(&ctx.gpu, 0, 0))),
-                };
-
-                let index_count = self.mesh_generator.update_mesh(
-                    &ctx.gpu,
-                    &mut ctx.mesh_buffer,
-                    &frame.assets,
+                update_text_mesh(
+                    &mut self.mesh_generator,
+                    ctx,
                     text_system,
-                    &mut item.state.buffer,
-                    text_mesh,
                     store,
+                    &mut cmd,
                     self.scale_factor,
-                );
-
-                cmd.set(
-                    item.id,
-                    draw_cmd(),
-                    DrawCommand {
-                        bind_group: self.mesh_generator.rasterizer.rasterized.bind_group.clone(),
-                        shader: self.mesh_generator.shader.clone(),
-                        mesh: text_mesh.clone(),
-                        index_count,
+                    TextMeshItem {
+                        id: item.id,
+                        text_mesh: item.text_mesh,
+                        state: item.state,
+                        layout_bounds: *item.layout_bounds,
+                        font_size: *item.font_size,
                     },
                 );
-
-                if let Some(text_mesh) = new_mesh {
-                    cmd.set(item.id, components::text_mesh(), text_mesh);
-                }
             });
 
         cmd.apply(&mut frame.world).unwrap();
@@ -455,8 +578,78 @@ impl TextRenderer {
         scale_factor: f64,
     ) {
         if self.scale_factor != scale_factor {
-            self.mesh_generator.rasterizer.clear();
+            self.clear_atlas = true;
         }
         self.scale_factor = scale_factor;
     }
 }
+
+struct TextMeshItem<'a> {
+    id: Entity,
+    text_mesh: Option<&'a mut Arc<MeshHandle>>,
+    state: &'a mut TextBufferState,
+    layout_bounds: Vec2,
+    font_size: f32,
+}
+
+fn update_text_mesh(
+    mesh_generator: &mut MeshGenerator,
+    ctx: &mut RendererContext,
+    text_system: &mut TextSystem,
+    store: &mut RendererStore,
+    cmd: &mut CommandBuffer,
+    scale_factor: f64,
+    item: TextMeshItem,
+) {
+    let _span = tracing::debug_span!("update_mesh").entered();
+
+    // Update intrinsic sizes
+    {
+        let sf = scale_factor as f32;
+        let metrics = item.state.metrics(item.font_size * sf);
+        let mut buffer = item.state.buffer.borrow_with(&mut text_system.font_system);
+
+        buffer.set_metrics_and_size(
+            metrics,
+            Some((item.layout_bounds.x + 5.0) * sf),
+            Some((item.layout_bounds.y + 5.0) * sf),
+        );
+
+        buffer.shape_until_scroll(true);
+    }
+
+    let mut new_mesh = None;
+
+    let text_mesh = match item.text_mesh {
+        Some(v) => v,
+        None => new_mesh.insert(Arc::new(ctx.mesh_buffer.allocate(&ctx.gpu, 0, 0))),
+    };
+
+    let (index_count, glyphs) = mesh_generator.update_mesh(
+        &ctx.gpu,
+        &mut ctx.mesh_buffer,
+        text_system,
+        &mut item.state.buffer,
+        &item.state.decorations,
+        text_mesh,
+        store,
+        scale_factor,
+    );
+
+    cmd.set(
+        item.id,
+        draw_cmd(),
+        DrawCommand {
+            bind_group: mesh_generator.rasterizer.rasterized.bind_group.clone(),
+            shader: mesh_generator.shader.clone(),
+            mesh: text_mesh.clone(),
+            index_count,
+        },
+    );
+
+    cmd.set(item.id, text_glyphs(), glyphs);
+
+    if let Some(text_mesh) = new_mesh {
+        cmd.set(item.id, components::text_mesh(), text_mesh);
+    }
+}
@@ -11,7 +11,7 @@ use palette::Srgba;
 use parking_lot::Mutex;
 use puffin::profile_scope;
 use violet_core::{
-    components::{color, font_size, layout_glyphs, size_resolver, text, text_wrap},
+    components::{color, font_size, layout_glyphs, line_height, size_resolver, text, text_wrap},
     style::get_stylesheet_from_entity,
     text::{LayoutGlyphs, TextSegment},
 };
@@ -31,6 +31,7 @@ struct TextBufferQuery {
     state: ComponentMut<TextBufferState>,
     text: Component<Vec<TextSegment>>,
     font_size: Component<f32>,
+    line_height: OptOr<Component<f32>, f32>,
     wrap: OptOr<Component<Wrap>, Wrap>,
     color: Component<Srgba>,
 }
@@ -44,6 +45,7 @@ impl TextBufferQuery {
             text: text(),
             // rect: rect(),
             font_size: font_size(),
+            line_height: line_height().opt_or(1.0),
             wrap: text_wrap().opt_or(Wrap::Word),
             color: color(),
         }
@@ -69,6 +71,7 @@ pub(crate) fn update_text_buffers(text_system: Arc<Mutex<TextSystem>>) -> BoxedS
                         &mut text_system.font_system,
                         item.text,
                         *item.color,
+                        *item.line_height,
                     );
 
                     let buffer = &mut item.state.buffer;
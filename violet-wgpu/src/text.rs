@@ -1,19 +1,23 @@
-use std::sync::Arc;
+use std::{borrow::Cow, collections::HashMap, iter, ops::Range, sync::Arc};
 
 use cosmic_text::{
-    fontdb::{Database, Source},
-    Attrs, Buffer, FontSystem, LayoutGlyph, Metrics, Shaping, SwashCache,
+    fontdb::{self, Database, Query, Source},
+    Attrs, Buffer, FeatureTag, FontFeatures, FontSystem, LayoutGlyph, Metrics, Shaping, Style,
+    SwashCache, Weight,
 };
 use flax::EntityRef;
 use glam::{vec2, BVec2, Vec2};
 use itertools::Itertools;
 use palette::Srgba;
 use parking_lot::Mutex;
+use unicode_segmentation::UnicodeSegmentation;
 use violet_core::{
-    components::{font_size, layout_glyphs},
+    components::{font_size, layout_glyphs, text_max_lines, text_overflow},
     layout::{LayoutArgs, QueryArgs, SizeResolver, SizingHints},
-    style::ResolvableStyle,
-    text::{LayoutGlyphs, LayoutLineGlyphs, TextSegment},
+    style::{
+        font_fallback, get_stylesheet_from_entity, icon_ellipsis, ResolvableStyle, ValueOrRef,
+    },
+    text::{FontFamily, LayoutGlyphs, LayoutLineGlyphs, TextOverflow, TextSegment, UnderlineStyle},
     Rect,
 };
 
@@ -53,6 +57,13 @@ impl TextSystem {
             swash_cache: SwashCache::new(),
         }
     }
+
+    /// Adds a font after the text system has been created.
+    ///
+    /// Text needs to be laid out again to make use of the font.
+    pub fn load_font(&mut self, source: Source) {
+        self.font_system.db_mut().load_font_source(source);
+    }
 }
 
 impl Default for TextSystem {
@@ -74,6 +85,8 @@ impl SizeResolver for TextSizeResolver {
         puffin::profile_scope!("TextSizeResolver::query");
         let _span = tracing::debug_span!("TextSizeResolver::query", ?args.direction).entered();
 
+        let overflow = OverflowOptions::from_entity(entity);
+
         let query = (text_buffer_state().as_mut(), font_size());
 
         let mut query = entity.query(&query);
@@ -98,6 +111,7 @@ impl SizeResolver for TextSizeResolver {
             state,
             text_system,
             font_size,
+            &overflow,
             vec2(1.0, args.limits.layout_max_size.y.max(line_height)),
         );
 
@@ -105,6 +119,7 @@ impl SizeResolver for TextSizeResolver {
             state,
             text_system,
             font_size,
+            &overflow,
             args.limits.layout_max_size.max(vec2(10.0, line_height)),
         );
         // + vec2(5.0, 5.0);
@@ -132,6 +147,8 @@ impl SizeResolver for TextSizeResolver {
         puffin::profile_scope!("TextSizeResolver::apply");
         let _span = tracing::debug_span!("TextSizeResolver::apply", ?args).entered();
 
+        let overflow = OverflowOptions::from_entity(entity);
+
         let query = (text_buffer_state().as_mut(), font_size());
 
         let mut query = entity.query(&query);
@@ -144,6 +161,7 @@ impl SizeResolver for TextSizeResolver {
             state,
             text_system,
             font_size,
+            &overflow,
             // Add a little leeway, because an exact fit from the query may miss the last
             // word/glyph
             args.limits.layout_max_size.max(vec2(0.0, line_height)) + vec2(5.0, 5.0),
@@ -172,18 +190,23 @@ impl TextSizeResolver {
         state: &mut TextBufferState,
         text_system: &mut TextSystem,
         font_size: f32,
+        overflow: &OverflowOptions,
         layout_size_limit: Vec2,
     ) -> (Vec2, BVec2, usize) {
         // let _span = tracing::debug_span!("resolve_text_size", font_size, ?text, ?limits).entered();
 
+        let metrics = state.metrics(font_size);
         let mut buffer = state.buffer.borrow_with(&mut text_system.font_system);
 
-        let metrics = Metrics::new(font_size, font_size);
         buffer.set_metrics(metrics);
         buffer.set_size(Some(layout_size_limit.x), Some(layout_size_limit.y));
 
         buffer.shape_until_scroll(true);
 
+        if overflow.is_enabled() {
+            state.fit_text(&mut text_system.font_system, overflow, layout_size_limit);
+        }
+
         let (size, overflow, total_lines) = measure(&state.buffer);
 
         // Warn if overflow would have occurred with the original height
@@ -232,51 +255,265 @@ fn measure(buffer: &Buffer) -> (Vec2, BVec2, usize) {
     )
 }
 
+/// Options for shortening text which does not fit
+#[derive(Clone, PartialEq)]
+struct OverflowOptions {
+    overflow: TextOverflow,
+    max_lines: Option<usize>,
+    ellipsis: String,
+}
+
+impl OverflowOptions {
+    fn from_entity(entity: &EntityRef) -> Self {
+        let overflow = entity.get_copy(text_overflow()).unwrap_or_default();
+        let ellipsis = match overflow {
+            TextOverflow::Visible => String::new(),
+            _ => get_stylesheet_from_entity(entity)
+                .get_clone(icon_ellipsis())
+                .unwrap_or_else(|_| "…".to_string()),
+        };
+
+        Self {
+            overflow,
+            max_lines: entity.get_copy(text_max_lines()).ok(),
+            ellipsis,
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.overflow != TextOverflow::Visible || self.max_lines.is_some()
+    }
+}
+
+/// Number of fitted sizes remembered for each text
+const FIT_CACHE_SIZE: usize = 4;
+
+/// Graphemes kept for the text to fit within a size, `None` if the full text fits
+struct FittedText {
+    size: Vec2,
+    metrics: Metrics,
+    overflow: OverflowOptions,
+    keep: Option<usize>,
+}
+
 pub(crate) struct TextBufferState {
     pub(crate) buffer: Buffer,
+    /// The full text with resolved colors, which may be truncated in the buffer
+    text: Vec<TextSegment>,
+    /// Byte range of each grapheme cluster of the text, along with the index of its segment.
+    ///
+    /// Only collected once the text needs to be truncated.
+    graphemes: Option<Vec<(usize, Range<usize>)>>,
+    /// Number of graphemes kept in the buffer, if the text is truncated
+    truncated: Option<usize>,
+    /// Recently fitted sizes, as layout queries the same sizes repeatedly
+    fitted: Vec<FittedText>,
+    /// Decorations of each segment in the buffer
+    pub(crate) decorations: Vec<Decoration>,
+    /// Fonts used for characters missing from the font of a segment
+    fallback: Vec<FontFamily>,
+    /// Fonts resolved for the segments and fallback chain, as the text is set many times while
+    /// fitting it
+    font_ids: FontIds,
+    /// Distance between lines, in multiples of the font size
+    pub(crate) line_height: f32,
 }
 
 impl TextBufferState {
     pub(crate) fn new(font_system: &mut FontSystem) -> Self {
         Self {
             buffer: Buffer::new(font_system, Metrics::new(14.0, 14.0)),
+            text: Vec::new(),
+            graphemes: None,
+            truncated: None,
+            fitted: Vec::new(),
+            decorations: Vec::new(),
+            fallback: Vec::new(),
+            font_ids: FontIds::new(),
+            line_height: 1.0,
         }
     }
 
+    /// Returns the buffer metrics of text with the given font size
+    pub(crate) fn metrics(&self, font_size: f32) -> Metrics {
+        Metrics::new(font_size, font_size * self.line_height)
+    }
+
     pub(crate) fn update_text(
         &mut self,
         stylesheet: &EntityRef,
         font_system: &mut FontSystem,
         text: &[TextSegment],
         base_color: Srgba,
+        line_height: f32,
     ) {
         puffin::profile_function!();
-        self.buffer.set_rich_text(
-            font_system,
-            text.iter().map(|v| {
+        self.text = text
+            .iter()
+            .map(|v| {
                 let color = v
                     .color
                     .map(|v| v.resolve(*stylesheet))
-                    .unwrap_or(base_color)
-                    .into_format();
-                (
-                    &*v.text,
-                    Attrs::new()
-                        .family((&v.family).into())
-                        .style(v.style)
-                        .weight(v.weight)
-                        .color(cosmic_text::Color::rgba(
-                            color.red,
-                            color.green,
-                            color.blue,
-                            color.alpha,
-                        )),
-                )
-            }),
-            &Attrs::new(),
-            Shaping::Advanced,
-            None,
+                    .unwrap_or(base_color);
+                let resolve = |v: ValueOrRef<Srgba>| ValueOrRef::Value(v.resolve(*stylesheet));
+
+                TextSegment {
+                    color: Some(ValueOrRef::Value(color)),
+                    highlight: v.highlight.map(resolve),
+                    decoration_color: v.decoration_color.map(resolve),
+                    ..v.clone()
+                }
+            })
+            .collect();
+
+        self.fallback = stylesheet.get_clone(font_fallback()).unwrap_or_default();
+        self.line_height = line_height;
+        // Fonts may have been loaded since the text was last set
+        self.font_ids.clear();
+        self.decorations = set_segments(
+            &mut self.buffer,
+            font_system,
+            &mut self.font_ids,
+            &self.text,
+            &self.fallback,
+            line_height,
         );
+        self.graphemes = None;
+        self.truncated = None;
+        self.fitted.clear();
+    }
+
+    /// Shortens the text until it fits within `size`, according to the overflow options.
+    ///
+    /// The result is cached for each size, and reused until the text changes.
+    fn fit_text(&mut self, font_system: &mut FontSystem, overflow: &OverflowOptions, size: Vec2) {
+        puffin::profile_function!();
+
+        // Count all lines, rather than only those within the height of the buffer
+        self.buffer.set_size(font_system, Some(size.x), None);
+
+        let metrics = self.buffer.metrics();
+        let cached = self
+            .fitted
+            .iter()
+            .find(|v| v.size == size && v.metrics == metrics && v.overflow == *overflow)
+            .map(|v| v.keep);
+
+        match cached {
+            Some(keep) => self.show(font_system, overflow, keep),
+            None => {
+                let keep = self.find_fit(font_system, overflow, size);
+
+                if self.fitted.len() >= FIT_CACHE_SIZE {
+                    self.fitted.remove(0);
+                }
+
+                self.fitted.push(FittedText {
+                    size,
+                    metrics,
+                    overflow: overflow.clone(),
+                    keep,
+                });
+            }
+        }
+
+        self.buffer.shape_until_scroll(font_system, true);
+        self.buffer
+            .set_size(font_system, Some(size.x), Some(size.y));
+    }
+
+    /// Returns the number of graphemes which can be kept for the text to fit, or `None` if the
+    /// full text fits.
+    ///
+    /// Leaves the fitted text in the buffer.
+    fn find_fit(
+        &mut self,
+        font_system: &mut FontSystem,
+        overflow: &OverflowOptions,
+        size: Vec2,
+    ) -> Option<usize> {
+        let max_lines = overflow.max_lines.unwrap_or(usize::MAX);
+
+        let fits = |buffer: &mut Buffer, font_system: &mut FontSystem| {
+            buffer.shape_until_scroll(font_system, true);
+            let (extent, _, lines) = measure(buffer);
+
+            // Only shorten text which overflows horizontally when an ellipsis is requested, and
+            // only shorten text which overflows vertically when clamped
+            lines <= max_lines
+                && (overflow.overflow == TextOverflow::Visible
+                    || (extent.x <= size.x + 1.0 && extent.y <= size.y + 1.0))
+        };
+
+        self.show(font_system, overflow, None);
+        if fits(&mut self.buffer, font_system) {
+            return None;
+        }
+
+        let len = self
+            .graphemes
+            .get_or_insert_with(|| grapheme_ranges(&self.text))
+            .len();
+
+        // Binary search for the most graphemes which can be kept
+        let (mut lo, mut hi) = (0, len);
+        while lo < hi {
+            let mid = (lo + hi).div_ceil(2);
+            self.show(font_system, overflow, Some(mid));
+
+            if fits(&mut self.buffer, font_system) {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+
+        self.show(font_system, overflow, Some(lo));
+        Some(lo)
+    }
+
+    /// Sets the buffer to the text truncated to `keep` graphemes, or the full text
+    fn show(
+        &mut self,
+        font_system: &mut FontSystem,
+        overflow: &OverflowOptions,
+        keep: Option<usize>,
+    ) {
+        if self.truncated == keep {
+            return;
+        }
+
+        let text = match keep {
+            Some(keep) => {
+                let ellipsis = match overflow.overflow {
+                    TextOverflow::Visible => "",
+                    _ => &*overflow.ellipsis,
+                };
+
+                let graphemes = self
+                    .graphemes
+                    .get_or_insert_with(|| grapheme_ranges(&self.text));
+
+                Cow::Owned(truncate_segments(
+                    &self.text,
+                    graphemes,
+                    keep,
+                    overflow.overflow,
+                    ellipsis,
+                ))
+            }
+            None => Cow::Borrowed(&self.text[..]),
+        };
+
+        self.decorations = set_segments(
+            &mut self.buffer,
+            font_system,
+            &mut self.font_ids,
+            &text,
+            &self.fallback,
+            self.line_height,
+        );
+        self.truncated = keep;
     }
 
     pub(crate) fn to_layout_lines(&self) -> impl Iterator<Item = Vec<LayoutLineGlyphs>> + '_ {
@@ -306,7 +543,9 @@ impl TextBufferState {
                                 let index = glyph_index;
                                 glyph_index += 1;
 
-                                current_offset = glyph.end;
+                                // Glyphs are in visual order, which differs from the text order
+                                // for right-to-left text
+                                current_offset = current_offset.max(glyph.end);
 
                                 violet_core::text::LayoutGlyph {
                                     index,
@@ -316,6 +555,7 @@ impl TextBufferState {
                                         min: vec2(glyph.x, 0.0),
                                         max: vec2(glyph.x + glyph.w, lh),
                                     },
+                                    rtl: glyph.level.is_rtl(),
                                 }
                             })
                             .collect_vec();
@@ -343,3 +583,403 @@ impl TextBufferState {
         LayoutGlyphs::new(lines, self.buffer.metrics().line_height)
     }
 }
+
+/// Lines and background drawn for a segment of text, see [`TextSegment`]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Decoration {
+    pub(crate) underline: Option<UnderlineStyle>,
+    pub(crate) strikethrough: bool,
+    pub(crate) highlight: Option<Srgba>,
+    pub(crate) color: Srgba,
+}
+
+impl Decoration {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.underline.is_none() && !self.strikethrough && self.highlight.is_none()
+    }
+}
+
+/// Sets the text of the buffer, returning the decorations of each segment.
+///
+/// The glyph metadata refers to the index of the segment.
+fn set_segments(
+    buffer: &mut Buffer,
+    font_system: &mut FontSystem,
+    font_ids: &mut FontIds,
+    text: &[TextSegment],
+    fallback: &[FontFamily],
+    line_height: f32,
+) -> Vec<Decoration> {
+    let resolved = |v: Option<ValueOrRef<Srgba>>| match v {
+        Some(ValueOrRef::Value(color)) => Some(color),
+        _ => None,
+    };
+
+    let decorations = text
+        .iter()
+        .map(|v| Decoration {
+            underline: v.underline,
+            strikethrough: v.strikethrough,
+            highlight: resolved(v.highlight),
+            color: resolved(v.decoration_color)
+                .or(resolved(v.color))
+                .unwrap_or(Srgba::new(1.0, 1.0, 1.0, 1.0)),
+        })
+        .collect();
+
+    let spans = text
+        .iter()
+        .enumerate()
+        .flat_map(|(i, v)| {
+            let color = match v.color {
+                Some(ValueOrRef::Value(color)) => color,
+                _ => Srgba::new(1.0, 1.0, 1.0, 1.0),
+            };
+
+            let color: Srgba<u8> = color.into_format();
+            let attrs = Attrs::new()
+                .family((&v.family).into())
+                .style(v.style)
+                .weight(v.weight)
+                .metadata(i)
+                .color(cosmic_text::Color::rgba(
+                    color.red,
+                    color.green,
+                    color.blue,
+                    color.alpha,
+                ));
+
+            let attrs = match v.font_size {
+                Some(font_size) => attrs.metrics(Metrics::new(font_size, font_size * line_height)),
+                None => attrs,
+            };
+
+            let attrs = match v.letter_spacing {
+                Some(letter_spacing) => attrs.letter_spacing(letter_spacing),
+                None => attrs,
+            };
+
+            let attrs = if v.features.is_empty() {
+                attrs
+            } else {
+                let mut features = FontFeatures::new();
+                for feature in &v.features {
+                    features.set(FeatureTag::new(&feature.tag), feature.value);
+                }
+
+                attrs.font_features(features)
+            };
+
+            fallback_spans(font_system, font_ids, &v.text, attrs, &v.family, fallback)
+        })
+        .collect_vec();
+
+    buffer.set_rich_text(font_system, spans, &Attrs::new(), Shaping::Advanced, None);
+
+    decorations
+}
+
+/// Font of each family, weight and style, or `None` if there is no matching font
+type FontIds = HashMap<(FontFamily, Weight, Style), Option<fontdb::ID>>;
+
+/// Splits the text into spans using the first font of the segment or fallback chain which has a
+/// glyph for each grapheme cluster.
+fn fallback_spans<'a>(
+    font_system: &mut FontSystem,
+    font_ids: &mut FontIds,
+    text: &'a str,
+    attrs: Attrs<'a>,
+    family: &'a FontFamily,
+    fallback: &'a [FontFamily],
+) -> Vec<(&'a str, Attrs<'a>)> {
+    if fallback.is_empty() {
+        return vec![(text, attrs)];
+    }
+
+    let families = iter::once(family).chain(fallback).collect_vec();
+
+    let fonts = families
+        .iter()
+        .map(|&family| {
+            let id = *font_ids
+                .entry((family.clone(), attrs.weight, attrs.style))
+                .or_insert_with(|| {
+                    font_system.db().query(&Query {
+                        families: &[family.into()],
+                        weight: attrs.weight,
+                        stretch: attrs.stretch,
+                        style: attrs.style,
+                    })
+                });
+
+            font_system.get_font(id?)
+        })
+        .collect_vec();
+
+    let font_for = |c: char| {
+        fonts
+            .iter()
+            .position(|font| {
+                font.as_ref()
+                    .is_some_and(|font| font.as_swash().charmap().map(c) != 0)
+            })
+            .unwrap_or(0)
+    };
+
+    let mut spans = Vec::new();
+    let mut start = 0;
+    let mut current = None;
+    // Clusters such as emoji sequences and combining marks are kept in the same font, which is
+    // chosen for their base character
+    for (i, cluster) in text.grapheme_indices(true) {
+        let Some(c) = cluster.chars().find(|&c| !is_joiner(c)) else {
+            continue;
+        };
+
+        // Whitespace stays with the surrounding text
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let font = font_for(c);
+        if let Some(current) = current.filter(|&v| v != font) {
+            spans.push((
+                &text[start..i],
+                attrs.clone().family(families[current].into()),
+            ));
+            start = i;
+        }
+
+        current = Some(font);
+    }
+
+    let family = families[current.unwrap_or(0)];
+    spans.push((&text[start..], attrs.family(family.into())));
+    spans
+}
+
+/// Zero width characters which join or modify the characters around them
+fn is_joiner(c: char) -> bool {
+    matches!(c, '\u{200C}' | '\u{200D}' | '\u{FE00}'..='\u{FE0F}')
+}
+
+/// Returns the byte range of each grapheme cluster of the text, along with the index of its
+/// segment
+fn grapheme_ranges(segments: &[TextSegment]) -> Vec<(usize, Range<usize>)> {
+    segments
+        .iter()
+        .enumerate()
+        .flat_map(|(i, v)| {
+            v.text
+                .grapheme_indices(true)
+                .map(move |(start, g)| (i, start..start + g.len()))
+        })
+        .collect_vec()
+}
+
+/// Keeps `keep` of the grapheme clusters of the text, replacing the rest with an ellipsis.
+///
+/// Truncating by grapheme keeps emoji sequences and combining marks intact.
+fn truncate_segments(
+    segments: &[TextSegment],
+    graphemes: &[(usize, Range<usize>)],
+    keep: usize,
+    overflow: TextOverflow,
+    ellipsis: &str,
+) -> Vec<TextSegment> {
+    let removed = graphemes.len() - keep;
+    let cut = match overflow {
+        TextOverflow::Visible | TextOverflow::EllipsisEnd => keep,
+        TextOverflow::EllipsisMiddle => keep.div_ceil(2),
+        TextOverflow::EllipsisStart => 0,
+    };
+
+    let mut result: Vec<(usize, String)> = Vec::new();
+    let mut push = |segment: usize, s: &str| match result.last_mut() {
+        Some((last, text)) if *last == segment => text.push_str(s),
+        _ => result.push((segment, s.to_string())),
+    };
+
+    for (segment, range) in &graphemes[..cut] {
+        push(*segment, &segments[*segment].text[range.clone()]);
+    }
+
+    if removed > 0 && !ellipsis.is_empty() {
+        // The ellipsis takes on the style of the text it replaces
+        push(graphemes[cut].0, ellipsis);
+    }
+
+    for (segment, range) in &graphemes[cut + removed..] {
+        push(*segment, &segments[*segment].text[range.clone()]);
+    }
+
+    result
+        .into_iter()
+        .map(|(segment, text)| TextSegment {
+            text,
+            ..segments[segment].clone()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn describe(segments: &[TextSegment]) -> Vec<(&str, u16)> {
+        segments
+            .iter()
+            .map(|v| (v.text.as_str(), v.weight.0))
+            .collect()
+    }
+
+    fn truncate(segments: &[TextSegment], keep: usize, overflow: TextOverflow) -> Vec<TextSegment> {
+        let ellipsis = match overflow {
+            TextOverflow::Visible => "",
+            _ => "…",
+        };
+
+        truncate_segments(
+            segments,
+            &grapheme_ranges(segments),
+            keep,
+            overflow,
+            ellipsis,
+        )
+    }
+
+    /// Plain ascii, followed by bold text with a combining accent and an emoji with a skin tone
+    fn mixed() -> [TextSegment; 2] {
+        [
+            TextSegment::new("ab"),
+            TextSegment::new("e\u{301}👍🏽x").with_weight(Weight::BOLD),
+        ]
+    }
+
+    #[test]
+    fn graphemes() {
+        assert_eq!(
+            grapheme_ranges(&mixed()),
+            [(0, 0..1), (0, 1..2), (1, 0..3), (1, 3..11), (1, 11..12)]
+        );
+    }
+
+    #[test]
+    fn truncate_single_segment() {
+        let text = [TextSegment::new("Hello world")];
+
+        assert_eq!(
+            describe(&truncate(&text, 5, TextOverflow::EllipsisEnd)),
+            [("Hello…", 400)]
+        );
+        assert_eq!(
+            describe(&truncate(&text, 6, TextOverflow::EllipsisMiddle)),
+            [("Hel…rld", 400)]
+        );
+        assert_eq!(
+            describe(&truncate(&text, 4, TextOverflow::EllipsisStart)),
+            [("…orld", 400)]
+        );
+
+        // Lines past `max_lines` are cut without an ellipsis
+        assert_eq!(
+            describe(&truncate(&text, 5, TextOverflow::Visible)),
+            [("Hello", 400)]
+        );
+
+        // Nothing is removed
+        assert_eq!(
+            describe(&truncate(&text, 11, TextOverflow::EllipsisEnd)),
+            [("Hello world", 400)]
+        );
+    }
+
+    #[test]
+    fn truncate_segments_by_grapheme() {
+        let text = mixed();
+
+        // Combining sequences and emoji are kept whole, and the ellipsis takes on the style of
+        // the text it replaces
+        assert_eq!(
+            describe(&truncate(&text, 3, TextOverflow::EllipsisEnd)),
+            [("ab", 400), ("e\u{301}…", 700)]
+        );
+        assert_eq!(
+            describe(&truncate(&text, 4, TextOverflow::EllipsisEnd)),
+            [("ab", 400), ("e\u{301}👍🏽…", 700)]
+        );
+
+        // Cut at the boundary between segments
+        assert_eq!(
+            describe(&truncate(&text, 2, TextOverflow::EllipsisEnd)),
+            [("ab", 400), ("…", 700)]
+        );
+        assert_eq!(
+            describe(&truncate(&text, 2, TextOverflow::EllipsisMiddle)),
+            [("a…", 400), ("x", 700)]
+        );
+        assert_eq!(
+            describe(&truncate(&text, 2, TextOverflow::EllipsisStart)),
+            [("…", 400), ("👍🏽x", 700)]
+        );
+        assert_eq!(
+            describe(&truncate(&text, 3, TextOverflow::EllipsisStart)),
+            [("…", 400), ("e\u{301}👍🏽x", 700)]
+        );
+        assert_eq!(
+            describe(&truncate(&text, 0, TextOverflow::EllipsisEnd)),
+            [("…", 400)]
+        );
+    }
+
+    #[test]
+    fn fallback_fonts() {
+        let mut text_system =
+            TextSystem::new_with_fonts([Source::Binary(Arc::new(INTER_FONT.to_vec()))]);
+        let font_system = &mut text_system.font_system;
+        let mut font_ids = FontIds::new();
+
+        let missing = FontFamily::named("Missing");
+        let inter = FontFamily::named("Inter");
+        let fallback = [FontFamily::named("Also missing"), inter.clone()];
+
+        let mut spans = |text: &'static str| {
+            fallback_spans(
+                font_system,
+                &mut font_ids,
+                text,
+                Attrs::new().family("Missing"),
+                &missing,
+                &fallback,
+            )
+            .into_iter()
+            .map(|(text, attrs)| match attrs.family {
+                cosmic_text::Family::Name(name) => (text, name),
+                family => panic!("Unexpected family {family:?}"),
+            })
+            .collect_vec()
+        };
+
+        // Characters which the font of the segment lacks use the first fallback which has them,
+        // and characters which no font has stay with the font of the segment
+        assert_eq!(
+            spans("ab漢c"),
+            [("ab", "Inter"), ("漢", "Missing"), ("c", "Inter")]
+        );
+
+        // Combining marks and emoji sequences are not split, and whitespace stays with the
+        // preceding text
+        assert_eq!(
+            spans("ae\u{301}👍🏽 👨\u{200D}👩b"),
+            [
+                ("ae\u{301}", "Inter"),
+                ("👍🏽 👨\u{200D}👩", "Missing"),
+                ("b", "Inter")
+            ]
+        );
+
+        // Each font is only looked up once
+        assert_eq!(font_ids.len(), 3);
+        assert!(font_ids[&(inter, Weight::NORMAL, Style::Normal)].is_some());
+    }
+}